@@ -0,0 +1,63 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use similarity_checker_lib::{calculate_similarity, group_files, Algorithm};
+
+const ALGORITHMS: [(&str, Algorithm); 12] = [
+    ("levenshtein", Algorithm::Levenshtein),
+    ("jaro", Algorithm::Jaro),
+    ("hamming", Algorithm::Hamming),
+    ("token", Algorithm::Token),
+    ("token-sort", Algorithm::TokenSort),
+    ("substring", Algorithm::Substring),
+    ("dice", Algorithm::Dice),
+    ("phonetic", Algorithm::Phonetic),
+    ("lcs", Algorithm::Lcs),
+    ("partial", Algorithm::Partial),
+    ("auto", Algorithm::Auto),
+    ("weighted", Algorithm::Weighted),
+];
+
+/// Builds a deterministic list of file names with clusters of near-duplicates.
+fn synthetic_files(count: usize) -> Vec<String> {
+    let stems = ["report", "invoice", "holiday_photo", "meeting-notes", "IMG", "backup archive"];
+    let extensions = ["pdf", "jpg", "txt", "docx"];
+
+    (0..count)
+        .map(|i| {
+            let stem = stems[i % stems.len()];
+            let ext = extensions[(i / stems.len()) % extensions.len()];
+            format!("{}_{:04}_v{}.{}", stem, i / 3, i % 3, ext)
+        })
+        .collect()
+}
+
+fn bench_group_files(c: &mut Criterion) {
+    let mut group = c.benchmark_group("group_files");
+    for size in [10, 100, 500] {
+        let files = synthetic_files(size);
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &files, |b, files| {
+            b.iter(|| group_files(black_box(files.clone()), 70, &Algorithm::Auto, false, 2));
+        });
+    }
+    group.finish();
+}
+
+fn bench_single_pair(c: &mut Criterion) {
+    let mut group = c.benchmark_group("calculate_similarity");
+    for (name, algorithm) in ALGORITHMS.iter() {
+        group.bench_function(*name, |b| {
+            b.iter(|| {
+                calculate_similarity(
+                    black_box("report_2024_final_v2.pdf"),
+                    black_box("Report-2024-draft_v3.pdf"),
+                    algorithm,
+                    false,
+                )
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_group_files, bench_single_pair);
+criterion_main!(benches);
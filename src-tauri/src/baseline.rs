@@ -0,0 +1,208 @@
+use crate::cli::{Algorithm, Require};
+use crate::grouper::{build_result, compare_pair, Edge, GroupingResult};
+use crate::similarity::{Affixes, ComponentWeights};
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+/// Reads a result previously written with `--format json`.
+pub fn load_baseline(path: &Path) -> Result<GroupingResult> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read baseline: {}", path.display()))?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("Invalid baseline {}: expected a --format json result", path.display()))
+}
+
+/// Like [`group_files_with_algorithms`](crate::grouper::group_files_with_algorithms),
+/// but starts from `baseline`, the result of an earlier run over mostly the
+/// same files.
+///
+/// Files already in the baseline keep their group, or stay ungrouped, without
+/// being compared again. Files removed since drop out of their groups, which
+/// dissolve if left smaller than `min_group_size`. Only files new to the
+/// baseline are compared, against every other file, so they can join or
+/// bridge existing groups. Ungrouped files are only known when the baseline
+/// lists them; one written with `--show-ungrouped false` treats them as new.
+#[allow(clippy::too_many_arguments)]
+pub fn group_files_incremental(
+    baseline: &GroupingResult,
+    files: Vec<String>,
+    threshold: f64,
+    algorithms: &[Algorithm],
+    require: &Require,
+    weights: &ComponentWeights,
+    case_sensitive: bool,
+    calibrate: bool,
+    min_group_size: usize,
+    affixes: &Affixes,
+) -> Result<GroupingResult> {
+    if (baseline.summary.threshold_used - threshold).abs() > 1e-9 {
+        anyhow::bail!(
+            "Baseline was grouped at a threshold of {} but this run uses {}; rerun without --baseline",
+            baseline.summary.threshold_used,
+            threshold
+        );
+    }
+
+    let index: HashMap<&str, usize> = files.iter().enumerate().map(|(i, file)| (file.as_str(), i)).collect();
+    let known: HashSet<&str> = baseline
+        .groups
+        .iter()
+        .flat_map(|group| &group.files)
+        .chain(&baseline.ungrouped)
+        .map(String::as_str)
+        .collect();
+    let is_new: Vec<bool> = files.iter().map(|file| !known.contains(file.as_str())).collect();
+    let _span = tracing::info_span!(
+        "group_files_incremental",
+        files = files.len(),
+        new = is_new.iter().filter(|&&new| new).count(),
+        threshold
+    )
+    .entered();
+
+    // The remaining members of each baseline group stay connected through
+    // edges carrying the group's similarity
+    let mut edges: Vec<Edge> = Vec::new();
+    for group in &baseline.groups {
+        let members: Vec<usize> = group.files.iter().filter_map(|file| index.get(file.as_str()).copied()).collect();
+        edges.extend(members.windows(2).map(|pair| (pair[0], pair[1], group.similarity)));
+    }
+
+    let names: Vec<_> = files.iter().map(|file| affixes.strip(file)).collect();
+    for i in (0..files.len()).filter(|&i| is_new[i]) {
+        // A pair of new files is compared once, from the earlier of the two
+        for j in (0..files.len()).filter(|&j| j != i && !(is_new[j] && j < i)) {
+            edges.extend(compare_pair(&names, i, j, threshold, algorithms, require, weights, case_sensitive, calibrate, None));
+        }
+    }
+
+    let mut result = build_result(files, &edges, threshold, min_group_size);
+
+    // Groups nothing was added to or removed from keep their baseline scores
+    let unchanged: HashMap<Vec<&str>, _> = baseline.groups.iter().map(|group| (sorted_files(&group.files), group)).collect();
+    for group in &mut result.groups {
+        if let Some(previous) = unchanged.get(&sorted_files(&group.files)) {
+            group.similarity = previous.similarity;
+            group.min_similarity = previous.min_similarity;
+            group.max_similarity = previous.max_similarity;
+            group.match_reason = previous.match_reason;
+            group.label = previous.label.clone();
+        }
+    }
+    Ok(result)
+}
+
+fn sorted_files(files: &[String]) -> Vec<&str> {
+    let mut sorted: Vec<&str> = files.iter().map(String::as_str).collect();
+    sorted.sort_unstable();
+    sorted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grouper::group_files_with_algorithms;
+    use tempfile::TempDir;
+
+    fn strings(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn incremental(baseline: &GroupingResult, files: &[&str]) -> Result<GroupingResult> {
+        group_files_incremental(
+            baseline,
+            strings(files),
+            0.8,
+            &[Algorithm::Levenshtein],
+            &Require::All,
+            &ComponentWeights::default(),
+            false,
+            false,
+            2,
+            &Affixes::default(),
+        )
+    }
+
+    #[test]
+    #[tracing_test::traced_test]
+    fn test_only_new_files_are_compared() {
+        let baseline = group_files_with_algorithms(
+            strings(&["report_v1.pdf", "report_v2.pdf", "holiday.jpg"]),
+            0.8,
+            &[Algorithm::Levenshtein],
+            &Require::All,
+            &ComponentWeights::default(),
+            false,
+            false,
+            2,
+            &Affixes::default(),
+        );
+
+        let result = incremental(&baseline, &["report_v1.pdf", "report_v2.pdf", "holiday.jpg", "report_v3.pdf"]).unwrap();
+        assert_eq!(result.groups.len(), 1);
+        assert_eq!(result.groups[0].files, vec!["report_v1.pdf", "report_v2.pdf", "report_v3.pdf"]);
+        assert_eq!(result.ungrouped, vec!["holiday.jpg"]);
+        assert_eq!(result.summary.total_files, 4);
+
+        // Only report_v3.pdf against each of the three baseline files
+        logs_assert(|lines: &[&str]| {
+            let compared: Vec<&&str> = lines
+                .iter()
+                .filter(|line| line.contains("group_files_incremental") && line.contains("compared pair"))
+                .collect();
+            match compared.len() {
+                3 if compared.iter().all(|line| line.contains("report_v3.pdf")) => Ok(()),
+                n => Err(format!("expected 3 comparisons with report_v3.pdf, got {}", n)),
+            }
+        });
+    }
+
+    #[test]
+    fn test_baseline_assignments_are_kept() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("baseline.json");
+        // Groups no name algorithm would form, so they can only come from the baseline
+        fs::write(
+            &path,
+            r#"{
+                "groups": [
+                    {"id": 1, "files": ["alpha.txt", "omega.txt"], "similarity": 0.9,
+                     "min_similarity": 0.9, "max_similarity": 0.9, "label": "kept"},
+                    {"id": 2, "files": ["cat.png", "dog.png", "emu.png"], "similarity": 0.85},
+                    {"id": 3, "files": ["red.doc", "blue.doc"], "similarity": 0.95}
+                ],
+                "ungrouped": ["zebra.csv"],
+                "summary": {"total_files": 8, "groups_found": 3, "ungrouped_files": 1, "threshold_used": 0.8}
+            }"#,
+        )
+        .unwrap();
+        let baseline = load_baseline(&path).unwrap();
+
+        // emu.png and blue.doc were deleted since
+        let result = incremental(&baseline, &["alpha.txt", "omega.txt", "cat.png", "dog.png", "red.doc", "zebra.csv"]).unwrap();
+        let groups: Vec<&Vec<String>> = result.groups.iter().map(|group| &group.files).collect();
+        assert_eq!(groups, vec![&strings(&["alpha.txt", "omega.txt"]), &strings(&["cat.png", "dog.png"])]);
+        assert_eq!(result.groups[0].label.as_deref(), Some("kept"));
+        assert!((result.groups[1].similarity - 0.85).abs() < 1e-9);
+        assert_eq!(result.ungrouped, vec!["red.doc", "zebra.csv"]);
+    }
+
+    #[test]
+    fn test_baseline_threshold_must_match() {
+        let baseline = group_files_with_algorithms(
+            strings(&["report_v1.pdf", "report_v2.pdf"]),
+            0.9,
+            &[Algorithm::Levenshtein],
+            &Require::All,
+            &ComponentWeights::default(),
+            false,
+            false,
+            2,
+            &Affixes::default(),
+        );
+        let error = incremental(&baseline, &["report_v1.pdf"]).unwrap_err().to_string();
+        assert!(error.contains("threshold of 0.9"), "{}", error);
+    }
+}
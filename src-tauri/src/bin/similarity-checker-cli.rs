@@ -0,0 +1,479 @@
+use anyhow::{Context, Result};
+use console::style;
+use indicatif::{ProgressBar, ProgressStyle};
+use similarity_checker_lib::baseline::{group_files_incremental, load_baseline};
+use similarity_checker_lib::checkpoint::group_files_checkpointed;
+use similarity_checker_lib::cli::{Algorithm, Args, Command, GroupBy, OutputFormat, TreeCompareArgs};
+use similarity_checker_lib::config::parse_args;
+use similarity_checker_lib::explain::{explain_groups, format_explained_json};
+use similarity_checker_lib::grouper::{
+    add_identical_groups, apply_ignore_groups, apply_report_threshold, find_similar_to, flag_case_only_groups,
+    group_files_prefiltered, group_files_with_algorithms, group_identical_content, group_singletons, sort_groups,
+    sweep_thresholds, GroupingResult, SWEEP_THRESHOLDS,
+};
+use similarity_checker_lib::hierarchy::{format_hierarchy, group_hierarchically, validate_threshold_levels};
+use similarity_checker_lib::input::{
+    check_zero_threshold, collect_files, describe_skipped, parse_since, read_ignore_groups, validate_min_group_size, validate_size_ratio,
+    validate_threshold, validate_threshold_float, FileDiscovery,
+};
+use similarity_checker_lib::interactive::select_files_to_trash;
+use similarity_checker_lib::output::{
+    enrich_paths, format_comment_header, format_enriched_json, format_matrix, format_reference, format_schema, format_summary, format_sweep,
+    format_tokens, write_pages, write_split, MATRIX_WARN_FILES,
+};
+use similarity_checker_lib::rename::{format_rename_preview, propose_renames};
+use similarity_checker_lib::scheme::{group_by_naming_scheme, naming_schemes};
+use similarity_checker_lib::similarity::Affixes;
+use similarity_checker_lib::tree::{compare_trees, format_tree_comparison};
+use similarity_checker_lib::DeleteResult;
+use std::fs::{self, File, OpenOptions};
+use std::io::{IsTerminal, Write};
+use std::time::Duration;
+
+/// Exit status when --fail-on-duplicates finds groups; errors exit with 1.
+const EXIT_DUPLICATES: i32 = 2;
+
+/// Returned by [`run`] after the report is written when
+/// --fail-on-duplicates applies.
+#[derive(Debug)]
+struct DuplicatesFound(usize);
+
+impl std::fmt::Display for DuplicatesFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Found {} group(s) of similar files (--fail-on-duplicates)", self.0)
+    }
+}
+
+impl std::error::Error for DuplicatesFound {}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("{} {:#}", style("Error:").red().bold(), e);
+        let code = if e.is::<DuplicatesFound>() { EXIT_DUPLICATES } else { 1 };
+        std::process::exit(code);
+    }
+}
+
+fn run() -> Result<()> {
+    let args = parse_args()?;
+    init_logging(args.verbose);
+    if let Some(Command::TreeCompare(tree_args)) = &args.command {
+        return run_tree_compare(&args, tree_args);
+    }
+    if args.emit_schema {
+        let mut buffer = Vec::new();
+        format_schema(&mut buffer)?;
+        return write_output(&args, &String::from_utf8(buffer)?);
+    }
+
+    validate_threshold_levels(&args.thresholds, args.hierarchical)?;
+    for &threshold in &args.thresholds {
+        validate_threshold(threshold)?;
+        if let Some(warning) = check_zero_threshold(threshold, args.allow_zero_threshold)? {
+            warn(&args, warning);
+        }
+    }
+    let threshold = match args.threshold_float {
+        Some(threshold) => {
+            validate_threshold_float(threshold)?;
+            if threshold == 0.0 {
+                if let Some(warning) = check_zero_threshold(0, args.allow_zero_threshold)? {
+                    warn(&args, warning);
+                }
+            }
+            threshold
+        }
+        None => args.threshold() as f64 / 100.0,
+    };
+    validate_min_group_size(args.min_group_size)?;
+    if let Some(report_threshold) = args.report_threshold {
+        validate_threshold(report_threshold)?;
+    }
+    if args.interactive {
+        if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+            anyhow::bail!("--interactive requires a terminal on stdin and stdout");
+        }
+        if args.discover.is_some() && !args.full_paths {
+            anyhow::bail!("--interactive with --discover requires --full-paths, so chosen files can be found");
+        }
+    }
+    if let Some(ratio) = args.size_prefilter {
+        validate_size_ratio(ratio)?;
+    }
+    if args.detect_identical && args.discover.is_some() && !args.full_paths {
+        anyhow::bail!("--detect-identical with --discover requires --full-paths, so files can be read");
+    }
+    if args.explain && !matches!(args.format, OutputFormat::Json) {
+        anyhow::bail!("--explain requires --format json");
+    }
+    if args.csv_comment_header && !matches!(args.format, OutputFormat::Csv | OutputFormat::Tsv) {
+        anyhow::bail!("--csv-comment-header requires --format csv or tsv");
+    }
+    if args.enrich && !matches!(args.format, OutputFormat::Json) {
+        anyhow::bail!("--enrich requires --format json");
+    }
+    let since = args.since.as_deref().map(parse_since).transpose()?;
+    let affixes = Affixes::new(args.strip_prefix.as_deref(), args.strip_suffix.as_deref())?
+        .strip_copy_suffixes(args.strip_copy_suffixes)
+        .normalize_unicode(args.normalize_unicode, args.case_sensitive)
+        .compare(args.compare_mode());
+
+    let collected = collect_files(
+        args.files.clone(),
+        args.input_file.clone(),
+        args.discover.clone(),
+        &args.include,
+        &args.exclude,
+        args.follow_symlinks,
+        since,
+        args.full_paths,
+        args.no_dedup,
+        args.preserve_order,
+    )?;
+    for message in collected.warnings.iter().cloned().chain(describe_skipped(&collected.skipped)) {
+        warn(&args, message);
+    }
+    let files = collected.files;
+
+    if files.len() < args.min_group_size {
+        warn(
+            &args,
+            format!(
+                "Only {} file(s) provided, fewer than the minimum group size of {}",
+                files.len(),
+                args.min_group_size
+            ),
+        );
+    }
+
+    let algorithms = if args.algorithms.is_empty() {
+        vec![args.algorithm.clone()]
+    } else {
+        args.algorithms.clone()
+    };
+    if args.weights.is_some() && !algorithms.contains(&Algorithm::Weighted) {
+        anyhow::bail!("--weights requires --algorithm weighted");
+    }
+    let weights = args.weights.clone().unwrap_or_default();
+    let baseline = args.baseline.as_deref().map(load_baseline).transpose()?;
+
+    if args.dump_tokens {
+        let mut buffer = Vec::new();
+        format_tokens(&files, args.case_sensitive, &affixes, &args.format, &mut buffer)?;
+        return write_output(&args, &String::from_utf8(buffer)?);
+    }
+
+    if args.matrix {
+        if files.len() > MATRIX_WARN_FILES {
+            warn(
+                &args,
+                format!(
+                    "--matrix scores all {} pairs of {} files; this may take a while and produce a very large table",
+                    files.len() * (files.len() - 1) / 2,
+                    files.len()
+                ),
+            );
+        }
+        let mut buffer = Vec::new();
+        format_matrix(&files, &algorithms, &args.require, &weights, args.case_sensitive, args.calibrate, &affixes, &mut buffer)?;
+        return write_output(&args, &String::from_utf8(buffer)?);
+    }
+
+    if args.sweep {
+        let rows = sweep_thresholds(
+            files,
+            &SWEEP_THRESHOLDS,
+            &algorithms,
+            &args.require,
+            &weights,
+            args.case_sensitive,
+            args.calibrate,
+            args.min_group_size,
+            &affixes,
+        );
+        let mut buffer = Vec::new();
+        format_sweep(&rows, &args.format, &mut buffer)?;
+        return write_output(&args, &String::from_utf8(buffer)?);
+    }
+
+    if let Some(reference) = &args.reference {
+        let matches = find_similar_to(
+            reference,
+            &files,
+            threshold,
+            &algorithms,
+            &args.require,
+            &weights,
+            args.case_sensitive,
+            args.calibrate,
+            &affixes,
+        );
+        let mut buffer = Vec::new();
+        format_reference(reference, threshold, &matches, &args.format, &mut buffer)?;
+        return write_output(&args, &String::from_utf8(buffer)?);
+    }
+
+    // Show a spinner for large datasets
+    let progress = if files.len() > 100 && !args.quiet {
+        let pb = ProgressBar::new_spinner();
+        pb.set_style(ProgressStyle::default_spinner().template("{spinner:.green} {msg}")?);
+        pb.set_message(format!("Comparing {} files...", files.len()));
+        pb.enable_steady_tick(Duration::from_millis(100));
+        Some(pb)
+    } else {
+        None
+    };
+
+    if args.hierarchical {
+        let hierarchy = group_hierarchically(
+            files,
+            &args.thresholds,
+            &algorithms,
+            &args.require,
+            &weights,
+            args.case_sensitive,
+            args.calibrate,
+            args.min_group_size,
+            &affixes,
+        );
+        if let Some(pb) = progress {
+            pb.finish_and_clear();
+        }
+        let mut buffer = Vec::new();
+        format_hierarchy(&hierarchy, &args.format, args.show_ungrouped, &mut buffer)?;
+        return write_output(&args, &String::from_utf8(buffer)?);
+    }
+
+    let (identical, files) = if args.detect_identical {
+        let content = group_identical_content(files, args.min_group_size);
+        for (file, reason) in &content.unhashed {
+            warn(&args, format!("Failed to hash {}, comparing its name only: {}", file, reason));
+        }
+        (content.groups, content.remaining)
+    } else {
+        (Vec::new(), files)
+    };
+    let mut result = match args.group_by {
+        GroupBy::Similarity => match (&baseline, &args.checkpoint) {
+            (None, None) if args.size_prefilter.is_some() => {
+                let sizes: Vec<Option<u64>> = files.iter().map(|file| fs::metadata(file).ok().map(|m| m.len())).collect();
+                group_files_prefiltered(
+                    files,
+                    &sizes,
+                    args.size_prefilter.unwrap_or_default(),
+                    threshold,
+                    &algorithms,
+                    &args.require,
+                    &weights,
+                    args.case_sensitive,
+                    args.calibrate,
+                    args.min_group_size,
+                    &affixes,
+                )
+            }
+            (Some(baseline), _) => group_files_incremental(
+                baseline,
+                files,
+                threshold,
+                &algorithms,
+                &args.require,
+                &weights,
+                args.case_sensitive,
+                args.calibrate,
+                args.min_group_size,
+                &affixes,
+            )?,
+            (None, Some(checkpoint)) => group_files_checkpointed(
+                files,
+                threshold,
+                &algorithms,
+                &args.require,
+                &weights,
+                args.case_sensitive,
+                args.calibrate,
+                args.min_group_size,
+                &affixes,
+                checkpoint,
+                &mut |message| warn(&args, message),
+            )?,
+            (None, None) => group_files_with_algorithms(
+                files,
+                threshold,
+                &algorithms,
+                &args.require,
+                &weights,
+                args.case_sensitive,
+                args.calibrate,
+                args.min_group_size,
+                &affixes,
+            ),
+        },
+        GroupBy::NamingScheme => {
+            let schemes = naming_schemes(&args.schemes)?;
+            group_by_naming_scheme(files, &schemes, args.min_group_size)
+        }
+    };
+
+    if let Some(pb) = progress {
+        pb.finish_and_clear();
+    }
+    add_identical_groups(&mut result, identical);
+
+    if let Some(report_threshold) = args.report_threshold {
+        apply_report_threshold(&mut result, report_threshold as f64 / 100.0);
+    }
+    if let Some(path) = &args.ignore_groups {
+        apply_ignore_groups(&mut result, &read_ignore_groups(path)?);
+    }
+    sort_groups(&mut result, &args.sort);
+    if args.group_singletons {
+        group_singletons(&mut result);
+    }
+
+    if args.flag_case_only {
+        flag_case_only_groups(&mut result);
+    }
+
+    // Groups of one from --group-singletons are not duplicates
+    let duplicate_groups = result.groups.iter().filter(|group| group.files.len() > 1).count();
+    if let Some(limit) = args.limit {
+        if result.groups.len() > limit {
+            warn(&args, format!("Showing the first {} of {} groups (--limit)", limit, result.groups.len()));
+            result.groups.truncate(limit);
+        }
+    }
+
+    if let (Some(dir), Some(page_size)) = (&args.output_dir, args.page_size) {
+        let index = write_pages(&result, &args.format, args.show_ungrouped, page_size, dir)?;
+        if !args.quiet {
+            eprintln!("Results written to {}", index.display());
+        }
+    } else if let Some(dir) = &args.split_output {
+        write_split(&result, &args.format, args.show_ungrouped, dir, args.force)?;
+        if !args.quiet {
+            eprintln!("Results written to {}", dir.display());
+        }
+    } else {
+        let output = if args.summary_only {
+            let mut buffer = Vec::new();
+            format_summary(&result.summary, &args.format, &mut buffer)?;
+            String::from_utf8(buffer)?
+        } else if args.explain {
+            let explanations = explain_groups(&result, &algorithms, &args.require, &weights, args.case_sensitive, args.calibrate, &affixes);
+            let mut buffer = Vec::new();
+            format_explained_json(&result, &explanations, &mut buffer, args.show_ungrouped)?;
+            String::from_utf8(buffer)?
+        } else if let (true, Some(dir)) = (args.enrich, &args.discover) {
+            let discovery = FileDiscovery::with_filters(&args.include, &args.exclude)?
+                .follow_symlinks(args.follow_symlinks)
+                .modified_since(since);
+            // Anything unreadable was already reported while collecting files
+            let metadata = enrich_paths(&discovery.discover(dir)?.paths, args.full_paths);
+            let mut buffer = Vec::new();
+            format_enriched_json(&result, &metadata, &mut buffer, args.show_ungrouped)?;
+            String::from_utf8(buffer)?
+        } else if args.rename_preview {
+            let proposals = propose_renames(&result, &args.rename_policy);
+            let mut buffer = Vec::new();
+            format_rename_preview(&proposals, &args.format, &mut buffer)?;
+            String::from_utf8(buffer)?
+        } else {
+            args.format.format(&result, args.show_ungrouped)?
+        };
+        let output = if args.csv_comment_header {
+            let mut buffer = Vec::new();
+            format_comment_header(threshold, &algorithms, chrono::Utc::now(), &mut buffer)?;
+            String::from_utf8(buffer)? + &output
+        } else {
+            output
+        };
+        write_output(&args, &output)?;
+    }
+
+    if args.interactive {
+        trash_interactively(&result)?;
+    }
+    if args.fail_on_duplicates && duplicate_groups as u64 >= args.fail_threshold {
+        return Err(DuplicatesFound(duplicate_groups).into());
+    }
+    Ok(())
+}
+
+fn run_tree_compare(args: &Args, tree_args: &TreeCompareArgs) -> Result<()> {
+    let discovery = FileDiscovery::with_filters(&args.include, &args.exclude)?.follow_symlinks(args.follow_symlinks);
+    let comparison = compare_trees(&tree_args.dir_a, &tree_args.dir_b, &discovery, tree_args.content)?;
+    let mut buffer = Vec::new();
+    format_tree_comparison(&comparison, &args.format, &mut buffer)?;
+    write_output(args, &String::from_utf8(buffer)?)
+}
+
+/// Asks which grouped files to move to the trash and trashes the confirmed ones.
+fn trash_interactively(result: &GroupingResult) -> Result<()> {
+    let files = select_files_to_trash(&result.groups, &mut std::io::stdin().lock(), &mut std::io::stdout())?;
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    let trashed = DeleteResult::from_paths(files, |path| trash::delete(path).map_err(|e| e.to_string()));
+    println!("Moved {} file(s) to the trash", trashed.deleted.len());
+    for (path, error) in &trashed.failed {
+        eprintln!("{} Could not move {} to the trash: {}", style("Error:").red().bold(), path, error);
+    }
+    if !trashed.failed.is_empty() {
+        anyhow::bail!("{} file(s) could not be moved to the trash", trashed.failed.len());
+    }
+    Ok(())
+}
+
+/// Sends tracing events to stderr at the level chosen by --verbose. Without
+/// -v no subscriber is installed, so events cost only a disabled check.
+fn init_logging(verbose: u8) {
+    let level = match verbose {
+        0 => return,
+        1 => tracing::Level::INFO,
+        2 => tracing::Level::DEBUG,
+        _ => tracing::Level::TRACE,
+    };
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(std::io::stderr)
+        .with_ansi(false)
+        .init();
+}
+
+/// Prints a warning to stderr unless --quiet is set.
+fn warn(args: &Args, message: impl std::fmt::Display) {
+    if !args.quiet {
+        eprintln!("{} {}", style("Warning:").yellow().bold(), message);
+    }
+}
+
+fn write_output(args: &Args, output: &str) -> Result<()> {
+    match &args.output {
+        Some(path) if args.append => {
+            if matches!(args.format, OutputFormat::Json | OutputFormat::Html) {
+                warn(args, format!("--append with --format {} leaves {} holding several documents, which most readers reject", args.format.extension(), path.display()));
+            }
+            let mut file = OpenOptions::new()
+                .append(true)
+                .create(true)
+                .open(path)
+                .with_context(|| format!("Failed to open output file: {}", path.display()))?;
+            file.write_all(output.as_bytes())?;
+            if !args.quiet {
+                eprintln!("Results appended to {}", path.display());
+            }
+        }
+        Some(path) => {
+            let mut file = File::create(path)
+                .with_context(|| format!("Failed to create output file: {}", path.display()))?;
+            file.write_all(output.as_bytes())?;
+            if !args.quiet {
+                eprintln!("Results written to {}", path.display());
+            }
+        }
+        None => print!("{}", output),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,249 @@
+use crate::cli::{Algorithm, Require};
+use crate::grouper::{build_result, scan_row, Edge, GroupingResult};
+use crate::similarity::{Affixes, ComponentWeights};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+/// Rows compared between checkpoint writes.
+const SAVE_INTERVAL: usize = 50;
+
+/// Saved state of an all-pairs comparison: rows `0..next_row` have been
+/// compared against every later file and produced `edges`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Checkpoint {
+    fingerprint: String,
+    next_row: usize,
+    edges: Vec<Edge>,
+}
+
+impl Checkpoint {
+    /// Loads the checkpoint at `path` if it belongs to the same run, and
+    /// starts fresh otherwise, passing the reason to `on_warning`.
+    fn load(path: &Path, fingerprint: &str, on_warning: &mut dyn FnMut(String)) -> Result<Self> {
+        let fresh = || Self {
+            fingerprint: fingerprint.to_string(),
+            ..Self::default()
+        };
+
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(fresh()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read checkpoint: {}", path.display()))
+            }
+        };
+
+        match serde_json::from_str::<Checkpoint>(&content) {
+            Ok(checkpoint) if checkpoint.fingerprint == fingerprint => Ok(checkpoint),
+            Ok(_) => {
+                on_warning(format!("Checkpoint {} is from a different run; starting over", path.display()));
+                Ok(fresh())
+            }
+            Err(e) => {
+                on_warning(format!("Ignoring unreadable checkpoint {}: {}", path.display(), e));
+                Ok(fresh())
+            }
+        }
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        // Write then rename so an interruption never leaves a truncated checkpoint
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, serde_json::to_string(self)?)
+            .with_context(|| format!("Failed to write checkpoint: {}", temp_path.display()))?;
+        fs::rename(&temp_path, path)
+            .with_context(|| format!("Failed to write checkpoint: {}", path.display()))?;
+        Ok(())
+    }
+}
+
+/// Identifies a run by its inputs, so a checkpoint is only resumed by the
+/// same files and comparison settings, including how names are stripped
+/// before they are scored.
+#[allow(clippy::too_many_arguments)]
+fn fingerprint(
+    files: &[String],
+    threshold: f64,
+    algorithms: &[Algorithm],
+    require: &Require,
+    weights: &ComponentWeights,
+    case_sensitive: bool,
+    calibrate: bool,
+    affixes: &Affixes,
+) -> String {
+    let mut hasher = Sha256::new();
+    for file in files {
+        hasher.update(file.as_bytes());
+        hasher.update([0]);
+    }
+    let names: Vec<String> = algorithms
+        .iter()
+        .filter_map(|algorithm| algorithm.to_possible_value())
+        .map(|value| value.get_name().to_string())
+        .collect();
+    hasher.update(format!("{}|{}|{:?}|{}", threshold, names.join(","), require, case_sensitive));
+    // Only weighted runs depend on the weights; leaving them out otherwise
+    // keeps existing checkpoints resumable
+    if algorithms.contains(&Algorithm::Weighted) {
+        hasher.update(format!("|{}", weights));
+    }
+    if calibrate {
+        hasher.update("|calibrated");
+    }
+    hasher.update(format!("|{}", affixes.fingerprint()));
+    hex::encode(hasher.finalize())
+}
+
+/// Like [`group_files_with_algorithms`](crate::grouper::group_files_with_algorithms),
+/// but periodically saves progress to `checkpoint_path` and resumes from it
+/// when the file belongs to the same run. A checkpoint that is not resumed
+/// is reported to `on_warning`.
+#[allow(clippy::too_many_arguments)]
+pub fn group_files_checkpointed(
+    files: Vec<String>,
+    threshold: f64,
+    algorithms: &[Algorithm],
+    require: &Require,
+    weights: &ComponentWeights,
+    case_sensitive: bool,
+    calibrate: bool,
+    min_group_size: usize,
+    affixes: &Affixes,
+    checkpoint_path: &Path,
+    on_warning: &mut dyn FnMut(String),
+) -> Result<GroupingResult> {
+    let result = run(
+        files,
+        threshold,
+        algorithms,
+        require,
+        weights,
+        case_sensitive,
+        calibrate,
+        min_group_size,
+        affixes,
+        checkpoint_path,
+        usize::MAX,
+        on_warning,
+    )?;
+    Ok(result.expect("an unlimited run always completes"))
+}
+
+/// Compares at most `row_limit` further rows, returning `None` if the scan
+/// stopped before finishing.
+#[allow(clippy::too_many_arguments)]
+fn run(
+    files: Vec<String>,
+    threshold: f64,
+    algorithms: &[Algorithm],
+    require: &Require,
+    weights: &ComponentWeights,
+    case_sensitive: bool,
+    calibrate: bool,
+    min_group_size: usize,
+    affixes: &Affixes,
+    checkpoint_path: &Path,
+    row_limit: usize,
+    on_warning: &mut dyn FnMut(String),
+) -> Result<Option<GroupingResult>> {
+    let fingerprint = fingerprint(&files, threshold, algorithms, require, weights, case_sensitive, calibrate, affixes);
+    let mut checkpoint = Checkpoint::load(checkpoint_path, &fingerprint, on_warning)?;
+    let names: Vec<_> = files.iter().map(|file| affixes.strip(file)).collect();
+
+    let mut rows_done = 0;
+    while checkpoint.next_row < files.len() {
+        if rows_done == row_limit {
+            checkpoint.save(checkpoint_path)?;
+            return Ok(None);
+        }
+        let row = checkpoint.next_row;
+        checkpoint.edges.extend(scan_row(&names, row, threshold, algorithms, require, weights, case_sensitive, calibrate, None));
+        checkpoint.next_row += 1;
+        rows_done += 1;
+        if checkpoint.next_row % SAVE_INTERVAL == 0 {
+            checkpoint.save(checkpoint_path)?;
+        }
+    }
+    checkpoint.save(checkpoint_path)?;
+
+    Ok(Some(build_result(files, &checkpoint.edges, threshold, min_group_size)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grouper::group_files_with_algorithms;
+    use tempfile::TempDir;
+
+    fn sample_files() -> Vec<String> {
+        (0..120)
+            .map(|i| format!("{}_report_v{}.pdf", ["alpha", "beta", "gamma"][i % 3], i / 3))
+            .collect()
+    }
+
+    fn summarize(result: &GroupingResult) -> (Vec<Vec<String>>, Vec<String>) {
+        (result.groups.iter().map(|g| g.files.clone()).collect(), result.ungrouped.clone())
+    }
+
+    #[test]
+    fn test_resume_matches_uninterrupted_run() {
+        let temp_dir = TempDir::new().unwrap();
+        let checkpoint_path = temp_dir.path().join("run.checkpoint");
+        let algorithms = [Algorithm::Levenshtein];
+        let affixes = Affixes::default();
+
+        let expected = group_files_with_algorithms(sample_files(), 0.85, &algorithms, &Require::All, &ComponentWeights::default(), false, false, 2, &affixes);
+
+        // Simulate an interruption part-way through the scan
+        let partial = run(sample_files(), 0.85, &algorithms, &Require::All, &ComponentWeights::default(), false, false, 2, &affixes, &checkpoint_path, 70, &mut |_| {}).unwrap();
+        assert!(partial.is_none());
+        let saved: Checkpoint = serde_json::from_str(&fs::read_to_string(&checkpoint_path).unwrap()).unwrap();
+        assert_eq!(saved.next_row, 70);
+
+        let resumed = group_files_checkpointed(sample_files(), 0.85, &algorithms, &Require::All, &ComponentWeights::default(), false, false, 2, &affixes, &checkpoint_path, &mut |_| {}).unwrap();
+        assert_eq!(summarize(&resumed), summarize(&expected));
+    }
+
+    #[test]
+    fn test_checkpoint_from_other_run_is_ignored() {
+        let temp_dir = TempDir::new().unwrap();
+        let checkpoint_path = temp_dir.path().join("run.checkpoint");
+        let algorithms = [Algorithm::Levenshtein];
+        let affixes = Affixes::default();
+
+        run(sample_files(), 0.85, &algorithms, &Require::All, &ComponentWeights::default(), false, false, 2, &affixes, &checkpoint_path, 10, &mut |_| {}).unwrap();
+
+        // A different threshold must not reuse edges scored at 0.85
+        let expected = group_files_with_algorithms(sample_files(), 0.95, &algorithms, &Require::All, &ComponentWeights::default(), false, false, 2, &affixes);
+        let mut warnings = Vec::new();
+        let result = group_files_checkpointed(sample_files(), 0.95, &algorithms, &Require::All, &ComponentWeights::default(), false, false, 2, &affixes, &checkpoint_path, &mut |warning| warnings.push(warning)).unwrap();
+        assert_eq!(summarize(&result), summarize(&expected));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("different run"), "{:?}", warnings);
+    }
+
+    #[test]
+    fn test_checkpoint_with_other_affixes_is_ignored() {
+        let temp_dir = TempDir::new().unwrap();
+        let checkpoint_path = temp_dir.path().join("run.checkpoint");
+        let algorithms = [Algorithm::Levenshtein];
+        let weights = ComponentWeights::default();
+        let alpha = Affixes::new(Some("alpha_"), None).unwrap();
+        let beta = Affixes::new(Some("beta_"), None).unwrap();
+
+        run(sample_files(), 0.85, &algorithms, &Require::All, &weights, false, false, 2, &alpha, &checkpoint_path, 10, &mut |_| {}).unwrap();
+
+        // Edges scored on names stripped of "alpha_" must not be reused
+        run(sample_files(), 0.85, &algorithms, &Require::All, &weights, false, false, 2, &beta, &checkpoint_path, 5, &mut |_| {}).unwrap();
+        let saved: Checkpoint = serde_json::from_str(&fs::read_to_string(&checkpoint_path).unwrap()).unwrap();
+        assert_eq!(saved.next_row, 5);
+
+        let expected = group_files_with_algorithms(sample_files(), 0.85, &algorithms, &Require::All, &weights, false, false, 2, &beta);
+        let result = group_files_checkpointed(sample_files(), 0.85, &algorithms, &Require::All, &weights, false, false, 2, &beta, &checkpoint_path, &mut |_| {}).unwrap();
+        assert_eq!(summarize(&result), summarize(&expected));
+    }
+}
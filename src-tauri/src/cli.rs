@@ -1,17 +1,388 @@
-#[derive(Clone, Debug)]
-#[allow(dead_code)]
+use crate::similarity::ComponentWeights;
+use clap::{Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+
+/// Command line arguments for the `similarity-checker-cli` binary.
+#[derive(Parser, Debug)]
+#[command(name = "similarity-checker", version, about = "Group files based on name similarity")]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Files to analyze
+    pub files: Vec<String>,
+
+    /// Config file supplying default flags (default: ./similarity-checker.toml if present)
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Apply the [profiles.NAME] section of the config file
+    #[arg(long, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Similarity threshold percentage (0-100); with --hierarchical, one per level (comma-separated)
+    #[arg(short = 't', long = "threshold", default_values_t = [70], value_delimiter = ',')]
+    pub thresholds: Vec<u8>,
+
+    /// Similarity threshold as a fraction (0.0-1.0), e.g. 0.725; overrides --threshold
+    #[arg(long, value_name = "FRACTION", conflicts_with_all = ["thresholds", "hierarchical"])]
+    pub threshold_float: Option<f64>,
+
+    /// Save comparison progress to FILE and resume from it if a previous run was interrupted
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["hierarchical", "group_by"])]
+    pub checkpoint: Option<PathBuf>,
+
+    /// Reuse a previous --format json result: only files added since are compared
+    #[arg(long, value_name = "RESULT", conflicts_with_all = ["hierarchical", "group_by", "checkpoint"])]
+    pub baseline: Option<PathBuf>,
+
+    /// After the report, ask which files of each group to move to the trash (needs a terminal)
+    #[arg(long, conflicts_with_all = ["hierarchical", "sweep", "matrix", "dump_tokens", "split_output"])]
+    pub interactive: bool,
+
+    /// Report each ungrouped file as a group of its own with similarity 1.0
+    #[arg(long, conflicts_with_all = ["hierarchical", "sweep", "matrix", "dump_tokens"])]
+    pub group_singletons: bool,
+
+    /// Exit with status 2 after printing the report if any group is found, e.g. to fail a CI check
+    #[arg(long, conflicts_with_all = ["hierarchical", "sweep", "matrix", "dump_tokens"])]
+    pub fail_on_duplicates: bool,
+
+    /// With --fail-on-duplicates, only fail when at least N groups are found
+    #[arg(long, value_name = "N", default_value_t = 1, requires = "fail_on_duplicates", value_parser = clap::value_parser!(u64).range(1..))]
+    pub fail_threshold: u64,
+
+    /// Rescale each algorithm's scores so unrelated names score near 0, making a threshold comparable across algorithms
+    #[arg(long)]
+    pub calibrate: bool,
+
+    /// Group files with byte-identical content (SHA-256) first, then group the rest by name; only files that exist on disk are hashed
+    #[arg(long, conflicts_with_all = ["hierarchical", "sweep", "matrix", "dump_tokens", "reference"])]
+    pub detect_identical: bool,
+
+    /// Skip pairs where the smaller file is less than RATIO (0.0-1.0] of the larger one's size; only files that exist on disk have a size
+    #[arg(long, value_name = "RATIO", conflicts_with_all = ["hierarchical", "group_by", "checkpoint", "baseline"])]
+    pub size_prefilter: Option<f64>,
+
+    /// Rank the files by similarity to this one instead of grouping them
+    #[arg(long, value_name = "FILE", conflicts_with_all = [
+        "hierarchical", "sweep", "matrix", "dump_tokens", "group_by", "checkpoint", "baseline", "size_prefilter",
+        "split_output", "output_dir", "summary_only", "explain", "rename_preview", "enrich", "interactive",
+        "group_singletons", "fail_on_duplicates",
+    ])]
+    pub reference: Option<String>,
+
+    /// Report groups and grouped files at thresholds 50-95 in steps of 5 instead of grouping
+    #[arg(long, conflicts_with_all = ["hierarchical", "group_by", "checkpoint", "baseline", "threshold_float"])]
+    pub sweep: bool,
+
+    /// Group the previous level's clusters again at each further --threshold, producing a tree
+    #[arg(long, conflicts_with_all = ["group_by", "rename_preview"])]
+    pub hierarchical: bool,
+
+    /// Permit --threshold 0, which puts every file into a single group
+    #[arg(long)]
+    pub allow_zero_threshold: bool,
+
+    /// Similarity algorithm
+    #[arg(short, long, value_enum, default_value_t = Algorithm::Auto)]
+    pub algorithm: Algorithm,
+
+    /// Combine several algorithms (comma-separated); overrides --algorithm
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub algorithms: Vec<Algorithm>,
+
+    /// Whether all or any of --algorithms must clear the threshold
+    #[arg(long, value_enum, default_value_t = Require::All)]
+    pub require: Require,
+
+    /// Components of --algorithm weighted, e.g. levenshtein=0.5,jaro=0.3,token=0.2 (must sum to 1.0)
+    #[arg(long, value_name = "SPEC", value_parser = ComponentWeights::parse)]
+    pub weights: Option<ComponentWeights>,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text, global = true)]
+    pub format: OutputFormat,
+
+    /// Start CSV or TSV output with `#` comment lines recording the threshold, algorithm and generation time
+    #[arg(long, conflicts_with_all = ["split_output", "output_dir", "hierarchical", "sweep", "matrix", "reference", "dump_tokens"])]
+    pub csv_comment_header: bool,
+
+    /// Output file (default: stdout)
+    #[arg(short, long, global = true)]
+    pub output: Option<PathBuf>,
+
+    /// Append to the --output file instead of overwriting it, e.g. to collect several runs (text, csv, tsv or jsonl)
+    #[arg(long, alias = "output-append", requires = "output", global = true)]
+    pub append: bool,
+
+    /// Directory for paginated output; requires --page-size
+    #[arg(long, requires = "page_size", conflicts_with_all = ["output", "rename_preview", "hierarchical"])]
+    pub output_dir: Option<PathBuf>,
+
+    /// Write each group to its own file (group-001.txt, ...) plus ungrouped.txt in DIR
+    #[arg(long, value_name = "DIR", conflicts_with_all = ["output", "output_dir", "rename_preview", "hierarchical", "summary_only", "explain"])]
+    pub split_output: Option<PathBuf>,
+
+    /// Allow --split-output to write into a directory that is not empty
+    #[arg(long, requires = "split_output")]
+    pub force: bool,
+
+    /// Split output into pages of at most N groups plus an index file
+    #[arg(long, value_name = "N", requires = "output_dir")]
+    pub page_size: Option<usize>,
+
+    /// Read file names from file
+    #[arg(short, long)]
+    pub input_file: Option<PathBuf>,
+
+    /// Discover files in directory
+    #[arg(short, long)]
+    pub discover: Option<PathBuf>,
+
+    /// Only discover files matching this glob, relative to the discovery root (repeatable)
+    #[arg(long, value_name = "GLOB", global = true)]
+    pub include: Vec<String>,
+
+    /// Skip discovered files matching this glob, relative to the discovery root (repeatable)
+    #[arg(long, value_name = "GLOB", global = true)]
+    pub exclude: Vec<String>,
+
+    /// Only discover files modified since this time: a duration ago (30m, 12h, 7d, 2w), an RFC 3339 time or a date (YYYY-MM-DD)
+    #[arg(long, value_name = "WHEN", requires = "discover")]
+    pub since: Option<String>,
+
+    /// Report discovered files by full path, grouping them by file name alone
+    #[arg(long)]
+    pub full_paths: bool,
+
+    /// Keep files given more than once instead of listing each once
+    #[arg(long)]
+    pub no_dedup: bool,
+
+    /// Keep files in the order given instead of sorting them
+    #[arg(long)]
+    pub preserve_order: bool,
+
+    /// Part of each file name that is compared (default: fullpath, or basename with --full-paths)
+    #[arg(long, value_enum)]
+    pub compare: Option<CompareMode>,
+
+    /// Follow symlinked files and directories during discovery (cycles are walked once)
+    #[arg(long, global = true)]
+    pub follow_symlinks: bool,
+
+    /// Minimum files per group
+    #[arg(long, default_value_t = 2)]
+    pub min_group_size: usize,
+
+    /// Show ungrouped files in output
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+    pub show_ungrouped: bool,
+
+    /// Enable case-sensitive matching
+    #[arg(long)]
+    pub case_sensitive: bool,
+
+    /// Regex for leading noise (e.g. a timestamp) to ignore when comparing names
+    #[arg(long, value_name = "RE")]
+    pub strip_prefix: Option<String>,
+
+    /// Regex for trailing noise before the extension (e.g. a user id) to ignore when comparing names
+    #[arg(long, value_name = "RE")]
+    pub strip_suffix: Option<String>,
+
+    /// Compare names in Unicode NFKC form, case-folded unless --case-sensitive (e.g. decomposed and precomposed accents match)
+    #[arg(long)]
+    pub normalize_unicode: bool,
+
+    /// Ignore duplicate markers such as " (1)", " - Copy", " copy" or "_1" at the end of names
+    #[arg(long)]
+    pub strip_copy_suffixes: bool,
+
+    /// How files are grouped
+    #[arg(long, value_enum, default_value_t = GroupBy::Similarity)]
+    pub group_by: GroupBy,
+
+    /// Extra naming scheme for --group-by naming-scheme, as NAME=REGEX (repeatable)
+    #[arg(long = "scheme", value_name = "NAME=REGEX")]
+    pub schemes: Vec<String>,
+
+    /// Only report groups whose average similarity is at least this percentage (0-100)
+    #[arg(long, value_name = "PERCENT")]
+    pub report_threshold: Option<u8>,
+
+    /// File of expected duplicate sets (one name per line, sets separated by blank lines) whose groups are not reported
+    #[arg(long, value_name = "FILE", conflicts_with = "hierarchical")]
+    pub ignore_groups: Option<PathBuf>,
+
+    /// Order of groups in the output
+    #[arg(long, value_enum, default_value_t = SortOrder::Similarity)]
+    pub sort: SortOrder,
+
+    /// Only output the first N groups after sorting; the summary still counts every group
+    #[arg(long, value_name = "N", conflicts_with_all = ["hierarchical", "sweep", "matrix", "dump_tokens", "reference"])]
+    pub limit: Option<usize>,
+
+    /// List each file as an object with its name, size, modified time and extension (JSON output, with --discover)
+    #[arg(long, requires = "discover", conflicts_with_all = ["explain", "summary_only", "rename_preview", "hierarchical", "sweep", "matrix", "dump_tokens", "split_output", "output_dir"])]
+    pub enrich: bool,
+
+    /// Include a score for every pair within each group (JSON output only)
+    #[arg(long, conflicts_with_all = ["hierarchical", "rename_preview", "output_dir"])]
+    pub explain: bool,
+
+    /// Print the pairwise similarity of all files as a CSV matrix instead of grouping
+    #[arg(long, conflicts_with_all = ["hierarchical", "rename_preview", "output_dir", "explain", "group_by", "checkpoint"])]
+    pub matrix: bool,
+
+    /// Print only the summary counts, without the groups or ungrouped files
+    #[arg(long, conflicts_with_all = ["hierarchical", "rename_preview", "output_dir", "explain", "matrix"])]
+    pub summary_only: bool,
+
+    /// Print the JSON Schema of --format json output, then exit
+    #[arg(long)]
+    pub emit_schema: bool,
+
+    /// Print how each file name is tokenized for comparison, then exit
+    #[arg(long, conflicts_with_all = ["matrix", "hierarchical", "rename_preview", "output_dir", "explain"])]
+    pub dump_tokens: bool,
+
+    /// Suppress warnings, the progress spinner and the "Results written to" message
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+
+    /// Log grouping decisions to stderr: -v for a summary, -vv for every compared pair, -vvv for each algorithm's score
+    #[arg(short, long, action = clap::ArgAction::Count, global = true, conflicts_with = "quiet")]
+    pub verbose: u8,
+
+    /// Flag groups whose members differ only by letter case
+    #[arg(long)]
+    pub flag_case_only: bool,
+
+    /// Print proposed canonical names for grouped files instead of the groups
+    #[arg(long)]
+    pub rename_preview: bool,
+
+    /// How --rename-preview picks the canonical base name of a group
+    #[arg(long, value_enum, default_value_t = RenamePolicy::MostCommon)]
+    pub rename_policy: RenamePolicy,
+}
+
+impl Args {
+    /// Threshold used for flat grouping, i.e. the first --threshold level.
+    pub fn threshold(&self) -> u8 {
+        self.thresholds.first().copied().unwrap_or(70)
+    }
+
+    /// The --compare mode, defaulting to basenames for --full-paths output.
+    pub fn compare_mode(&self) -> CompareMode {
+        self.compare.clone().unwrap_or(if self.full_paths { CompareMode::Basename } else { CompareMode::Fullpath })
+    }
+}
+
+/// Subcommands that replace the default file grouping.
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Report how similar two directory trees are and which files differ
+    TreeCompare(TreeCompareArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct TreeCompareArgs {
+    pub dir_a: PathBuf,
+    pub dir_b: PathBuf,
+
+    /// Also require files at the same relative path to have identical content
+    #[arg(long)]
+    pub content: bool,
+}
+
+/// Similarity algorithm used to compare two file names.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, ValueEnum)]
 pub enum Algorithm {
+    #[value(alias = "lev")]
     Levenshtein,
     Jaro,
+    /// Share of matching positions for names of equal length; Levenshtein otherwise
+    Hamming,
     Token,
+    /// Levenshtein over the alphabetically sorted tokens, ignoring word order
+    TokenSort,
     Substring,
+    Dice,
+    Phonetic,
+    Lcs,
+    /// Levenshtein of the shorter name against its best-matching window in the longer one
+    Partial,
     Auto,
+    /// Weighted sum of other algorithms' scores; see `--weights`
+    Weighted,
 }
 
-#[derive(Clone, Debug)]
-#[allow(dead_code)]
+/// Part of each input string that names are compared by.
+#[derive(Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum CompareMode {
+    /// The file name without its directory
+    Basename,
+    /// The string exactly as given
+    #[default]
+    Fullpath,
+    /// The file name without its directory or extension
+    Stem,
+}
+
+/// How the verdicts of multiple algorithms are combined.
+#[derive(Clone, Debug, ValueEnum)]
+pub enum Require {
+    /// Every algorithm must clear the threshold
+    All,
+    /// At least one algorithm must clear the threshold
+    Any,
+}
+
+/// Strategy used to form groups.
+#[derive(Clone, Debug, ValueEnum)]
+pub enum GroupBy {
+    /// Pairwise name similarity
+    Similarity,
+    /// Camera/phone naming scheme such as IMG_, PXL_ or DSC
+    NamingScheme,
+}
+
+/// Order in which groups are reported.
+#[derive(Clone, Debug, ValueEnum)]
+pub enum SortOrder {
+    /// Highest similarity first
+    Similarity,
+    /// Largest groups first
+    Size,
+    /// Alphabetically by the group's first file name
+    Name,
+}
+
+/// Policy for choosing the canonical base name of a group.
+#[derive(Clone, Debug, ValueEnum)]
+pub enum RenamePolicy {
+    /// The most frequent file stem, falling back to the shortest
+    MostCommon,
+    /// The shortest file stem
+    Shortest,
+    /// The stem of the first file in the group
+    First,
+}
+
+/// Format used to render a [`GroupingResult`](crate::grouper::GroupingResult).
+#[derive(Clone, Debug, ValueEnum)]
 pub enum OutputFormat {
     Text,
     Json,
+    Jsonl,
     Csv,
-}
\ No newline at end of file
+    /// Tab-separated values with the same columns as csv
+    Tsv,
+    /// Self-contained interactive review page
+    Html,
+    /// Bordered table with aligned columns, fitted to the terminal width
+    Table,
+}
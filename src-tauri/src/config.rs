@@ -0,0 +1,265 @@
+use crate::cli::Args;
+use anyhow::{Context, Result};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Config file picked up from the working directory when `--config` is not given.
+pub const DEFAULT_CONFIG_FILE: &str = "similarity-checker.toml";
+
+/// Settings that only make sense on the command line.
+const CLI_ONLY: [&str; 3] = ["files", "config", "profile"];
+
+/// Parses the command line, filling in flags it leaves unset from the config
+/// file. Top-level keys apply to every run; `--profile NAME` layers the
+/// `[profiles.NAME]` table on top. Keys are long flag names such as
+/// `threshold` or `min-group-size`.
+pub fn parse_args() -> Result<Args> {
+    parse_args_from(std::env::args_os())
+}
+
+pub fn parse_args_from<I, T>(argv: I) -> Result<Args>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let argv: Vec<OsString> = argv.into_iter().map(Into::into).collect();
+    let matches = Args::command().get_matches_from(&argv);
+    let args = Args::from_arg_matches(&matches)?;
+
+    let config_path = match &args.config {
+        Some(path) => Some(path.clone()),
+        None => Some(PathBuf::from(DEFAULT_CONFIG_FILE)).filter(|path| path.is_file()),
+    };
+    let Some(config_path) = config_path else {
+        if let Some(profile) = &args.profile {
+            anyhow::bail!(
+                "--profile {} needs a config file; pass --config or create {}",
+                profile,
+                DEFAULT_CONFIG_FILE
+            );
+        }
+        return Ok(args);
+    };
+
+    let settings = load_settings(&config_path, args.profile.as_deref())?;
+    let defaults = settings_to_args(&settings, &matches)
+        .with_context(|| format!("Invalid config file: {}", config_path.display()))?;
+    if defaults.is_empty() {
+        return Ok(args);
+    }
+
+    // Config flags go before the user's arguments so they can never end up
+    // after a `--` separator
+    let mut merged = vec![argv[0].clone()];
+    merged.extend(defaults);
+    merged.extend(argv.into_iter().skip(1));
+    let matches = Args::command()
+        .try_get_matches_from(merged)
+        .map_err(|e| anyhow::anyhow!("Invalid config file {}: {}", config_path.display(), e.render()))?;
+    Ok(Args::from_arg_matches(&matches)?)
+}
+
+/// Contents of a config file. Settings are kept as raw TOML values keyed by
+/// long flag name and validated by clap, so every flag can be configured and
+/// any key left out keeps its usual default.
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    profiles: BTreeMap<String, toml::Table>,
+    #[serde(flatten)]
+    settings: toml::Table,
+}
+
+impl ConfigFile {
+    fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Failed to parse config file: {}", path.display()))
+    }
+}
+
+/// Reads the top-level settings of `path`, overlaid with `profile` if given.
+fn load_settings(path: &Path, profile: Option<&str>) -> Result<toml::Table> {
+    let ConfigFile { mut profiles, mut settings } = ConfigFile::load(path)?;
+
+    if let Some(name) = profile {
+        let Some(selected) = profiles.remove(name) else {
+            let available: Vec<&str> = profiles.keys().map(String::as_str).collect();
+            anyhow::bail!(
+                "Unknown profile '{}' in {} (available: {})",
+                name,
+                path.display(),
+                if available.is_empty() { "none".to_string() } else { available.join(", ") }
+            );
+        };
+        settings.extend(selected);
+    }
+
+    Ok(settings)
+}
+
+/// Turns config settings into command-line arguments, skipping any flag the
+/// user already passed explicitly.
+fn settings_to_args(settings: &toml::Table, matches: &ArgMatches) -> Result<Vec<OsString>> {
+    let command = Args::command();
+    let mut args = Vec::new();
+
+    for (key, value) in settings {
+        let long = key.replace('_', "-");
+        let Some(arg) = command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(long.as_str()))
+        else {
+            anyhow::bail!("Unknown setting '{}'", key);
+        };
+        let id = arg.get_id().as_str();
+        if CLI_ONLY.contains(&id) {
+            anyhow::bail!("'{}' can only be set on the command line", key);
+        }
+        if matches.value_source(id) == Some(ValueSource::CommandLine) {
+            continue;
+        }
+
+        let flag = OsString::from(format!("--{}", long));
+        let takes_value = arg.get_action().takes_values();
+        let values = match value {
+            toml::Value::Array(items) => items.iter().collect(),
+            _ => vec![value],
+        };
+        for value in values {
+            match (value, takes_value) {
+                (toml::Value::Boolean(true), false) => args.push(flag.clone()),
+                (toml::Value::Boolean(false), false) => {}
+                (_, false) => anyhow::bail!("'{}' is a switch and must be true or false", key),
+                (toml::Value::String(s), true) => args.extend([flag.clone(), s.into()]),
+                (toml::Value::Integer(_) | toml::Value::Float(_) | toml::Value::Boolean(_), true) => {
+                    args.extend([flag.clone(), value.to_string().into()])
+                }
+                (_, true) => anyhow::bail!("Unsupported value for '{}'", key),
+            }
+        }
+    }
+
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Algorithm, Command, OutputFormat};
+    use tempfile::TempDir;
+
+    const CONFIG: &str = r#"
+format = "json"
+
+[profiles.photos]
+threshold = 90
+algorithm = "token"
+include = ["*.jpg", "*.png"]
+case_sensitive = true
+
+[profiles.documents]
+threshold = 75
+algorithm = "jaro"
+min-group-size = 3
+"#;
+
+    fn write_config(dir: &TempDir) -> String {
+        let path = dir.path().join("config.toml");
+        fs::write(&path, CONFIG).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_profile_settings_apply_with_cli_override() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = write_config(&temp_dir);
+
+        let args = parse_args_from(["similarity-checker", "--config", &config, "--profile", "photos", "-a", "jaro", "a.jpg"]).unwrap();
+        assert_eq!(args.thresholds, vec![90]);
+        assert!(matches!(args.algorithm, Algorithm::Jaro));
+        assert_eq!(args.include, vec!["*.jpg", "*.png"]);
+        assert!(args.case_sensitive);
+        assert!(matches!(args.format, OutputFormat::Json));
+        assert_eq!(args.files, vec!["a.jpg"]);
+    }
+
+    #[test]
+    fn test_top_level_settings_without_profile() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = write_config(&temp_dir);
+
+        let args = parse_args_from(["similarity-checker", "--config", &config]).unwrap();
+        assert!(matches!(args.format, OutputFormat::Json));
+        assert_eq!(args.thresholds, vec![70]);
+
+        let args = parse_args_from(["similarity-checker", "--config", &config, "--profile", "documents"]).unwrap();
+        assert_eq!(args.thresholds, vec![75]);
+        assert_eq!(args.min_group_size, 3);
+    }
+
+    #[test]
+    fn test_invalid_profiles_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = write_config(&temp_dir);
+
+        let err = parse_args_from(["similarity-checker", "--config", &config, "--profile", "music"]).unwrap_err();
+        assert!(err.to_string().contains("available: documents, photos"));
+
+        let bad = temp_dir.path().join("bad.toml");
+        fs::write(&bad, "thresold = 80\n").unwrap();
+        assert!(parse_args_from(["similarity-checker", "--config", bad.to_str().unwrap()]).is_err());
+    }
+
+    #[test]
+    fn test_config_defaults_merge_with_cli_flags() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("defaults.toml");
+        fs::write(
+            &path,
+            "threshold = 85\nalgorithm = \"lev\"\nformat = \"csv\"\nexclude = [\"*.tmp\", \"cache/*\"]\nmin_group_size = 3\n",
+        )
+        .unwrap();
+        let config = path.to_str().unwrap();
+
+        let args = parse_args_from(["similarity-checker", "--config", config, "--format", "json"]).unwrap();
+        assert_eq!(args.thresholds, vec![85]);
+        assert!(matches!(args.algorithm, Algorithm::Levenshtein));
+        assert!(matches!(args.format, OutputFormat::Json));
+        assert_eq!(args.exclude, vec!["*.tmp", "cache/*"]);
+        assert_eq!(args.min_group_size, 3);
+        // Fields absent from the file keep their defaults
+        assert!(args.show_ungrouped);
+        assert!(!args.case_sensitive);
+    }
+
+    #[test]
+    fn test_tree_compare_subcommand_takes_shared_flags() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = write_config(&temp_dir);
+
+        let args = parse_args_from(["similarity-checker", "--config", &config, "tree-compare", "a", "b", "--content", "--exclude", "*.tmp"]).unwrap();
+        let Some(Command::TreeCompare(tree_args)) = &args.command else {
+            panic!("expected tree-compare, got {:?}", args.command);
+        };
+        assert_eq!(tree_args.dir_a, PathBuf::from("a"));
+        assert_eq!(tree_args.dir_b, PathBuf::from("b"));
+        assert!(tree_args.content);
+        assert_eq!(args.exclude, vec!["*.tmp"]);
+        assert!(matches!(args.format, OutputFormat::Json));
+        assert!(args.files.is_empty());
+    }
+
+    #[test]
+    fn test_missing_config_file_is_an_error() {
+        let temp_dir = TempDir::new().unwrap();
+        let missing = temp_dir.path().join("missing.toml");
+        let err = parse_args_from(["similarity-checker", "--config", missing.to_str().unwrap()]).unwrap_err();
+        assert!(format!("{:#}", err).contains("Failed to read config file"));
+    }
+}
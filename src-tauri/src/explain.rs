@@ -0,0 +1,131 @@
+use crate::cli::{Algorithm, Require};
+use crate::grouper::GroupingResult;
+use crate::similarity::{calculate_combined_similarity, dominant_auto_algorithm, Affixes, ComponentWeights};
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+/// Score of one pair of files within a group.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairExplanation {
+    pub file_a: String,
+    pub file_b: String,
+    pub similarity: f64,
+    /// With `--algorithm auto`, the blended algorithm that contributed most
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dominant_algorithm: Option<String>,
+}
+
+/// Scores every pair of files within each group, in group order.
+pub fn explain_groups(
+    result: &GroupingResult,
+    algorithms: &[Algorithm],
+    require: &Require,
+    weights: &ComponentWeights,
+    case_sensitive: bool,
+    calibrate: bool,
+    affixes: &Affixes,
+) -> Vec<Vec<PairExplanation>> {
+    let is_auto = matches!(algorithms, [Algorithm::Auto]);
+
+    result
+        .groups
+        .iter()
+        .map(|group| {
+            let names: Vec<_> = group.files.iter().map(|file| affixes.strip(file)).collect();
+            let mut pairs = Vec::new();
+            for i in 0..names.len() {
+                for j in (i + 1)..names.len() {
+                    let dominant_algorithm = is_auto.then(|| {
+                        let algorithm = dominant_auto_algorithm(&names[i], &names[j], case_sensitive);
+                        algorithm_name(&algorithm)
+                    });
+                    pairs.push(PairExplanation {
+                        file_a: group.files[i].clone(),
+                        file_b: group.files[j].clone(),
+                        similarity: calculate_combined_similarity(&names[i], &names[j], algorithms, require, weights, case_sensitive, calibrate),
+                        dominant_algorithm,
+                    });
+                }
+            }
+            pairs
+        })
+        .collect()
+}
+
+fn algorithm_name(algorithm: &Algorithm) -> String {
+    algorithm
+        .to_possible_value()
+        .map(|value| value.get_name().to_string())
+        .unwrap_or_default()
+}
+
+/// JSON output like `--format json`, with each group carrying its `pairs`.
+pub fn format_explained_json<W: Write>(
+    result: &GroupingResult,
+    explanations: &[Vec<PairExplanation>],
+    writer: &mut W,
+    show_ungrouped: bool,
+) -> Result<()> {
+    let mut output = serde_json::json!({
+        "groups": result.groups,
+        "summary": result.summary
+    });
+
+    if let Some(groups) = output["groups"].as_array_mut() {
+        for (group, pairs) in groups.iter_mut().zip(explanations) {
+            group["pairs"] = serde_json::to_value(pairs)?;
+        }
+    }
+    if show_ungrouped {
+        output["ungrouped"] = serde_json::to_value(&result.ungrouped)?;
+    }
+
+    writeln!(writer, "{}", serde_json::to_string_pretty(&output)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grouper::Group;
+
+    fn create_test_result(files: &[&str]) -> GroupingResult {
+        GroupingResult::fixture(vec![Group::fixture(1, files, 0.8)], &[])
+    }
+
+    #[test]
+    fn test_dominant_algorithm_attribution() {
+        // A character typo in a plain name and a word reorder in a structured one
+        let result = create_test_result(&["photograph.jpg", "photgraph.jpg", "march_invoice", "invoice_march"]);
+
+        let explanations = explain_groups(&result, &[Algorithm::Auto], &Require::All, &ComponentWeights::default(), false, false, &Affixes::default());
+        let pairs = &explanations[0];
+        assert_eq!(pairs.len(), 6);
+
+        let find = |a: &str, b: &str| pairs.iter().find(|p| p.file_a == a && p.file_b == b).unwrap();
+        assert_eq!(find("photograph.jpg", "photgraph.jpg").dominant_algorithm.as_deref(), Some("levenshtein"));
+        assert_eq!(find("march_invoice", "invoice_march").dominant_algorithm.as_deref(), Some("token"));
+    }
+
+    #[test]
+    fn test_dominant_algorithm_only_for_auto() {
+        let result = create_test_result(&["report_v1.pdf", "report_v2.pdf"]);
+        let explanations = explain_groups(&result, &[Algorithm::Levenshtein], &Require::All, &ComponentWeights::default(), false, false, &Affixes::default());
+        assert!(explanations[0][0].dominant_algorithm.is_none());
+        assert!(explanations[0][0].similarity > 0.9);
+    }
+
+    #[test]
+    fn test_format_explained_json() {
+        let result = create_test_result(&["march_invoice", "invoice_march"]);
+        let explanations = explain_groups(&result, &[Algorithm::Auto], &Require::All, &ComponentWeights::default(), false, false, &Affixes::default());
+
+        let mut output = Vec::new();
+        format_explained_json(&result, &explanations, &mut output, false).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(value["groups"][0]["pairs"][0]["dominant_algorithm"], "token");
+        assert!(value.get("ungrouped").is_none());
+    }
+}
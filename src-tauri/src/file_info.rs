@@ -1,9 +1,12 @@
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::io::{BufReader, Read};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
 use anyhow::Result;
+use crate::hash_cache::HashCache;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileInfo {
@@ -13,6 +16,17 @@ pub struct FileInfo {
     pub last_modified: u64,
     pub path: String,
     pub hash: Option<String>,
+    /// MIME type sniffed from the file's leading bytes, if recognized
+    #[serde(default)]
+    pub detected_mime: Option<String>,
+    /// `(device, inode)` of the file on unix; files sharing it are hard links
+    /// to the same data
+    #[serde(default)]
+    pub inode: Option<(u64, u64)>,
+    /// Set on grouped files that are hard links to another file of the same
+    /// group. Deleting one of them frees no space.
+    #[serde(default)]
+    pub hard_link: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +35,17 @@ pub struct SimilarityGroup {
     pub files: Vec<FileInfo>,
     pub similarity_type: SimilarityType,
     pub similarity_score: f64,
+    /// Why each file joined, parallel to `files`. The first file seeds the
+    /// group and has no entry (`null`).
+    #[serde(default)]
+    pub matches: Vec<Option<FileMatch>>,
+}
+
+/// The criterion a file matched the seed of its group by, and their score.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileMatch {
+    pub similarity_type: SimilarityType,
+    pub score: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +58,139 @@ pub enum SimilarityType {
     Size,
     #[serde(rename = "content")]
     Content,
+    /// Zero-byte files, kept apart from identical content unless configured otherwise
+    #[serde(rename = "empty")]
+    Empty,
+    /// Images that look alike (e.g. resized or recompressed copies)
+    #[serde(rename = "perceptual")]
+    Perceptual,
+}
+
+/// Which file of a group [`suggest_keepers`] proposes to keep.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeepPolicy {
+    ShortestName,
+    Newest,
+    Oldest,
+    Largest,
+    Smallest,
+}
+
+/// The file to keep from one group and the ones that duplicate it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeepSuggestion {
+    pub group_id: String,
+    pub keep: FileInfo,
+    pub redundant: Vec<FileInfo>,
+}
+
+/// Picks a file to keep from each group according to `policy`; the other
+/// files are listed as redundant. Ties go to the file listed first.
+pub fn suggest_keepers(groups: &[SimilarityGroup], policy: KeepPolicy) -> Vec<KeepSuggestion> {
+    groups
+        .iter()
+        .filter_map(|group| {
+            let files = group.files.iter().enumerate();
+            let (keep_index, _) = match policy {
+                KeepPolicy::ShortestName => files.min_by_key(|(_, file)| file.name.chars().count()),
+                KeepPolicy::Newest => files.min_by_key(|(_, file)| std::cmp::Reverse(file.last_modified)),
+                KeepPolicy::Oldest => files.min_by_key(|(_, file)| file.last_modified),
+                KeepPolicy::Largest => files.min_by_key(|(_, file)| std::cmp::Reverse(file.size)),
+                KeepPolicy::Smallest => files.min_by_key(|(_, file)| file.size),
+            }?;
+            
+            let mut redundant = group.files.clone();
+            let keep = redundant.remove(keep_index);
+            Some(KeepSuggestion {
+                group_id: group.id.clone(),
+                keep,
+                redundant,
+            })
+        })
+        .collect()
+}
+
+/// Name-similarity thresholds for the tiered detection in [`group_similar_files`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GroupingConfig {
+    /// Minimum name similarity for same-size files (Tier 2)
+    pub tier2_threshold: f64,
+    /// Minimum name similarity for name-only matches (Tier 3)
+    pub tier3_threshold: f64,
+    /// Largest size difference, in bytes, at which files still count as the
+    /// same size for Tier 2; 0 requires an exact match
+    pub size_tolerance_bytes: u64,
+    /// Only match same-size files by name (Tier 2) when they share a file type
+    pub tier2_same_type: bool,
+    /// Only match files by name (Tier 3) when they share a file type
+    pub tier3_same_type: bool,
+    /// Group zero-byte files as identical content (they all share one hash)
+    /// instead of reporting them in a separate `empty` group
+    pub include_zero_byte_as_identical: bool,
+    /// Treat text files that differ only in trailing whitespace or runs of
+    /// blank lines as identical content (Tier 1); binary files still need
+    /// byte-identical content
+    pub ignore_whitespace_differences: bool,
+    /// Names longer than this (after normalization) are never matched by name
+    pub max_name_length: usize,
+    /// Only compare files whose detected MIME categories (e.g. `image`) match;
+    /// files without a detected type only match each other
+    pub same_mime_category: bool,
+    /// Group images whose perceptual hashes differ in at most this many of
+    /// their 64 bits, so resized or recompressed copies match; disabled when
+    /// unset. Files that are not decodable images use the other tiers
+    pub perceptual_hash_distance: Option<u32>,
+    /// Location of the on-disk hash cache; hashes are always recomputed when unset
+    #[serde(skip)]
+    pub hash_cache_path: Option<PathBuf>,
+}
+
+impl Default for GroupingConfig {
+    fn default() -> Self {
+        Self {
+            tier2_threshold: 0.8,
+            tier3_threshold: 0.9,
+            size_tolerance_bytes: 0,
+            tier2_same_type: false,
+            tier3_same_type: false,
+            include_zero_byte_as_identical: false,
+            ignore_whitespace_differences: false,
+            max_name_length: DEFAULT_MAX_NAME_LENGTH,
+            same_mime_category: false,
+            perceptual_hash_distance: None,
+            hash_cache_path: None,
+        }
+    }
+}
+
+impl GroupingConfig {
+    pub fn validate(&self) -> Result<()> {
+        for (name, value) in [("tier2_threshold", self.tier2_threshold), ("tier3_threshold", self.tier3_threshold)] {
+            if !(0.0..=1.0).contains(&value) {
+                anyhow::bail!("{} must be between 0.0 and 1.0, got {}", name, value);
+            }
+        }
+        if let Some(distance) = self.perceptual_hash_distance {
+            if distance > 64 {
+                anyhow::bail!("perceptual_hash_distance must be at most 64, got {}", distance);
+            }
+        }
+        if self.tier3_threshold < self.tier2_threshold {
+            anyhow::bail!(
+                "tier3_threshold ({}) must be greater than or equal to tier2_threshold ({})",
+                self.tier3_threshold,
+                self.tier2_threshold
+            );
+        }
+        Ok(())
+    }
+    
+    /// Whether two file sizes are close enough for Tier 2.
+    pub fn sizes_match(&self, size1: u64, size2: u64) -> bool {
+        size1.abs_diff(size2) <= self.size_tolerance_bytes
+    }
 }
 
 impl FileInfo {
@@ -59,9 +217,19 @@ impl FileInfo {
             last_modified,
             path: path.to_string_lossy().to_string(),
             hash: None,
+            detected_mime: detect_mime(path),
+            inode: inode(&metadata),
+            hard_link: false,
         })
     }
     
+    /// The top-level part of the detected MIME type, e.g. `image` for `image/png`.
+    pub fn mime_category(&self) -> Option<&str> {
+        self.detected_mime
+            .as_deref()
+            .map(|mime| mime.split('/').next().unwrap_or(mime))
+    }
+    
     pub fn calculate_hash(&mut self) -> Result<String> {
         if let Some(ref hash) = self.hash {
             return Ok(hash.clone());
@@ -72,37 +240,163 @@ impl FileInfo {
         Ok(hash_string)
     }
     
-    fn calculate_chunked_hash(&self) -> Result<String> {
-        const CHUNK_SIZE: usize = 64 * 1024; // 64KB chunks
+    /// Like [`calculate_hash`](Self::calculate_hash), but skips reading the
+    /// file when `cache` has an entry with the same size and mtime.
+    pub fn calculate_hash_cached(&mut self, cache: &mut HashCache) -> Result<String> {
+        if let Some(ref hash) = self.hash {
+            return Ok(hash.clone());
+        }
+        
+        if let Some(hash) = cache.get(&self.path, self.size, self.last_modified) {
+            self.hash = Some(hash.clone());
+            return Ok(hash);
+        }
+        
+        let hash_string = self.calculate_hash()?;
+        cache.insert(&self.path, self.size, self.last_modified, hash_string.clone());
+        Ok(hash_string)
+    }
+    
+    /// SHA-256 of the file's text with trailing whitespace removed from every
+    /// line and the end of the file, and runs of blank lines collapsed into
+    /// one. Returns `None` for binary files, i.e. those containing a NUL byte
+    /// near the start or that are not valid UTF-8.
+    pub fn normalized_text_hash(&self) -> Result<Option<String>> {
+        const SNIFF_SIZE: usize = 8 * 1024;
         
-        let file = fs::File::open(&self.path)?;
-        let mut reader = BufReader::new(file);
-        let mut hasher = Sha256::new();
-        let mut buffer = vec![0u8; CHUNK_SIZE];
+        let mut file = fs::File::open(&self.path)?;
+        let mut head = vec![0u8; SNIFF_SIZE];
+        let read = file.read(&mut head)?;
+        if head[..read].contains(&0) {
+            return Ok(None);
+        }
+        head.truncate(read);
+        file.read_to_end(&mut head)?;
+        let Ok(text) = String::from_utf8(head) else {
+            return Ok(None);
+        };
         
-        loop {
-            let bytes_read = reader.read(&mut buffer)?;
-            if bytes_read == 0 {
-                break;
+        let mut normalized = String::with_capacity(text.len());
+        let mut previous_blank = false;
+        for line in text.lines().map(str::trim_end) {
+            if line.is_empty() && previous_blank {
+                continue;
             }
-            hasher.update(&buffer[..bytes_read]);
+            previous_blank = line.is_empty();
+            normalized.push_str(line);
+            normalized.push('\n');
+        }
+        let normalized = normalized.trim_end();
+        
+        Ok(Some(hex::encode(Sha256::digest(normalized.as_bytes()))))
+    }
+    
+    /// 64-bit difference hash (dHash) of an image: the picture is shrunk to
+    /// 9×8 grayscale pixels and each bit records whether a pixel is brighter
+    /// than its right-hand neighbour. Resizing or recompressing an image
+    /// changes only a few bits. Returns `None` unless the file was detected as
+    /// an image.
+    pub fn perceptual_hash(&self) -> Result<Option<u64>> {
+        if self.mime_category() != Some("image") {
+            return Ok(None);
         }
         
-        let result = hasher.finalize();
-        Ok(hex::encode(result))
+        let image = image::open(&self.path)?;
+        let pixels = image
+            .resize_exact(9, 8, image::imageops::FilterType::Triangle)
+            .to_luma8();
+        let mut hash = 0u64;
+        for y in 0..8 {
+            for x in 0..8 {
+                let brighter = pixels.get_pixel(x, y)[0] > pixels.get_pixel(x + 1, y)[0];
+                hash = (hash << 1) | u64::from(brighter);
+            }
+        }
+        Ok(Some(hash))
+    }
+    
+    fn calculate_chunked_hash(&self) -> Result<String> {
+        sha256_file(Path::new(&self.path))
     }
 }
 
-pub fn calculate_name_similarity(name1: &str, name2: &str) -> f64 {
-    let normalize = |s: &str| -> String {
-        s.to_lowercase()
-            .chars()
-            .filter(|c| c.is_alphanumeric())
-            .collect()
-    };
+/// Hex-encoded SHA-256 of the file at `path`, read in chunks so large files
+/// are never held in memory at once.
+pub fn sha256_file(path: &Path) -> Result<String> {
+    const CHUNK_SIZE: usize = 64 * 1024; // 64KB chunks
+    
+    let file = fs::File::open(path)?;
+    let mut reader = BufReader::new(file);
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; CHUNK_SIZE];
     
-    let n1 = normalize(name1);
-    let n2 = normalize(name2);
+    loop {
+        let bytes_read = reader.read(&mut buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..bytes_read]);
+    }
+    
+    let result = hasher.finalize();
+    Ok(hex::encode(result))
+}
+
+#[cfg(unix)]
+fn inode(metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn inode(_metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// Flags the files of a group that share their inode with another member.
+fn flag_hard_links(files: &mut [FileInfo]) {
+    let mut counts: std::collections::HashMap<(u64, u64), usize> = std::collections::HashMap::new();
+    for inode in files.iter().filter_map(|file| file.inode) {
+        *counts.entry(inode).or_default() += 1;
+    }
+    for file in files.iter_mut() {
+        file.hard_link = file.inode.is_some_and(|inode| counts[&inode] > 1);
+    }
+}
+
+/// Sniffs the MIME type from the first bytes of the file. Returns `None` for
+/// unreadable files and formats without a recognizable signature, such as
+/// plain text.
+fn detect_mime(path: &Path) -> Option<String> {
+    infer::get_from_path(path)
+        .ok()
+        .flatten()
+        .map(|kind| kind.mime_type().to_string())
+}
+
+/// Longest normalized name compared by default. File systems cap names well
+/// below this, so only pathological inputs hit it.
+pub const DEFAULT_MAX_NAME_LENGTH: usize = 1024;
+
+pub fn calculate_name_similarity(name1: &str, name2: &str) -> f64 {
+    calculate_name_similarity_capped(name1, name2, DEFAULT_MAX_NAME_LENGTH)
+}
+
+/// Lowercases `name` and keeps only its alphanumeric characters, the form
+/// names are compared in.
+fn normalize_name(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect()
+}
+
+/// Like [`calculate_name_similarity`], but names whose normalized length
+/// exceeds `max_length` are scored `0.0` instead of building a quadratic
+/// edit-distance matrix for them.
+pub fn calculate_name_similarity_capped(name1: &str, name2: &str, max_length: usize) -> f64 {
+    let n1 = normalize_name(name1);
+    let n2 = normalize_name(name2);
     
     if n1 == n2 {
         return 1.0;
@@ -118,6 +412,9 @@ pub fn calculate_name_similarity(name1: &str, name2: &str) -> f64 {
     if len1 == 0 || len2 == 0 {
         return 0.0;
     }
+    if len1.max(len2) > max_length {
+        return 0.0;
+    }
     
     let chars1: Vec<char> = n1.chars().collect();
     let chars2: Vec<char> = n2.chars().collect();
@@ -126,11 +423,11 @@ pub fn calculate_name_similarity(name1: &str, name2: &str) -> f64 {
     let mut matrix = vec![vec![0; len1 + 1]; len2 + 1];
     
     // Initialize base cases
-    for i in 0..=len1 {
-        matrix[0][i] = i;
+    for (i, cell) in matrix[0].iter_mut().enumerate() {
+        *cell = i;
     }
-    for j in 0..=len2 {
-        matrix[j][0] = j;
+    for (j, row) in matrix.iter_mut().enumerate() {
+        row[0] = j;
     }
     
     // Fill matrix with minimum edit distances
@@ -153,50 +450,263 @@ pub fn calculate_name_similarity(name1: &str, name2: &str) -> f64 {
     }
 }
 
-pub async fn group_similar_files(mut files: Vec<FileInfo>) -> Result<Vec<SimilarityGroup>> {
+/// Progress reported by [`group_similar_files_with_progress`]. Counts are
+/// 1-based and never decrease within a stage.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "stage", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    Hashing { current: usize, total: usize, file: String },
+    Comparing { current: usize, total: usize },
+}
+
+/// Returned (wrapped in [`anyhow::Error`]) when an analysis is aborted via
+/// its cancellation flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Analysis cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Hashes every file that has no hash yet on a pool of threads, one per
+/// available core, and calls `on_hashed` on this thread as each one finishes.
+/// A file that can't be read is reported and left without a hash, so it only
+/// misses the identical-content tier. No new file is started once `cancel`
+/// is set. Returns the indices of the files hashed.
+fn hash_files_parallel<F>(files: &mut [FileInfo], cancel: &AtomicBool, mut on_hashed: F) -> Vec<usize>
+where
+    F: FnMut(&FileInfo),
+{
+    let pending: Vec<usize> = (0..files.len()).filter(|&index| files[index].hash.is_none()).collect();
+    let workers = std::thread::available_parallelism().map_or(1, |cores| cores.get()).min(pending.len());
+    let next = AtomicUsize::new(0);
+    let mut results = Vec::with_capacity(pending.len());
+    
+    std::thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+        let (files, pending, next) = (&*files, &pending, &next);
+        for _ in 0..workers {
+            let sender = sender.clone();
+            scope.spawn(move || {
+                while !cancel.load(Ordering::Relaxed) {
+                    let Some(&index) = pending.get(next.fetch_add(1, Ordering::Relaxed)) else {
+                        break;
+                    };
+                    if sender.send((index, files[index].calculate_chunked_hash())).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+        
+        for (index, hash) in receiver {
+            on_hashed(&files[index]);
+            results.push((index, hash));
+        }
+    });
+    
+    let mut computed = Vec::with_capacity(results.len());
+    for (index, hash) in results {
+        match hash {
+            Ok(hash) => {
+                files[index].hash = Some(hash);
+                computed.push(index);
+            }
+            Err(e) => eprintln!("Warning: Failed to hash {}: {}", files[index].path, e),
+        }
+    }
+    computed.sort_unstable();
+    computed
+}
+
+pub async fn group_similar_files(files: Vec<FileInfo>, config: &GroupingConfig) -> Result<Vec<SimilarityGroup>> {
+    group_similar_files_with_progress(files, config, |_| {}, &AtomicBool::new(false)).await
+}
+
+/// Like [`group_similar_files`], but calls `on_progress` as each file is
+/// hashed and as each file is compared against the rest, and stops with a
+/// [`Cancelled`] error as soon as `cancel` is set.
+pub async fn group_similar_files_with_progress<F>(
+    mut files: Vec<FileInfo>,
+    config: &GroupingConfig,
+    mut on_progress: F,
+    cancel: &AtomicBool,
+) -> Result<Vec<SimilarityGroup>>
+where
+    F: FnMut(ProgressEvent),
+{
+    config.validate()?;
+    let total = files.len();
+    let check_cancelled = || -> Result<()> {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(Cancelled.into());
+        }
+        Ok(())
+    };
+    
     let mut groups = Vec::new();
     let mut processed_files = std::collections::HashSet::new();
     
-    // Calculate hashes for all files
-    for file in &mut files {
-        file.calculate_hash()?;
+    // Calculate hashes for all files, reusing cached ones when configured
+    check_cancelled()?;
+    {
+        let mut hashed = 0;
+        let mut report = |file: &FileInfo| {
+            hashed += 1;
+            on_progress(ProgressEvent::Hashing { current: hashed, total, file: file.name.clone() });
+        };
+        let mut cache = config.hash_cache_path.as_deref().map(HashCache::load).transpose()?;
+        if let Some(reason) = cache.as_ref().and_then(HashCache::discarded) {
+            eprintln!("Warning: {}", reason);
+        }
+        for file in files.iter_mut() {
+            if let (None, Some(cache)) = (&file.hash, &mut cache) {
+                file.hash = cache.get(&file.path, file.size, file.last_modified);
+            }
+            if file.hash.is_some() {
+                report(file);
+            }
+        }
+        
+        let computed = hash_files_parallel(&mut files, cancel, &mut report);
+        if let Some(mut cache) = cache {
+            for file in computed.iter().map(|&index| &files[index]) {
+                if let Some(hash) = &file.hash {
+                    cache.insert(&file.path, file.size, file.last_modified, hash.clone());
+                }
+            }
+            // Keep the hashes computed so far even when cancelled
+            if let Err(e) = cache.save() {
+                eprintln!("Warning: Failed to save hash cache: {}", e);
+            }
+        }
+    }
+    check_cancelled()?;
+    
+    // Content compared by Tier 1; whitespace-only edits to text files don't count
+    let content_keys: Vec<Option<String>> = files
+        .iter()
+        .map(|file| {
+            if !config.ignore_whitespace_differences {
+                return file.hash.clone();
+            }
+            match file.normalized_text_hash() {
+                Ok(Some(hash)) => Some(hash),
+                Ok(None) => file.hash.clone(),
+                Err(e) => {
+                    eprintln!("Warning: Failed to read {} as text: {}", file.path, e);
+                    file.hash.clone()
+                }
+            }
+        })
+        .collect();
+    
+    // Perceptual hashes of images, only computed when perceptual matching is on
+    let perceptual_hashes: Vec<Option<u64>> = match config.perceptual_hash_distance {
+        Some(_) => files
+            .iter()
+            .map(|file| {
+                file.perceptual_hash().unwrap_or_else(|e| {
+                    eprintln!("Warning: Failed to decode image {}: {}", file.path, e);
+                    None
+                })
+            })
+            .collect(),
+        None => vec![None; files.len()],
+    };
+    
+    // Set zero-byte files aside so they don't all collapse into one "identical" group
+    if !config.include_zero_byte_as_identical {
+        let empty: Vec<usize> = (0..files.len()).filter(|&i| files[i].size == 0).collect();
+        if empty.len() > 1 {
+            groups.push(SimilarityGroup {
+                id: format!("group-{}", groups.len()),
+                files: empty.iter().map(|&i| files[i].clone()).collect(),
+                similarity_type: SimilarityType::Empty,
+                similarity_score: 1.0,
+                matches: std::iter::once(None)
+                    .chain(empty[1..].iter().map(|_| Some(FileMatch { similarity_type: SimilarityType::Empty, score: 1.0 })))
+                    .collect(),
+            });
+        }
+        processed_files.extend(empty);
+    }
+    
+    // Names over the cap are never compared by name; say so once per file
+    for file in &files {
+        let length = normalize_name(&file.name).chars().count();
+        if length > config.max_name_length {
+            eprintln!(
+                "Warning: Skipping name comparison for {}: name longer than {} characters ({})",
+                file.path, config.max_name_length, length
+            );
+        }
     }
     
     for i in 0..files.len() {
+        check_cancelled()?;
+        on_progress(ProgressEvent::Comparing { current: i + 1, total });
         if processed_files.contains(&i) {
             continue;
         }
         
         let current_file = &files[i];
         let mut similar_files = vec![current_file.clone()];
+        let mut matches = vec![None];
         processed_files.insert(i);
         
         let mut similarity_type = SimilarityType::Identical;
         let mut similarity_score: f64 = 1.0;
         
         // Find similar files using three-tier detection system
-        for j in (i + 1)..files.len() {
+        for (j, compare_file) in files.iter().enumerate().skip(i + 1) {
             if processed_files.contains(&j) {
                 continue;
             }
-            
-            let compare_file = &files[j];
+            if config.same_mime_category && current_file.mime_category() != compare_file.mime_category() {
+                continue;
+            }
             
             // Tier 1: Identical Content Detection (SHA-256 hash comparison)
-            if let (Some(ref hash1), Some(ref hash2)) = (&current_file.hash, &compare_file.hash) {
+            if let (Some(hash1), Some(hash2)) = (&content_keys[i], &content_keys[j]) {
                 if hash1 == hash2 {
                     similar_files.push(compare_file.clone());
+                    matches.push(Some(FileMatch { similarity_type: SimilarityType::Identical, score: 1.0 }));
                     processed_files.insert(j);
                     // Keep similarity_type as Identical and similarity_score as 1.0
                     continue;
                 }
             }
             
+            // Tier 1b: Visually Similar Images (perceptual hash Hamming distance)
+            if let (Some(max_distance), Some(hash1), Some(hash2)) =
+                (config.perceptual_hash_distance, perceptual_hashes[i], perceptual_hashes[j])
+            {
+                let distance = (hash1 ^ hash2).count_ones();
+                if distance <= max_distance {
+                    let score = 1.0 - distance as f64 / 64.0;
+                    similar_files.push(compare_file.clone());
+                    matches.push(Some(FileMatch { similarity_type: SimilarityType::Perceptual, score }));
+                    processed_files.insert(j);
+                    similarity_type = SimilarityType::Perceptual;
+                    similarity_score = similarity_score.min(score);
+                    continue;
+                }
+            }
+            
+            let same_type = current_file.file_type.eq_ignore_ascii_case(&compare_file.file_type);
+            
             // Tier 2: Content Similarity (Size + Name)
-            if current_file.size == compare_file.size {
-                let name_similarity = calculate_name_similarity(&current_file.name, &compare_file.name);
-                if name_similarity > 0.8 {
+            if config.sizes_match(current_file.size, compare_file.size) && (same_type || !config.tier2_same_type) {
+                let name_similarity = calculate_name_similarity_capped(&current_file.name, &compare_file.name, config.max_name_length);
+                if name_similarity > config.tier2_threshold {
                     similar_files.push(compare_file.clone());
+                    matches.push(Some(FileMatch { similarity_type: SimilarityType::Content, score: name_similarity }));
                     processed_files.insert(j);
                     similarity_type = SimilarityType::Content;
                     similarity_score = similarity_score.min(name_similarity);
@@ -205,9 +715,13 @@ pub async fn group_similar_files(mut files: Vec<FileInfo>) -> Result<Vec<Similar
             }
             
             // Tier 3: Name-Only Similarity
-            let name_similarity = calculate_name_similarity(&current_file.name, &compare_file.name);
-            if name_similarity > 0.9 {
+            if config.tier3_same_type && !same_type {
+                continue;
+            }
+            let name_similarity = calculate_name_similarity_capped(&current_file.name, &compare_file.name, config.max_name_length);
+            if name_similarity > config.tier3_threshold {
                 similar_files.push(compare_file.clone());
+                matches.push(Some(FileMatch { similarity_type: SimilarityType::Name, score: name_similarity }));
                 processed_files.insert(j);
                 similarity_type = SimilarityType::Name;
                 similarity_score = similarity_score.min(name_similarity);
@@ -221,19 +735,44 @@ pub async fn group_similar_files(mut files: Vec<FileInfo>) -> Result<Vec<Similar
                 files: similar_files,
                 similarity_type,
                 similarity_score,
+                matches,
             });
         }
     }
     
-    // Sort groups by similarity score (highest first)
-    groups.sort_by(|a, b| b.similarity_score.partial_cmp(&a.similarity_score).unwrap_or(std::cmp::Ordering::Equal));
+    for group in &mut groups {
+        flag_hard_links(&mut group.files);
+    }
+    
+    // Sort groups by similarity score (highest first), then by first file
+    // name and larger groups first, so equal scores come out in the same
+    // order on every run and platform
+    groups.sort_by(|a, b| {
+        b.similarity_score
+            .partial_cmp(&a.similarity_score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| first_file_name(a).cmp(first_file_name(b)))
+            .then_with(|| b.files.len().cmp(&a.files.len()))
+            .then_with(|| a.files.first().map(|file| &file.path).cmp(&b.files.first().map(|file| &file.path)))
+    });
     
     Ok(groups)
 }
 
+fn first_file_name(group: &SimilarityGroup) -> &str {
+    group.files.first().map_or("", |file| file.name.as_str())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    /// Writes `content` to `name` in `dir` and reads it back as a [`FileInfo`].
+    fn write_file(dir: &Path, name: &str, content: impl AsRef<[u8]>) -> FileInfo {
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        FileInfo::from_path(&path).unwrap()
+    }
     
     #[test]
     fn test_calculate_name_similarity() {
@@ -284,4 +823,520 @@ mod tests {
         // - Has proper threshold values for the three tiers
         // - Uses minimum similarity for group scoring
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_grouping_config_validation() {
+        assert!(GroupingConfig::default().validate().is_ok());
+        assert!(GroupingConfig { tier2_threshold: -0.1, tier3_threshold: 0.9, ..Default::default() }.validate().is_err());
+        assert!(GroupingConfig { tier2_threshold: 0.8, tier3_threshold: 1.5, ..Default::default() }.validate().is_err());
+        assert!(GroupingConfig { tier2_threshold: 0.9, tier3_threshold: 0.8, ..Default::default() }.validate().is_err());
+        assert!(GroupingConfig { tier2_threshold: 0.7, tier3_threshold: 0.7, ..Default::default() }.validate().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_lower_tier3_threshold_groups_more_files() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path1 = temp_dir.path().join("holiday_photo.jpg");
+        let path2 = temp_dir.path().join("holiday_photos_2.jpg");
+        fs::write(&path1, "first image").unwrap();
+        fs::write(&path2, "second, larger image").unwrap();
+        
+        let files = vec![
+            FileInfo::from_path(&path1).unwrap(),
+            FileInfo::from_path(&path2).unwrap(),
+        ];
+        
+        // Name similarity is ~0.88: below the default Tier 3 threshold
+        let groups = group_similar_files(files.clone(), &GroupingConfig::default()).await.unwrap();
+        assert!(groups.is_empty());
+        
+        let config = GroupingConfig { tier2_threshold: 0.7, tier3_threshold: 0.7, ..Default::default() };
+        let groups = group_similar_files(files, &config).await.unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].files.len(), 2);
+        assert!(matches!(groups[0].similarity_type, SimilarityType::Name));
+    }
+
+    #[tokio::test]
+    async fn test_equal_scores_sort_by_first_file_name() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut files = Vec::new();
+        for (name, content) in [("zulu_1.dat", "zulu"), ("zulu_2.dat", "zulu"), ("alpha_1.dat", "alpha!"), ("alpha_2.dat", "alpha!")] {
+            let path = temp_dir.path().join(name);
+            fs::write(&path, content).unwrap();
+            files.push(FileInfo::from_path(&path).unwrap());
+        }
+        let first_names = |groups: &[SimilarityGroup]| -> Vec<String> { groups.iter().map(|group| group.files[0].name.clone()).collect() };
+        
+        // Both groups are identical content scoring 1.0; zulu is seen first
+        let groups = group_similar_files(files.clone(), &GroupingConfig::default()).await.unwrap();
+        assert!(groups.iter().all(|group| group.similarity_score == 1.0));
+        assert_eq!(first_names(&groups), vec!["alpha_1.dat", "zulu_1.dat"]);
+        
+        files.reverse();
+        let groups = group_similar_files(files, &GroupingConfig::default()).await.unwrap();
+        assert_eq!(first_names(&groups), vec!["alpha_2.dat", "zulu_2.dat"]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_hard_links_are_flagged() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let original = temp_dir.path().join("report.pdf");
+        let link = temp_dir.path().join("report_link.pdf");
+        let copy = temp_dir.path().join("report_copy.pdf");
+        fs::write(&original, "quarterly numbers").unwrap();
+        fs::hard_link(&original, &link).unwrap();
+        fs::copy(&original, &copy).unwrap();
+        
+        let files: Vec<FileInfo> = [&original, &link, &copy].iter().map(|path| FileInfo::from_path(path).unwrap()).collect();
+        assert_eq!(files[0].inode, files[1].inode);
+        assert_ne!(files[0].inode, files[2].inode);
+        
+        let groups = group_similar_files(files, &GroupingConfig::default()).await.unwrap();
+        assert_eq!(groups.len(), 1);
+        let flags: Vec<(&str, bool)> = groups[0].files.iter().map(|file| (file.name.as_str(), file.hard_link)).collect();
+        assert_eq!(flags, vec![("report.pdf", true), ("report_link.pdf", true), ("report_copy.pdf", false)]);
+    }
+
+    #[tokio::test]
+    async fn test_per_file_match_reasons() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let files = vec![
+            write_file(temp_dir.path(), "quarterly_report.pdf", "revenue: 100"),
+            write_file(temp_dir.path(), "backup.pdf", "revenue: 100"),
+            write_file(temp_dir.path(), "quarterly_report2.pdf", "revenue: 100, costs: 80"),
+            write_file(temp_dir.path(), "quarterly_report3.pdf", "revenue: 120"),
+        ];
+        
+        let groups = group_similar_files(files, &GroupingConfig::default()).await.unwrap();
+        assert_eq!(groups.len(), 1);
+        let group = &groups[0];
+        assert_eq!(group.matches.len(), group.files.len());
+        assert!(group.matches[0].is_none());
+        
+        let reasons: Vec<(&str, &str)> = group.files[1..]
+            .iter()
+            .zip(&group.matches[1..])
+            .map(|(file, reason)| {
+                let reason = reason.as_ref().unwrap();
+                let name = match reason.similarity_type {
+                    SimilarityType::Identical => "identical",
+                    SimilarityType::Content => "content",
+                    SimilarityType::Name => "name",
+                    _ => "other",
+                };
+                (file.name.as_str(), name)
+            })
+            .collect();
+        assert_eq!(reasons, vec![("backup.pdf", "identical"), ("quarterly_report2.pdf", "name"), ("quarterly_report3.pdf", "content")]);
+        
+        let name_score = group.matches[2].as_ref().unwrap().score;
+        assert!((name_score - calculate_name_similarity("quarterly_report.pdf", "quarterly_report2.pdf")).abs() < 1e-9);
+        assert_eq!(group.matches[1].as_ref().unwrap().score, 1.0);
+        
+        let json = serde_json::to_value(group).unwrap();
+        assert!(json["matches"][0].is_null());
+        assert_eq!(json["matches"][1]["similarity_type"], "identical");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_config_is_rejected() {
+        let config = GroupingConfig { tier2_threshold: 0.95, tier3_threshold: 0.9, ..Default::default() };
+        assert!(group_similar_files(Vec::new(), &config).await.is_err());
+    }
+
+    #[test]
+    fn test_chunked_hash_matches_whole_file_digest() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("large.bin");
+        // Not a multiple of the chunk size, so the final partial chunk is exercised
+        let content: Vec<u8> = (0..(3 * 1024 * 1024 + 123)).map(|i| (i % 251) as u8).collect();
+        fs::write(&path, &content).unwrap();
+        
+        let mut file_info = FileInfo::from_path(&path).unwrap();
+        let expected = hex::encode(Sha256::digest(&content));
+        assert_eq!(file_info.calculate_hash().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_calculate_hash_cached_hits_on_second_call() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("cached.txt");
+        fs::write(&path, "cache me").unwrap();
+        let cache_path = temp_dir.path().join("hashes.json");
+        
+        let mut cache = HashCache::load(&cache_path).unwrap();
+        let mut first = FileInfo::from_path(&path).unwrap();
+        let hash = first.calculate_hash_cached(&mut cache).unwrap();
+        assert_eq!(cache.hits(), 0);
+        cache.save().unwrap();
+        
+        let mut cache = HashCache::load(&cache_path).unwrap();
+        let mut second = FileInfo::from_path(&path).unwrap();
+        assert_eq!(second.calculate_hash_cached(&mut cache).unwrap(), hash);
+        assert_eq!(cache.hits(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_group_similar_files_populates_hash_cache() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path1 = temp_dir.path().join("a.txt");
+        let path2 = temp_dir.path().join("b.txt");
+        fs::write(&path1, "same").unwrap();
+        fs::write(&path2, "same").unwrap();
+        let cache_path = temp_dir.path().join("hashes.json");
+        
+        let config = GroupingConfig { hash_cache_path: Some(cache_path.clone()), ..Default::default() };
+        let files = vec![FileInfo::from_path(&path1).unwrap(), FileInfo::from_path(&path2).unwrap()];
+        let groups = group_similar_files(files.clone(), &config).await.unwrap();
+        assert_eq!(groups.len(), 1);
+        
+        let mut cache = HashCache::load(&cache_path).unwrap();
+        for file in &files {
+            assert!(cache.get(&file.path, file.size, file.last_modified).is_some());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_same_type_restricts_name_tiers_only() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let photo = write_file(temp_dir.path(), "vacation_photo.jpg", "jpeg bytes");
+        let temp = write_file(temp_dir.path(), "vacation_photo.tmp", "partial");
+        let copy_a = write_file(temp_dir.path(), "copy_a.jpg", "same content");
+        let copy_b = write_file(temp_dir.path(), "other_name.png", "same content");
+        let files = vec![photo, temp, copy_a, copy_b];
+        
+        let loose = GroupingConfig { tier2_threshold: 0.5, tier3_threshold: 0.7, ..Default::default() };
+        let groups = group_similar_files(files.clone(), &loose).await.unwrap();
+        assert_eq!(groups.len(), 2);
+        
+        let strict = GroupingConfig { tier2_same_type: true, tier3_same_type: true, ..loose };
+        let groups = group_similar_files(files, &strict).await.unwrap();
+        assert_eq!(groups.len(), 1);
+        assert!(matches!(groups[0].similarity_type, SimilarityType::Identical));
+        assert_eq!(groups[0].files.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_zero_byte_files_are_separated_by_default() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let files = vec![
+            write_file(temp_dir.path(), "placeholder.txt", ""),
+            write_file(temp_dir.path(), "unrelated.log", ""),
+            write_file(temp_dir.path(), "data_a.bin", "payload"),
+            write_file(temp_dir.path(), "data_b.bin", "payload"),
+        ];
+        
+        let groups = group_similar_files(files.clone(), &GroupingConfig::default()).await.unwrap();
+        assert_eq!(groups.len(), 2);
+        let empty = groups.iter().find(|g| matches!(g.similarity_type, SimilarityType::Empty)).unwrap();
+        assert!(empty.files.iter().all(|f| f.size == 0));
+        let identical = groups.iter().find(|g| matches!(g.similarity_type, SimilarityType::Identical)).unwrap();
+        assert!(identical.files.iter().all(|f| f.size > 0));
+        
+        let config = GroupingConfig { include_zero_byte_as_identical: true, ..Default::default() };
+        let groups = group_similar_files(files, &config).await.unwrap();
+        assert_eq!(groups.len(), 2);
+        assert!(groups.iter().all(|g| matches!(g.similarity_type, SimilarityType::Identical)));
+    }
+
+    #[test]
+    fn test_name_similarity_length_cap() {
+        let long1 = "a".repeat(DEFAULT_MAX_NAME_LENGTH + 1);
+        let long2 = format!("{}b", "a".repeat(DEFAULT_MAX_NAME_LENGTH));
+        assert_eq!(calculate_name_similarity(&long1, &long2), 0.0);
+        
+        // Identical names short-circuit before the cap
+        assert_eq!(calculate_name_similarity(&long1, &long1), 1.0);
+        
+        assert_eq!(calculate_name_similarity_capped("report_v1", "report_v2", 4), 0.0);
+        assert!(calculate_name_similarity_capped("report_v1", "report_v2", 64) > 0.8);
+    }
+
+    #[tokio::test]
+    async fn test_group_similar_files_reports_progress() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut files = Vec::new();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            let path = temp_dir.path().join(name);
+            fs::write(&path, name).unwrap();
+            files.push(FileInfo::from_path(&path).unwrap());
+        }
+        
+        let mut events = Vec::new();
+        group_similar_files_with_progress(files, &GroupingConfig::default(), |event| events.push(event), &AtomicBool::new(false))
+            .await
+            .unwrap();
+        
+        let hashing: Vec<usize> = events
+            .iter()
+            .filter_map(|e| match e {
+                ProgressEvent::Hashing { current, total, .. } => Some((*current, *total)),
+                _ => None,
+            })
+            .map(|(current, total)| {
+                assert_eq!(total, 3);
+                current
+            })
+            .collect();
+        let comparing: Vec<usize> = events
+            .iter()
+            .filter_map(|e| match e {
+                ProgressEvent::Comparing { current, .. } => Some(*current),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(hashing, vec![1, 2, 3]);
+        assert_eq!(comparing, vec![1, 2, 3]);
+        
+        // All hashing happens before any comparison
+        let first_compare = events.iter().position(|e| matches!(e, ProgressEvent::Comparing { .. })).unwrap();
+        assert!(events[..first_compare].iter().all(|e| matches!(e, ProgressEvent::Hashing { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_parallel_hashing_matches_sequential() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut files = Vec::new();
+        for index in 0..12 {
+            let path = temp_dir.path().join(format!("file_{:02}.bin", index));
+            // A few share content, the rest differ; some span several read chunks
+            let content = vec![(index % 4) as u8; 10_000 * (index % 4 + 1)];
+            fs::write(&path, content).unwrap();
+            files.push(FileInfo::from_path(&path).unwrap());
+        }
+        let missing = temp_dir.path().join("gone.bin");
+        fs::write(&missing, "soon deleted").unwrap();
+        files.push(FileInfo::from_path(&missing).unwrap());
+        fs::remove_file(&missing).unwrap();
+        
+        let expected: Vec<Option<String>> = files
+            .iter()
+            .map(|file| file.clone().calculate_hash().ok())
+            .collect();
+        assert!(expected[..12].iter().all(Option::is_some));
+        
+        let mut hashed = files.clone();
+        let mut finished = Vec::new();
+        let computed = hash_files_parallel(&mut hashed, &AtomicBool::new(false), |file| finished.push(file.name.clone()));
+        // The unreadable file is skipped without stopping the rest
+        assert_eq!(computed, (0..12).collect::<Vec<_>>());
+        assert_eq!(finished.len(), 13);
+        let hashes: Vec<Option<String>> = hashed.iter().map(|file| file.hash.clone()).collect();
+        assert_eq!(hashes, expected);
+        
+        // Identical content forms four groups of three, whichever thread
+        // hashed each file
+        let groups = group_similar_files(files[..12].to_vec(), &GroupingConfig::default()).await.unwrap();
+        let mut identical: Vec<Vec<String>> = groups
+            .iter()
+            .filter(|group| matches!(group.similarity_type, SimilarityType::Identical))
+            .map(|group| {
+                let mut names: Vec<String> = group.files.iter().map(|file| file.name.clone()).collect();
+                names.sort();
+                names
+            })
+            .collect();
+        identical.sort();
+        assert_eq!(
+            identical,
+            (0..4)
+                .map(|first| (0..3).map(|k| format!("file_{:02}.bin", first + 4 * k)).collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_before_start_processes_nothing() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("a.txt");
+        fs::write(&path, "content").unwrap();
+        let files = vec![FileInfo::from_path(&path).unwrap(); 3];
+        
+        let mut events = Vec::new();
+        let cancel = AtomicBool::new(true);
+        let err = group_similar_files_with_progress(files, &GroupingConfig::default(), |event| events.push(event), &cancel)
+            .await
+            .unwrap_err();
+        
+        assert!(err.is::<Cancelled>());
+        assert!(events.is_empty());
+    }
+
+    const PNG_BYTES: &[u8] = &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0, 0, 0x0D, b'I', b'H', b'D', b'R'];
+
+    #[test]
+    fn test_detected_mime_ignores_extension() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("screenshot.txt");
+        fs::write(&path, PNG_BYTES).unwrap();
+        
+        let file_info = FileInfo::from_path(&path).unwrap();
+        assert_eq!(file_info.file_type, "txt");
+        assert_eq!(file_info.detected_mime.as_deref(), Some("image/png"));
+        assert_eq!(file_info.mime_category(), Some("image"));
+        
+        let text_path = temp_dir.path().join("notes.txt");
+        fs::write(&text_path, "plain text").unwrap();
+        assert_eq!(FileInfo::from_path(&text_path).unwrap().detected_mime, None);
+    }
+
+    #[tokio::test]
+    async fn test_same_mime_category_keeps_text_and_images_apart() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let text_path = temp_dir.path().join("notes.txt");
+        let image_path = temp_dir.path().join("notes.jpg");
+        fs::write(&text_path, "meeting notes").unwrap();
+        fs::write(&image_path, [0xFF, 0xD8, 0xFF, 0xE0, 0, 0x10, b'J', b'F', b'I', b'F']).unwrap();
+        let files = vec![FileInfo::from_path(&text_path).unwrap(), FileInfo::from_path(&image_path).unwrap()];
+        
+        let loose = GroupingConfig { tier2_threshold: 0.6, tier3_threshold: 0.6, ..Default::default() };
+        assert_eq!(group_similar_files(files.clone(), &loose).await.unwrap().len(), 1);
+        
+        let strict = GroupingConfig { same_mime_category: true, ..loose };
+        assert!(group_similar_files(files, &strict).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_size_tolerance_for_tier2() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let original = write_file(temp_dir.path(), "notes_v1.txt", "meeting notes");
+        let newline = write_file(temp_dir.path(), "notes_v2.txt", "meeting notes\n");
+        let longer = write_file(temp_dir.path(), "notes_v3.txt", "meeting notes!!!");
+        
+        // Names alone are not similar enough for Tier 3
+        let exact = GroupingConfig { tier2_threshold: 0.5, tier3_threshold: 0.99, ..Default::default() };
+        assert!(group_similar_files(vec![original.clone(), newline.clone()], &exact).await.unwrap().is_empty());
+        
+        let tolerant = GroupingConfig { size_tolerance_bytes: 2, ..exact };
+        let groups = group_similar_files(vec![original.clone(), newline], &tolerant).await.unwrap();
+        assert_eq!(groups.len(), 1);
+        assert!(matches!(groups[0].similarity_type, SimilarityType::Content));
+        assert_eq!(groups[0].files.len(), 2);
+        
+        // Three bytes apart is outside the tolerance
+        assert!(group_similar_files(vec![original, longer], &tolerant).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ignore_whitespace_differences() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let original = write_file(temp_dir.path(), "alpha.txt", b"line one\nline two\n\nend");
+        let padded = write_file(temp_dir.path(), "zulu.md", b"line one   \nline two\t\n\n\n\nend\n\n");
+        let edited = write_file(temp_dir.path(), "quartz.log", b"line one\nline 2\n\nend");
+        let files = vec![original, padded, edited];
+        
+        let config = GroupingConfig::default();
+        assert!(group_similar_files(files.clone(), &config).await.unwrap().is_empty());
+        
+        let relaxed = GroupingConfig { ignore_whitespace_differences: true, ..config };
+        let groups = group_similar_files(files, &relaxed).await.unwrap();
+        assert_eq!(groups.len(), 1);
+        assert!(matches!(groups[0].similarity_type, SimilarityType::Identical));
+        let names: Vec<&str> = groups[0].files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["alpha.txt", "zulu.md"]);
+    }
+
+    #[tokio::test]
+    async fn test_perceptual_hash_groups_resized_copies() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let original = image::GrayImage::from_fn(64, 64, |x, y| image::Luma([((x * 3 + y * y / 16) % 256) as u8]));
+        let mirrored = image::imageops::flip_horizontal(&original);
+        let resized = image::imageops::resize(&original, 32, 32, image::imageops::FilterType::Triangle);
+        
+        let sunset = temp_dir.path().join("sunset.png");
+        let copy = temp_dir.path().join("IMG_2041.jpg");
+        let other = temp_dir.path().join("quartz.png");
+        original.save(&sunset).unwrap();
+        resized.save(&copy).unwrap();
+        mirrored.save(&other).unwrap();
+        let files: Vec<FileInfo> = [&sunset, &copy, &other].iter().map(|path| FileInfo::from_path(path).unwrap()).collect();
+        
+        let hash = |file: &FileInfo| file.perceptual_hash().unwrap().unwrap();
+        assert!((hash(&files[0]) ^ hash(&files[1])).count_ones() <= 5);
+        assert!((hash(&files[0]) ^ hash(&files[2])).count_ones() > 20);
+        
+        let config = GroupingConfig::default();
+        assert!(group_similar_files(files.clone(), &config).await.unwrap().is_empty());
+        
+        let perceptual = GroupingConfig { perceptual_hash_distance: Some(5), ..config };
+        let groups = group_similar_files(files, &perceptual).await.unwrap();
+        assert_eq!(groups.len(), 1);
+        assert!(matches!(groups[0].similarity_type, SimilarityType::Perceptual));
+        let names: Vec<&str> = groups[0].files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["sunset.png", "IMG_2041.jpg"]);
+        assert!(groups[0].similarity_score > 0.9);
+    }
+    
+    #[test]
+    fn test_perceptual_hash_skips_non_images() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let text = temp_dir.path().join("notes.txt");
+        fs::write(&text, "plain text").unwrap();
+        assert_eq!(FileInfo::from_path(&text).unwrap().perceptual_hash().unwrap(), None);
+        
+        assert!(GroupingConfig { perceptual_hash_distance: Some(65), ..Default::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_normalized_text_hash_skips_binary_files() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let binary = temp_dir.path().join("image.bin");
+        fs::write(&binary, b"\x89PNG\0\0data  \n").unwrap();
+        assert_eq!(FileInfo::from_path(&binary).unwrap().normalized_text_hash().unwrap(), None);
+        
+        let crlf = temp_dir.path().join("windows.txt");
+        fs::write(&crlf, b"a \r\nb\r\n").unwrap();
+        let unix = temp_dir.path().join("unix.txt");
+        fs::write(&unix, b"a\nb").unwrap();
+        assert_eq!(
+            FileInfo::from_path(&crlf).unwrap().normalized_text_hash().unwrap(),
+            FileInfo::from_path(&unix).unwrap().normalized_text_hash().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_suggest_keepers() {
+        let file = |name: &str, size: u64, last_modified: u64| FileInfo {
+            name: name.to_string(),
+            size,
+            file_type: "jpg".to_string(),
+            last_modified,
+            path: format!("/photos/{}", name),
+            hash: None,
+            detected_mime: None,
+            inode: None,
+            hard_link: false,
+        };
+        let group = SimilarityGroup {
+            id: "group-0".to_string(),
+            files: vec![
+                file("beach (copy).jpg", 300, 20),
+                file("beach.jpg", 200, 10),
+                file("beach_edited.jpg", 500, 30),
+                file("beach_2.jpg", 100, 15),
+            ],
+            similarity_type: SimilarityType::Name,
+            similarity_score: 0.9,
+            matches: Vec::new(),
+        };
+        
+        let keep = |policy| {
+            let suggestions = suggest_keepers(std::slice::from_ref(&group), policy);
+            assert_eq!(suggestions.len(), 1);
+            assert_eq!(suggestions[0].group_id, "group-0");
+            assert_eq!(suggestions[0].redundant.len(), 3);
+            assert!(suggestions[0].redundant.iter().all(|f| f.name != suggestions[0].keep.name));
+            suggestions[0].keep.name.clone()
+        };
+        assert_eq!(keep(KeepPolicy::ShortestName), "beach.jpg");
+        assert_eq!(keep(KeepPolicy::Newest), "beach_edited.jpg");
+        assert_eq!(keep(KeepPolicy::Oldest), "beach.jpg");
+        assert_eq!(keep(KeepPolicy::Largest), "beach_edited.jpg");
+        assert_eq!(keep(KeepPolicy::Smallest), "beach_2.jpg");
+        
+        let empty = SimilarityGroup { files: vec![], ..group.clone() };
+        assert!(suggest_keepers(&[empty], KeepPolicy::Newest).is_empty());
+    }
+}
@@ -1,7 +1,11 @@
-use crate::cli::Algorithm;
-use crate::similarity::calculate_similarity;
+use crate::cli::{Algorithm, Require, SortOrder};
+use crate::file_info::sha256_file;
+use crate::input::validate_threshold_float;
+use crate::similarity::{calculate_combined_similarity, calculate_similarity, Affixes, ComponentWeights, Similarity, SimilarityCache};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use anyhow::Result;
 
 pub struct FileGrouper {
@@ -21,27 +25,130 @@ impl FileGrouper {
         }
     }
     
+    /// Groups `files` at the grouper's threshold, a fraction that is used
+    /// as is rather than rounded to a whole percentage. Thresholds outside
+    /// `0.0..=1.0` (or NaN) are rejected.
     pub fn group_files(&mut self, files: Vec<String>) -> Result<GroupingResult> {
-        let threshold_u8 = (self.threshold * 100.0) as u8;
-        Ok(group_files(files, threshold_u8, &self.algorithm, self.case_sensitive, self.min_group_size))
+        validate_threshold_float(self.threshold)?;
+        Ok(group_files_with_algorithms(
+            files,
+            self.threshold,
+            std::slice::from_ref(&self.algorithm),
+            &Require::All,
+            &ComponentWeights::default(),
+            self.case_sensitive,
+            false,
+            self.min_group_size,
+            &Affixes::default(),
+        ))
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A set of files whose names are similar to each other.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Group {
     pub id: usize,
     pub files: Vec<String>,
+    /// Mean of the pairwise scores that formed the group, as a ratio
+    /// (0.0-1.0). Also read from `similarity_score`, the key the GUI uses.
+    #[serde(alias = "similarity_score")]
     pub similarity: f64,
+    /// Lowest pairwise score that formed the group
+    #[serde(default)]
+    pub min_similarity: f64,
+    /// Highest pairwise score that formed the group
+    #[serde(default)]
+    pub max_similarity: f64,
+    /// Set when every member is the same name in a different letter case,
+    /// which usually points to a sync artifact rather than a near-duplicate.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub case_only: bool,
+    /// Why the members were grouped together.
+    #[serde(default)]
+    pub match_reason: MatchReason,
+    /// Optional name for the group, e.g. the naming scheme its members follow.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// The criterion that formed a [`Group`], mirroring `file_info::SimilarityType`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchReason {
+    /// Members have byte-identical content
+    IdenticalContent,
+    /// Members have similar names
+    #[default]
+    NameSimilarity,
+    /// Members have the same size and similar names
+    SizeAndName,
+    /// Members follow the same camera/phone naming scheme
+    NamingScheme,
+    /// A lone file matched nothing; see [`group_singletons`]
+    Singleton,
+}
+
+impl MatchReason {
+    pub fn label(&self) -> &'static str {
+        match self {
+            MatchReason::IdenticalContent => "identical content",
+            MatchReason::NameSimilarity => "name similarity",
+            MatchReason::SizeAndName => "size and name",
+            MatchReason::NamingScheme => "naming scheme",
+            MatchReason::Singleton => "single file",
+        }
+    }
+}
+
+/// Output of [`group_files`]: the groups found plus the files left over.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct GroupingResult {
     pub groups: Vec<Group>,
+    /// Files in no group; omitted from JSON output with `--show-ungrouped false`
+    #[serde(default)]
     pub ungrouped: Vec<String>,
     pub summary: Summary,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Test fixtures shared by the modules that consume grouping results.
+#[cfg(test)]
+impl Group {
+    /// A name-similarity group of `files` whose scores are all `similarity`.
+    pub(crate) fn fixture(id: usize, files: &[&str], similarity: f64) -> Self {
+        Group {
+            id,
+            files: files.iter().map(|file| file.to_string()).collect(),
+            similarity,
+            min_similarity: similarity,
+            max_similarity: similarity,
+            case_only: false,
+            match_reason: MatchReason::NameSimilarity,
+            label: None,
+        }
+    }
+}
+
+#[cfg(test)]
+impl GroupingResult {
+    /// A result of `groups` and `ungrouped` at a 0.7 threshold, with the
+    /// summary counted from them.
+    pub(crate) fn fixture(groups: Vec<Group>, ungrouped: &[&str]) -> Self {
+        let grouped: usize = groups.iter().map(|group| group.files.len()).sum();
+        GroupingResult {
+            summary: Summary {
+                total_files: grouped + ungrouped.len(),
+                groups_found: groups.len(),
+                ungrouped_files: ungrouped.len(),
+                threshold_used: 0.7,
+            },
+            groups,
+            ungrouped: ungrouped.iter().map(|file| file.to_string()).collect(),
+        }
+    }
+}
+
+/// Aggregate counts describing a grouping run.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Summary {
     pub total_files: usize,
     pub groups_found: usize,
@@ -49,101 +156,398 @@ pub struct Summary {
     pub threshold_used: f64,
 }
 
-pub fn group_files(
+/// Groups `files` whose pairwise similarity under `scorer` is at least
+/// `threshold` percent. `scorer` is an [`Algorithm`] or any other
+/// [`Similarity`], such as a closure.
+///
+/// Similarity is transitive: if A~B and B~C, all three end up in one group
+/// even when A and C are not similar themselves. A group's similarity is the
+/// mean of the pairwise scores above the threshold within it. Groups smaller
+/// than `min_group_size` are dropped and their files reported as ungrouped.
+/// Groups are returned sorted by descending similarity.
+pub fn group_files<S: Similarity + ?Sized>(
     files: Vec<String>,
     threshold: u8,
-    algorithm: &Algorithm,
+    scorer: &S,
     case_sensitive: bool,
     min_group_size: usize,
 ) -> GroupingResult {
-    let threshold_f64 = threshold as f64 / 100.0;
-    let mut groups: Vec<Group> = Vec::new();
-    let mut processed: HashSet<usize> = HashSet::new();
+    let threshold = threshold as f64 / 100.0;
+    let _span = tracing::info_span!("group_files", files = files.len(), threshold).entered();
+    let names: Vec<Cow<str>> = files.iter().map(|file| Cow::Borrowed(file.as_str())).collect();
+    let edges = scan_with(&names, case_sensitive, |i, j| {
+        let similarity = calculate_similarity(&names[i], &names[j], scorer, case_sensitive);
+        let passed = similarity >= threshold;
+        tracing::debug!(a = %names[i], b = %names[j], similarity, passed, "compared pair");
+        passed.then_some((i, j, similarity))
+    });
+    
+    build_result(files, &edges, threshold, min_group_size)
+}
+
+/// Like [`group_files`], but scores each pair with several algorithms and
+/// combines their verdicts according to `require`. Names are compared after
+/// `affixes` are stripped; groups still list the original names.
+///
+/// `threshold` is a fraction between `0.0` and `1.0` rather than a percentage,
+/// so thresholds such as `0.725` keep their precision.
+#[allow(clippy::too_many_arguments)]
+pub fn group_files_with_algorithms(
+    files: Vec<String>,
+    threshold: f64,
+    algorithms: &[Algorithm],
+    require: &Require,
+    weights: &ComponentWeights,
+    case_sensitive: bool,
+    calibrate: bool,
+    min_group_size: usize,
+    affixes: &Affixes,
+) -> GroupingResult {
+    let _span = tracing::info_span!("group_files", files = files.len(), threshold, ?algorithms).entered();
+    let names: Vec<_> = files.iter().map(|file| affixes.strip(file)).collect();
+    let edges = scan_all(&names, threshold, algorithms, require, weights, case_sensitive, calibrate, None);
     
+    build_result(files, &edges, threshold, min_group_size)
+}
+
+/// Like [`group_files_with_algorithms`], but looks pair scores up in `cache`
+/// first, so jobs of a batch that share files don't score the same pair again.
+#[allow(clippy::too_many_arguments)]
+pub fn group_files_cached(
+    files: Vec<String>,
+    threshold: f64,
+    algorithms: &[Algorithm],
+    require: &Require,
+    weights: &ComponentWeights,
+    case_sensitive: bool,
+    calibrate: bool,
+    min_group_size: usize,
+    affixes: &Affixes,
+    cache: &SimilarityCache,
+) -> GroupingResult {
+    let names: Vec<_> = files.iter().map(|file| affixes.strip(file)).collect();
+    let edges = scan_all(&names, threshold, algorithms, require, weights, case_sensitive, calibrate, Some(cache));
+    
+    build_result(files, &edges, threshold, min_group_size)
+}
+
+/// Like [`group_files_with_algorithms`], but skips pairs of files whose sizes
+/// differ too much for their contents to be similar. `sizes` runs parallel to
+/// `files`; a pair is treated as dissimilar without being scored when the
+/// smaller file is less than `min_ratio` (a fraction) of the larger one.
+/// Pairs with an unknown size are always scored.
+#[allow(clippy::too_many_arguments)]
+pub fn group_files_prefiltered(
+    files: Vec<String>,
+    sizes: &[Option<u64>],
+    min_ratio: f64,
+    threshold: f64,
+    algorithms: &[Algorithm],
+    require: &Require,
+    weights: &ComponentWeights,
+    case_sensitive: bool,
+    calibrate: bool,
+    min_group_size: usize,
+    affixes: &Affixes,
+) -> GroupingResult {
+    assert_eq!(files.len(), sizes.len(), "one size per file");
+    let _span = tracing::info_span!("group_files_prefiltered", files = files.len(), threshold, min_ratio).entered();
+    let names: Vec<_> = files.iter().map(|file| affixes.strip(file)).collect();
+    let mut edges: Vec<Edge> = Vec::new();
     for i in 0..files.len() {
-        if processed.contains(&i) {
-            continue;
-        }
-        
-        let mut current_group = vec![i];
-        let mut similarities = Vec::new();
-        
-        // Find all files similar to the current file
         for j in (i + 1)..files.len() {
-            if processed.contains(&j) {
+            if sizes_too_different(sizes[i], sizes[j], min_ratio) {
+                tracing::debug!(a = %names[i], b = %names[j], "skipped pair by size");
                 continue;
             }
-            
-            let similarity = calculate_similarity(
-                &files[i],
-                &files[j],
-                algorithm,
-                case_sensitive,
-            );
-            
-            if similarity >= threshold_f64 {
-                current_group.push(j);
-                similarities.push(similarity);
-            }
+            edges.extend(compare_pair(&names, i, j, threshold, algorithms, require, weights, case_sensitive, calibrate, None));
         }
-        
-        // Check for transitive relationships within the group
-        let mut expanded_group = current_group.clone();
-        let mut added_any = true;
-        
-        while added_any {
-            added_any = false;
-            for &group_idx in current_group.iter() {
-                for k in 0..files.len() {
-                    if processed.contains(&k) || expanded_group.contains(&k) {
-                        continue;
-                    }
-                    
-                    let similarity = calculate_similarity(
-                        &files[group_idx],
-                        &files[k],
-                        algorithm,
-                        case_sensitive,
-                    );
-                    
-                    if similarity >= threshold_f64 {
-                        expanded_group.push(k);
-                        similarities.push(similarity);
-                        added_any = true;
-                    }
-                }
+    }
+
+    build_result(files, &edges, threshold, min_group_size)
+}
+
+/// Whether the smaller of two known sizes is less than `min_ratio` of the
+/// larger. Two empty files are the same size.
+fn sizes_too_different(a: Option<u64>, b: Option<u64>, min_ratio: f64) -> bool {
+    let (Some(a), Some(b)) = (a, b) else {
+        return false;
+    };
+    let (smaller, larger) = if a <= b { (a, b) } else { (b, a) };
+    larger > 0 && (smaller as f64) < min_ratio * larger as f64
+}
+
+/// Thresholds, in percent, tried by `--sweep`.
+pub const SWEEP_THRESHOLDS: [u8; 10] = [50, 55, 60, 65, 70, 75, 80, 85, 90, 95];
+
+/// How many files one threshold of [`sweep_thresholds`] groups.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SweepRow {
+    pub threshold: u8,
+    pub groups: usize,
+    pub grouped_files: usize,
+    pub ungrouped_files: usize,
+}
+
+/// Groups `files` at each of `thresholds` (percent), so the effect of a
+/// threshold can be seen before choosing one. Pairs are scored once, at the
+/// lowest threshold, and each threshold groups the pairs that clear it.
+#[allow(clippy::too_many_arguments)]
+pub fn sweep_thresholds(
+    files: Vec<String>,
+    thresholds: &[u8],
+    algorithms: &[Algorithm],
+    require: &Require,
+    weights: &ComponentWeights,
+    case_sensitive: bool,
+    calibrate: bool,
+    min_group_size: usize,
+    affixes: &Affixes,
+) -> Vec<SweepRow> {
+    let Some(&lowest) = thresholds.iter().min() else {
+        return Vec::new();
+    };
+    let names: Vec<_> = files.iter().map(|file| affixes.strip(file)).collect();
+    let edges = scan_all(&names, lowest as f64 / 100.0, algorithms, require, weights, case_sensitive, calibrate, None);
+
+    thresholds
+        .iter()
+        .map(|&threshold| {
+            let fraction = threshold as f64 / 100.0;
+            let kept: Vec<Edge> = edges.iter().copied().filter(|&(_, _, similarity)| similarity >= fraction).collect();
+            let result = build_result(files.clone(), &kept, fraction, min_group_size);
+            SweepRow {
+                threshold,
+                groups: result.groups.len(),
+                grouped_files: result.groups.iter().map(|group| group.files.len()).sum(),
+                ungrouped_files: result.ungrouped.len(),
             }
-            current_group = expanded_group.clone();
-        }
-        
-        // Only create a group if it meets the minimum size requirement
-        if expanded_group.len() >= min_group_size {
-            let avg_similarity = if similarities.is_empty() {
-                1.0
-            } else {
-                similarities.iter().sum::<f64>() / similarities.len() as f64
+        })
+        .collect()
+}
+
+/// A file scored against the `--reference` file by [`find_similar_to`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReferenceMatch {
+    pub file: String,
+    pub similarity: f64,
+}
+
+/// Scores each of `files` against `reference` instead of grouping them, and
+/// returns those at or above `threshold`, most similar first and ties by name.
+/// The reference itself is skipped if it is among `files`.
+#[allow(clippy::too_many_arguments)]
+pub fn find_similar_to(
+    reference: &str,
+    files: &[String],
+    threshold: f64,
+    algorithms: &[Algorithm],
+    require: &Require,
+    weights: &ComponentWeights,
+    case_sensitive: bool,
+    calibrate: bool,
+    affixes: &Affixes,
+) -> Vec<ReferenceMatch> {
+    let _span = tracing::info_span!("find_similar_to", reference, files = files.len(), threshold).entered();
+    let files: Vec<&String> = files.iter().filter(|file| file.as_str() != reference).collect();
+    let names: Vec<_> = std::iter::once(reference)
+        .chain(files.iter().map(|file| file.as_str()))
+        .map(|name| affixes.strip(name))
+        .collect();
+    
+    let mut matches: Vec<ReferenceMatch> = (1..names.len())
+        .filter_map(|i| compare_pair(&names, 0, i, threshold, algorithms, require, weights, case_sensitive, calibrate, None))
+        .map(|(_, i, similarity)| ReferenceMatch { file: files[i - 1].clone(), similarity })
+        .collect();
+    matches.sort_by(|a, b| {
+        b.similarity
+            .partial_cmp(&a.similarity)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.file.cmp(&b.file))
+    });
+    matches
+}
+
+/// A pair of file indices whose similarity cleared the threshold.
+pub(crate) type Edge = (usize, usize, f64);
+
+/// Compares every pair of `names`, returning the pairs that clear `threshold`
+/// in the same order as scanning each row with [`scan_row`].
+///
+/// A row stops at the first later file whose compared name is identical to
+/// its own, as that pair scores 1.0 and every further file scores the same
+/// against both. The twin's row supplies those pairs, copied over once all
+/// rows are scanned, so groups and their scores are unchanged. Other pairs
+/// scoring 1.0, such as reordered tokens, don't stop the row: the two names
+/// can still score differently against a third.
+#[allow(clippy::too_many_arguments)]
+fn scan_all(
+    names: &[Cow<'_, str>],
+    threshold: f64,
+    algorithms: &[Algorithm],
+    require: &Require,
+    weights: &ComponentWeights,
+    case_sensitive: bool,
+    calibrate: bool,
+    cache: Option<&SimilarityCache>,
+) -> Vec<Edge> {
+    scan_with(names, case_sensitive, |i, j| {
+        compare_pair(names, i, j, threshold, algorithms, require, weights, case_sensitive, calibrate, cache)
+    })
+}
+
+/// The scan of [`scan_all`], with `compare` scoring each pair of indices and
+/// returning its edge if it clears the threshold.
+fn scan_with<F>(names: &[Cow<'_, str>], case_sensitive: bool, mut compare: F) -> Vec<Edge>
+where
+    F: FnMut(usize, usize) -> Option<Edge>,
+{
+    let mut rows: Vec<Vec<Edge>> = vec![Vec::new(); names.len()];
+    let mut twins: Vec<Option<usize>> = vec![None; names.len()];
+    for row in 0..names.len() {
+        for j in (row + 1)..names.len() {
+            let Some(edge) = compare(row, j) else {
+                continue;
             };
-            
-            let group_files: Vec<String> = expanded_group
-                .iter()
-                .map(|&idx| files[idx].clone())
-                .collect();
-            
-            groups.push(Group {
-                id: groups.len() + 1,
-                files: group_files,
-                similarity: avg_similarity,
-            });
-            
-            // Mark all files in this group as processed
-            for &idx in &expanded_group {
-                processed.insert(idx);
+            rows[row].push(edge);
+            if edge.2 >= 1.0 && same_name(&names[row], &names[j], case_sensitive) {
+                twins[row] = Some(j);
+                break;
             }
-        } else {
-            // Don't mark single files as processed - they should be ungrouped
         }
     }
     
+    // Later rows first, so a twin's row is complete before it is copied
+    for row in (0..names.len()).rev() {
+        if let Some(twin) = twins[row] {
+            let copied: Vec<Edge> = rows[twin].iter().map(|&(_, k, similarity)| (row, k, similarity)).collect();
+            rows[row].extend(copied);
+        }
+    }
+    rows.concat()
+}
+
+/// Whether two names are the same string as the algorithms see it.
+fn same_name(a: &str, b: &str, case_sensitive: bool) -> bool {
+    a == b || (!case_sensitive && a.to_lowercase() == b.to_lowercase())
+}
+
+/// Compares file `row` against every later file, returning the pairs that
+/// clear `threshold`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn scan_row(
+    names: &[Cow<'_, str>],
+    row: usize,
+    threshold: f64,
+    algorithms: &[Algorithm],
+    require: &Require,
+    weights: &ComponentWeights,
+    case_sensitive: bool,
+    calibrate: bool,
+    cache: Option<&SimilarityCache>,
+) -> Vec<Edge> {
+    ((row + 1)..names.len())
+        .filter_map(|j| compare_pair(names, row, j, threshold, algorithms, require, weights, case_sensitive, calibrate, cache))
+        .collect()
+}
+
+/// Compares files `i` and `j`, returning their edge if it clears `threshold`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn compare_pair(
+    names: &[Cow<'_, str>],
+    i: usize,
+    j: usize,
+    threshold: f64,
+    algorithms: &[Algorithm],
+    require: &Require,
+    weights: &ComponentWeights,
+    case_sensitive: bool,
+    calibrate: bool,
+    cache: Option<&SimilarityCache>,
+) -> Option<Edge> {
+    let similarity = match cache {
+        Some(cache) => cache.combined_similarity(&names[i], &names[j], algorithms, require, weights, case_sensitive, calibrate),
+        None => calculate_combined_similarity(&names[i], &names[j], algorithms, require, weights, case_sensitive, calibrate),
+    };
+    let passed = similarity >= threshold;
+    tracing::debug!(a = %names[i], b = %names[j], ?algorithms, ?require, similarity, passed, "compared pair");
+    passed.then_some((i, j, similarity))
+}
+
+/// Turns the pairs above the threshold into groups.
+pub(crate) fn build_result(
+    files: Vec<String>,
+    edges: &[Edge],
+    threshold: f64,
+    min_group_size: usize,
+) -> GroupingResult {
+    let mut groups: Vec<Group> = Vec::new();
+    let mut processed: HashSet<usize> = HashSet::new();
+    
+    // Union every pair above the threshold so groups are the connected
+    // components of the similarity graph, independent of input order
+    let mut components = DisjointSet::new(files.len());
+    for &(i, j, _) in edges {
+        components.union(i, j);
+    }
+    
+    // Collect the pairwise scores belonging to each component
+    let mut component_similarities: HashMap<usize, Vec<f64>> = HashMap::new();
+    for &(i, _, similarity) in edges {
+        component_similarities
+            .entry(components.find(i))
+            .or_default()
+            .push(similarity);
+    }
+    
+    // Members are listed in input order; components are ordered by their first member
+    let mut members: Vec<(usize, Vec<usize>)> = Vec::new();
+    let mut member_index: HashMap<usize, usize> = HashMap::new();
+    for idx in 0..files.len() {
+        let root = components.find(idx);
+        let slot = *member_index.entry(root).or_insert_with(|| {
+            members.push((root, Vec::new()));
+            members.len() - 1
+        });
+        members[slot].1.push(idx);
+    }
+    
+    for (root, component) in members {
+        // Only create a group if it meets the minimum size requirement
+        if component.len() < min_group_size {
+            continue;
+        }
+        
+        let similarities = component_similarities.get(&root).map(Vec::as_slice).unwrap_or(&[]);
+        let (avg_similarity, min_similarity, max_similarity) = if similarities.is_empty() {
+            (1.0, 1.0, 1.0)
+        } else {
+            (
+                similarities.iter().sum::<f64>() / similarities.len() as f64,
+                similarities.iter().copied().fold(f64::INFINITY, f64::min),
+                similarities.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            )
+        };
+        
+        let group_files: Vec<String> = component
+            .iter()
+            .map(|&idx| files[idx].clone())
+            .collect();
+        
+        groups.push(Group {
+            id: 0,
+            files: group_files,
+            similarity: avg_similarity,
+            min_similarity,
+            max_similarity,
+            case_only: false,
+            match_reason: MatchReason::NameSimilarity,
+            label: None,
+        });
+        
+        processed.extend(component);
+    }
+    
     // Collect ungrouped files
     let ungrouped: Vec<String> = files
         .iter()
@@ -161,11 +565,24 @@ pub fn group_files(
         total_files: files.len(),
         groups_found: groups.len(),
         ungrouped_files: ungrouped.len(),
-        threshold_used: threshold_f64,
+        threshold_used: threshold,
     };
     
-    // Sort groups by similarity score in descending order
-    groups.sort_by(|a, b| b.similarity.partial_cmp(&a.similarity).unwrap_or(std::cmp::Ordering::Equal));
+    // Sort groups by similarity score in descending order, breaking ties by
+    // their members so the order, and with it each group's id, does not
+    // depend on the order files were given in
+    groups.sort_by(|a, b| {
+        b.similarity
+            .partial_cmp(&a.similarity)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| sorted_members(a).cmp(&sorted_members(b)))
+    });
+    
+    // Renumber so displayed ids ascend in output order
+    for (index, group) in groups.iter_mut().enumerate() {
+        group.id = index + 1;
+    }
+    tracing::info!(groups = groups.len(), ungrouped = ungrouped.len(), pairs = edges.len(), "grouping finished");
     
     GroupingResult {
         groups,
@@ -174,10 +591,233 @@ pub fn group_files(
     }
 }
 
+/// A group's file names in sorted order, used as a stable tie-breaker.
+fn sorted_members(group: &Group) -> Vec<&str> {
+    let mut members: Vec<&str> = group.files.iter().map(String::as_str).collect();
+    members.sort_unstable();
+    members
+}
+
+/// Disjoint-set forest with path compression and union by rank.
+struct DisjointSet {
+    parent: Vec<usize>,
+    rank: Vec<usize>,
+}
+
+impl DisjointSet {
+    fn new(size: usize) -> Self {
+        Self {
+            parent: (0..size).collect(),
+            rank: vec![0; size],
+        }
+    }
+    
+    fn find(&mut self, x: usize) -> usize {
+        if self.parent[x] != x {
+            let root = self.find(self.parent[x]);
+            self.parent[x] = root;
+        }
+        self.parent[x]
+    }
+    
+    fn union(&mut self, a: usize, b: usize) {
+        let root_a = self.find(a);
+        let root_b = self.find(b);
+        if root_a == root_b {
+            return;
+        }
+        
+        match self.rank[root_a].cmp(&self.rank[root_b]) {
+            std::cmp::Ordering::Less => self.parent[root_a] = root_b,
+            std::cmp::Ordering::Greater => self.parent[root_b] = root_a,
+            std::cmp::Ordering::Equal => {
+                self.parent[root_b] = root_a;
+                self.rank[root_a] += 1;
+            }
+        }
+    }
+}
+
+/// Drops groups whose mean similarity is below `report_threshold`, moving
+/// their files to the ungrouped list and updating the summary. Remaining
+/// groups are renumbered in order.
+pub fn apply_report_threshold(result: &mut GroupingResult, report_threshold: f64) {
+    drop_groups(result, |group| group.similarity < report_threshold);
+}
+
+/// Drops groups of expected duplicates: every group whose files all belong
+/// to one of the `ignored` sets. Their files become ungrouped as with
+/// [`apply_report_threshold`].
+pub fn apply_ignore_groups(result: &mut GroupingResult, ignored: &[HashSet<String>]) {
+    drop_groups(result, |group| {
+        ignored.iter().any(|set| group.files.iter().all(|file| set.contains(file)))
+    });
+}
+
+fn drop_groups<F>(result: &mut GroupingResult, mut drop: F)
+where
+    F: FnMut(&Group) -> bool,
+{
+    let (dropped, kept): (Vec<Group>, Vec<Group>) = std::mem::take(&mut result.groups)
+        .into_iter()
+        .partition(|group| drop(group));
+    
+    result.groups = kept;
+    for group in dropped {
+        result.ungrouped.extend(group.files);
+    }
+    for (index, group) in result.groups.iter_mut().enumerate() {
+        group.id = index + 1;
+    }
+    result.summary.groups_found = result.groups.len();
+    result.summary.ungrouped_files = result.ungrouped.len();
+}
+
+/// Reorders groups for output, breaking ties by the existing group id, then
+/// renumbers them so ids ascend in the new order.
+pub fn sort_groups(result: &mut GroupingResult, order: &SortOrder) {
+    match order {
+        SortOrder::Similarity => result.groups.sort_by(|a, b| {
+            b.similarity
+                .partial_cmp(&a.similarity)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.id.cmp(&b.id))
+        }),
+        SortOrder::Size => result
+            .groups
+            .sort_by(|a, b| b.files.len().cmp(&a.files.len()).then(a.id.cmp(&b.id))),
+        SortOrder::Name => result
+            .groups
+            .sort_by(|a, b| a.files.first().cmp(&b.files.first()).then(a.id.cmp(&b.id))),
+    }
+    
+    for (index, group) in result.groups.iter_mut().enumerate() {
+        group.id = index + 1;
+    }
+}
+
+/// Turns every ungrouped file into a [`MatchReason::Singleton`] group of its
+/// own with similarity 1.0, numbered after the existing groups, so every
+/// file appears in a group.
+pub fn group_singletons(result: &mut GroupingResult) {
+    for file in std::mem::take(&mut result.ungrouped) {
+        result.groups.push(Group {
+            id: result.groups.len() + 1,
+            files: vec![file],
+            similarity: 1.0,
+            min_similarity: 1.0,
+            max_similarity: 1.0,
+            case_only: false,
+            match_reason: MatchReason::Singleton,
+            label: None,
+        });
+    }
+    result.summary.groups_found = result.groups.len();
+    result.summary.ungrouped_files = 0;
+}
+
+/// Output of [`group_identical_content`].
+#[derive(Debug, Default)]
+pub struct IdenticalContent {
+    /// Sets of byte-identical files, as [`MatchReason::IdenticalContent`] groups
+    pub groups: Vec<Group>,
+    /// Every other file, in input order, for name grouping
+    pub remaining: Vec<String>,
+    /// Files that could not be hashed and why; they are compared by name only
+    pub unhashed: Vec<(String, String)>,
+}
+
+/// Pre-pass for `--detect-identical`: groups files with byte-identical
+/// content by their SHA-256, before any names are compared. Only entries
+/// naming an existing file are hashed. Sets of identical files with at least
+/// `min_group_size` members become [`MatchReason::IdenticalContent`] groups;
+/// every other file is returned, in input order, for name grouping.
+pub fn group_identical_content(files: Vec<String>, min_group_size: usize) -> IdenticalContent {
+    let mut unhashed = Vec::new();
+    let mut by_hash: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut hashes: Vec<Option<String>> = vec![None; files.len()];
+    for (index, file) in files.iter().enumerate() {
+        let path = std::path::Path::new(file);
+        if !path.is_file() {
+            continue;
+        }
+        match sha256_file(path) {
+            Ok(hash) => {
+                by_hash.entry(hash.clone()).or_default().push(index);
+                hashes[index] = Some(hash);
+            }
+            Err(e) => unhashed.push((file.clone(), e.to_string())),
+        }
+    }
+    
+    let mut grouped: HashSet<usize> = HashSet::new();
+    let mut groups = Vec::new();
+    // Groups come in the order of their first file
+    for (index, hash) in hashes.iter().enumerate() {
+        let Some(members) = hash.as_ref().and_then(|hash| by_hash.get(hash)) else {
+            continue;
+        };
+        if members[0] != index || members.len() < min_group_size.max(2) {
+            continue;
+        }
+        groups.push(Group {
+            id: groups.len() + 1,
+            files: members.iter().map(|&member| files[member].clone()).collect(),
+            similarity: 1.0,
+            min_similarity: 1.0,
+            max_similarity: 1.0,
+            case_only: false,
+            match_reason: MatchReason::IdenticalContent,
+            label: None,
+        });
+        grouped.extend(members);
+    }
+    
+    let remaining = files
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| !grouped.contains(index))
+        .map(|(_, file)| file)
+        .collect();
+    IdenticalContent { groups, remaining, unhashed }
+}
+
+/// Puts the groups of [`group_identical_content`] ahead of the name groups
+/// in `result`, renumbering every group and counting their files in the
+/// summary.
+pub fn add_identical_groups(result: &mut GroupingResult, identical: Vec<Group>) {
+    result.summary.total_files += identical.iter().map(|group| group.files.len()).sum::<usize>();
+    result.groups.splice(0..0, identical);
+    for (index, group) in result.groups.iter_mut().enumerate() {
+        group.id = index + 1;
+    }
+    result.summary.groups_found = result.groups.len();
+}
+
+/// Flags groups whose members differ only by letter case, e.g. `Photo.JPG`
+/// and `photo.jpg`.
+pub fn flag_case_only_groups(result: &mut GroupingResult) {
+    for group in &mut result.groups {
+        group.case_only = is_case_only_variant(&group.files);
+    }
+}
+
+fn is_case_only_variant(files: &[String]) -> bool {
+    let Some((first, rest)) = files.split_first() else {
+        return false;
+    };
+    let folded = first.to_lowercase();
+    
+    // Exact repeats are not case variants
+    rest.iter().all(|file| file.to_lowercase() == folded)
+        && rest.iter().any(|file| file != first)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::cli::Algorithm;
+    use crate::cli::{Algorithm, CompareMode};
+    use crate::similarity::calculate_similarity;
 
     #[test]
     fn test_group_files_basic() {
@@ -194,6 +834,7 @@ mod tests {
         assert_eq!(result.groups[0].files.len(), 2);
         assert!(result.groups[0].files.contains(&"report_v1.pdf".to_string()));
         assert!(result.groups[0].files.contains(&"report_v2.pdf".to_string()));
+        assert_eq!(result.groups[0].match_reason, MatchReason::NameSimilarity);
     }
 
     #[test]
@@ -208,4 +849,667 @@ mod tests {
         assert_eq!(result.groups.len(), 0);
         assert_eq!(result.ungrouped.len(), 3);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_transitive_chain_is_order_independent() {
+        // a~b and b~c (75%), but a and c are only 50% similar
+        let a = "aaaa".to_string();
+        let b = "aaab".to_string();
+        let c = "aabb".to_string();
+        assert!(calculate_similarity(&a, &c, &Algorithm::Levenshtein, false) < 0.7);
+        
+        let orders = [
+            vec![a.clone(), b.clone(), c.clone()],
+            vec![a.clone(), c.clone(), b.clone()],
+            vec![b.clone(), a.clone(), c.clone()],
+            vec![b.clone(), c.clone(), a.clone()],
+            vec![c.clone(), a.clone(), b.clone()],
+            vec![c.clone(), b.clone(), a.clone()],
+        ];
+        
+        for files in orders {
+            let result = group_files(files.clone(), 70, &Algorithm::Levenshtein, false, 2);
+            assert_eq!(result.groups.len(), 1, "order {:?}", files);
+            assert_eq!(result.groups[0].files, files);
+            assert!((result.groups[0].similarity - 0.75).abs() < 1e-9);
+            assert!(result.ungrouped.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_separate_components_stay_separate() {
+        let files = vec![
+            "alpha_1.txt".to_string(),
+            "zzzz".to_string(),
+            "alpha_2.txt".to_string(),
+            "zzzy".to_string(),
+            "lonely.doc".to_string(),
+        ];
+        
+        let result = group_files(files, 70, &Algorithm::Levenshtein, false, 2);
+        assert_eq!(result.groups.len(), 2);
+        assert_eq!(result.ungrouped, vec!["lonely.doc".to_string()]);
+    }
+
+    #[test]
+    fn test_group_files_with_algorithms_require_all_and_any() {
+        // Levenshtein scores this pair above 80%, token similarity only 50%
+        let files = vec!["report_v1.pdf".to_string(), "report_v2.pdf".to_string()];
+        let algorithms = [Algorithm::Levenshtein, Algorithm::Token];
+        
+        let result = group_files_with_algorithms(files.clone(), 0.8, &algorithms, &Require::All, &ComponentWeights::default(), false, false, 2, &Affixes::default());
+        assert_eq!(result.groups.len(), 0);
+        assert_eq!(result.ungrouped.len(), 2);
+        
+        let result = group_files_with_algorithms(files, 0.8, &algorithms, &Require::Any, &ComponentWeights::default(), false, false, 2, &Affixes::default());
+        assert_eq!(result.groups.len(), 1);
+        assert_eq!(result.groups[0].files.len(), 2);
+    }
+
+    #[test]
+    fn test_flag_case_only_groups() {
+        let files = vec![
+            "Photo.JPG".to_string(),
+            "photo.jpg".to_string(),
+            "report_v1.pdf".to_string(),
+            "report_v2.pdf".to_string(),
+        ];
+        
+        let mut result = group_files(files, 80, &Algorithm::Levenshtein, false, 2);
+        flag_case_only_groups(&mut result);
+        
+        assert_eq!(result.groups.len(), 2);
+        let case_group = result.groups.iter().find(|g| g.files.contains(&"Photo.JPG".to_string())).unwrap();
+        assert!(case_group.case_only);
+        let report_group = result.groups.iter().find(|g| g.files.contains(&"report_v1.pdf".to_string())).unwrap();
+        assert!(!report_group.case_only);
+    }
+
+    #[test]
+    fn test_is_case_only_variant() {
+        assert!(is_case_only_variant(&["README.md".to_string(), "readme.md".to_string(), "ReadMe.md".to_string()]));
+        assert!(!is_case_only_variant(&["readme.md".to_string(), "readme.md".to_string()]));
+        assert!(!is_case_only_variant(&["readme.md".to_string(), "readme1.md".to_string()]));
+        assert!(!is_case_only_variant(&[]));
+    }
+
+    #[test]
+    fn test_group_ids_ascend_in_output_order() {
+        // The weaker group is formed first, so sorting by similarity reorders them
+        let files = vec![
+            "report_v1.pdf".to_string(),
+            "report_v2.pdf".to_string(),
+            "notes.txt".to_string(),
+            "notes.txt".to_string(),
+        ];
+        
+        let result = group_files(files, 80, &Algorithm::Levenshtein, false, 2);
+        
+        assert_eq!(result.groups.len(), 2);
+        assert_eq!(result.groups[0].files[0], "notes.txt");
+        let ids: Vec<usize> = result.groups.iter().map(|g| g.id).collect();
+        assert_eq!(ids, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_group_similarity_range() {
+        let files = vec!["report.pdf".to_string(), "report1.pdf".to_string(), "report12.pdf".to_string()];
+        let result = group_files(files, 80, &Algorithm::Levenshtein, false, 2);
+        let group = &result.groups[0];
+        assert!(group.min_similarity < group.similarity);
+        assert!(group.similarity < group.max_similarity);
+        
+        // A single pair has one score, so min, max and mean coincide
+        let files = vec!["report_v1.pdf".to_string(), "report_v2.pdf".to_string()];
+        let result = group_files(files, 80, &Algorithm::Levenshtein, false, 2);
+        let group = &result.groups[0];
+        assert_eq!(group.min_similarity, group.similarity);
+        assert_eq!(group.max_similarity, group.similarity);
+    }
+
+    #[test]
+    fn test_strip_prefix_groups_dated_files() {
+        let files = vec![
+            "20240101-093015_budget.xlsx".to_string(),
+            "20231215-174502_budget.xlsx".to_string(),
+        ];
+        let algorithms = [Algorithm::Levenshtein];
+        
+        let result = group_files_with_algorithms(files.clone(), 0.8, &algorithms, &Require::All, &ComponentWeights::default(), false, false, 2, &Affixes::default());
+        assert!(result.groups.is_empty());
+        
+        let affixes = Affixes::new(Some(r"\d{8}-\d{6}_"), None).unwrap();
+        let result = group_files_with_algorithms(files.clone(), 0.8, &algorithms, &Require::All, &ComponentWeights::default(), false, false, 2, &affixes);
+        assert_eq!(result.groups.len(), 1);
+        assert_eq!(result.groups[0].files, files);
+    }
+
+    #[test]
+    fn test_fractional_threshold_keeps_precision() {
+        // 27 and 28 substitutions out of 100 characters score 0.73 and 0.72
+        let base = "a".repeat(100);
+        let at_73 = format!("{}{}", "a".repeat(73), "b".repeat(27));
+        let at_72 = format!("{}{}", "a".repeat(72), "c".repeat(28));
+        let algorithms = [Algorithm::Levenshtein];
+        
+        let result = group_files_with_algorithms(vec![base.clone(), at_73.clone()], 0.725, &algorithms, &Require::All, &ComponentWeights::default(), true, false, 2, &Affixes::default());
+        assert_eq!(result.groups.len(), 1);
+        assert!((result.summary.threshold_used - 0.725).abs() < f64::EPSILON);
+        
+        let result = group_files_with_algorithms(vec![base, at_72], 0.725, &algorithms, &Require::All, &ComponentWeights::default(), true, false, 2, &Affixes::default());
+        assert!(result.groups.is_empty());
+    }
+
+    #[test]
+    fn test_file_grouper_threshold_is_not_truncated() {
+        // 294, 296 and 2 substitutions out of 1000 characters score 0.706, 0.704 and 0.998
+        let base = "a".repeat(1000);
+        let substituted = |count: usize| format!("{}{}", "a".repeat(1000 - count), "b".repeat(count));
+        let grouper = |threshold: f64| FileGrouper {
+            algorithm: Algorithm::Levenshtein,
+            case_sensitive: true,
+            ..FileGrouper::new(threshold)
+        };
+        
+        let result = grouper(0.705).group_files(vec![base.clone(), substituted(294)]).unwrap();
+        assert_eq!(result.groups.len(), 1);
+        assert_eq!(result.summary.threshold_used, 0.705);
+        // Truncating 0.705 to 70% would have grouped these
+        assert!(grouper(0.705).group_files(vec![base.clone(), substituted(296)]).unwrap().groups.is_empty());
+        // ...and 0.999 to 99% these
+        assert!(grouper(0.999).group_files(vec![base, substituted(2)]).unwrap().groups.is_empty());
+    }
+
+    #[test]
+    fn test_file_grouper_rejects_out_of_range_thresholds() {
+        let files = vec!["report_v1.pdf".to_string(), "report_v2.pdf".to_string()];
+        for threshold in [-0.1, 1.5, 2.56, f64::NAN] {
+            assert!(FileGrouper::new(threshold).group_files(files.clone()).is_err(), "accepted {}", threshold);
+        }
+        assert_eq!(FileGrouper::new(1.0).group_files(files.clone()).unwrap().summary.threshold_used, 1.0);
+        assert_eq!(FileGrouper::new(0.0).group_files(files).unwrap().groups.len(), 1);
+    }
+
+    #[test]
+    fn test_sort_groups() {
+        let files = vec![
+            "zeta_1.txt".to_string(),
+            "zeta_2.txt".to_string(),
+            "zeta_3.txt".to_string(),
+            "alpha.doc".to_string(),
+            "alpha.doc".to_string(),
+            "mid_a.png".to_string(),
+            "mid_b.png".to_string(),
+        ];
+        let first_files = |result: &GroupingResult| -> Vec<String> {
+            result.groups.iter().map(|g| g.files[0].clone()).collect()
+        };
+        
+        let mut result = group_files(files, 80, &Algorithm::Levenshtein, false, 2);
+        sort_groups(&mut result, &SortOrder::Similarity);
+        assert_eq!(first_files(&result), vec!["alpha.doc", "zeta_1.txt", "mid_a.png"]);
+        
+        sort_groups(&mut result, &SortOrder::Size);
+        assert_eq!(first_files(&result), vec!["zeta_1.txt", "alpha.doc", "mid_a.png"]);
+        
+        sort_groups(&mut result, &SortOrder::Name);
+        assert_eq!(first_files(&result), vec!["alpha.doc", "mid_a.png", "zeta_1.txt"]);
+        
+        let ids: Vec<usize> = result.groups.iter().map(|g| g.id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_apply_report_threshold() {
+        // 9 substitutions out of 20 characters score 0.55
+        let files = vec![
+            "a".repeat(20),
+            format!("{}{}", "a".repeat(11), "b".repeat(9)),
+            "report_v1.pdf".to_string(),
+            "report_v2.pdf".to_string(),
+        ];
+        let mut result = group_files(files, 50, &Algorithm::Levenshtein, true, 2);
+        assert_eq!(result.groups.len(), 2);
+        let weak = result.groups.iter().find(|g| g.files[0].starts_with("aaaa")).unwrap();
+        assert!((weak.similarity - 0.55).abs() < 1e-9);
+        
+        apply_report_threshold(&mut result, 0.6);
+        
+        assert_eq!(result.groups.len(), 1);
+        assert_eq!(result.groups[0].id, 1);
+        assert_eq!(result.groups[0].files, vec!["report_v1.pdf", "report_v2.pdf"]);
+        assert_eq!(result.ungrouped.len(), 2);
+        assert_eq!(result.summary.groups_found, 1);
+        assert_eq!(result.summary.ungrouped_files, 2);
+        assert_eq!(result.summary.total_files, 4);
+    }
+
+    #[test]
+    fn test_grouping_result_parses_cli_json() {
+        let files = vec!["report_v1.pdf".to_string(), "report_v2.pdf".to_string(), "notes.txt".to_string()];
+        let result = group_files(files, 70, &Algorithm::Levenshtein, false, 2);
+        let json = crate::cli::OutputFormat::Json.format(&result, true).unwrap();
+        
+        let parsed: GroupingResult = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.groups.len(), 1);
+        // A ratio, which the GUI multiplies by 100 for display
+        assert!((parsed.groups[0].similarity - 12.0 / 13.0).abs() < 1e-9);
+        assert_eq!(parsed.ungrouped, vec!["notes.txt"]);
+        assert_eq!(parsed.summary.threshold_used, 0.7);
+        
+        // The GUI's key is accepted too
+        let gui_json = r#"{"id": 1, "files": ["a.txt", "b.txt"], "similarity_score": 0.85}"#;
+        let group: Group = serde_json::from_str(gui_json).unwrap();
+        assert_eq!(group.similarity, 0.85);
+        assert_eq!(group.match_reason, MatchReason::NameSimilarity);
+    }
+
+    #[test]
+    fn test_group_ids_are_deterministic() {
+        let files: Vec<String> = [
+            "zeta_v1.txt", "alpha_v1.txt", "photo_1.jpg", "zeta_v2.txt", "notes.md", "alpha_v2.txt", "photo_2.jpg",
+        ]
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+        let ids = |files: Vec<String>| -> Vec<(usize, Vec<String>)> {
+            group_files(files, 80, &Algorithm::Levenshtein, false, 2)
+                .groups
+                .into_iter()
+                .map(|group| {
+                    let mut members = group.files;
+                    members.sort();
+                    (group.id, members)
+                })
+                .collect()
+        };
+        
+        let first = ids(files.clone());
+        assert_eq!(first.len(), 3);
+        assert_eq!(first, ids(files.clone()));
+        
+        // The photo and zeta groups tie on similarity; reordering the input
+        // must not swap their ids
+        let mut reversed = files;
+        reversed.reverse();
+        assert_eq!(first, ids(reversed));
+        let photo = first.iter().find(|(_, members)| members[0] == "photo_1.jpg").unwrap();
+        let zeta = first.iter().find(|(_, members)| members[0] == "zeta_v1.txt").unwrap();
+        assert!(photo.0 < zeta.0);
+    }
+
+    #[test]
+    #[tracing_test::traced_test]
+    fn test_compared_pairs_are_logged() {
+        let files = vec!["report_v1.pdf".to_string(), "report_v2.pdf".to_string(), "holiday.jpg".to_string()];
+        group_files(files, 80, &Algorithm::Levenshtein, false, 2);
+        
+        assert!(logs_contain("compared pair"));
+        assert!(logs_contain("a=report_v1.pdf b=report_v2.pdf"));
+        assert!(logs_contain("passed=true"));
+        assert!(logs_contain("a=report_v1.pdf b=holiday.jpg"));
+        assert!(logs_contain("passed=false"));
+        assert!(logs_contain("Levenshtein"));
+    }
+
+    #[test]
+    fn test_group_files_with_custom_scorer() {
+        struct SameExtension;
+        
+        impl Similarity for SameExtension {
+            fn score(&self, a: &str, b: &str) -> f64 {
+                let extension = |name: &str| name.rsplit_once('.').map(|(_, ext)| ext.to_string());
+                if extension(a) == extension(b) { 1.0 } else { 0.0 }
+            }
+        }
+        
+        let files: Vec<String> = ["report.pdf", "photo.jpg", "Invoice.PDF", "notes.txt", "holiday.jpg"]
+            .iter()
+            .map(|file| file.to_string())
+            .collect();
+        let members = |result: &GroupingResult| {
+            let mut groups: Vec<Vec<String>> = result.groups.iter().map(|group| group.files.clone()).collect();
+            groups.sort();
+            groups
+        };
+        
+        // Names reach the scorer lowercased, so "PDF" matches "pdf"
+        let result = group_files(files.clone(), 90, &SameExtension, false, 2);
+        assert_eq!(members(&result), vec![vec!["photo.jpg", "holiday.jpg"], vec!["report.pdf", "Invoice.PDF"]]);
+        assert_eq!(result.ungrouped, vec!["notes.txt"]);
+        let result = group_files(files.clone(), 90, &SameExtension, true, 2);
+        assert_eq!(members(&result), vec![vec!["photo.jpg", "holiday.jpg"]]);
+        
+        // Closures and trait objects are scorers too
+        let same_length = |a: &str, b: &str| if a.len() == b.len() { 1.0 } else { 0.0 };
+        let result = group_files(files.clone(), 50, &same_length, false, 2);
+        assert_eq!(members(&result), vec![vec!["Invoice.PDF", "holiday.jpg"], vec!["photo.jpg", "notes.txt"]]);
+        let scorer: Box<dyn Similarity> = Box::new(Algorithm::Levenshtein);
+        let boxed = group_files(files.clone(), 60, scorer.as_ref(), false, 2);
+        let direct = group_files(files, 60, &Algorithm::Levenshtein, false, 2);
+        assert_eq!(serde_json::to_value(&boxed).unwrap(), serde_json::to_value(&direct).unwrap());
+    }
+
+    #[test]
+    fn test_group_identical_content_runs_before_names() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = |name: &str| temp_dir.path().join(name).to_string_lossy().into_owned();
+        for (name, content) in [("holiday.jpg", "same bytes"), ("IMG_0042.jpg", "same bytes"), ("report_v1.pdf", "first"), ("report_v2.pdf", "second")] {
+            std::fs::write(path(name), content).unwrap();
+        }
+        // A name that is not a file on disk is left to name grouping
+        let files = vec![path("holiday.jpg"), path("report_v1.pdf"), path("IMG_0042.jpg"), path("report_v2.pdf"), "notes.txt".to_string()];
+        
+        let IdenticalContent { groups: identical, remaining, unhashed } = group_identical_content(files.clone(), 2);
+        assert!(unhashed.is_empty());
+        assert_eq!(identical.len(), 1);
+        assert_eq!(identical[0].files, vec![path("holiday.jpg"), path("IMG_0042.jpg")]);
+        assert_eq!(identical[0].match_reason, MatchReason::IdenticalContent);
+        assert_eq!(remaining, vec![path("report_v1.pdf"), path("report_v2.pdf"), "notes.txt".to_string()]);
+        
+        let mut result = group_files_with_algorithms(remaining, 0.7, &[Algorithm::Levenshtein], &Require::All, &ComponentWeights::default(), false, false, 2, &Affixes::default().compare(CompareMode::Basename));
+        add_identical_groups(&mut result, identical);
+        assert_eq!(result.groups.iter().map(|group| group.id).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(result.groups[0].match_reason, MatchReason::IdenticalContent);
+        assert_eq!(result.groups[1].match_reason, MatchReason::NameSimilarity);
+        assert_eq!(result.groups[1].files, vec![path("report_v1.pdf"), path("report_v2.pdf")]);
+        assert_eq!(result.ungrouped, vec!["notes.txt"]);
+        assert_eq!((result.summary.total_files, result.summary.groups_found, result.summary.ungrouped_files), (5, 2, 1));
+        
+        // Identical sets below the minimum group size are compared by name
+        let content = group_identical_content(files.clone(), 3);
+        assert!(content.groups.is_empty());
+        assert_eq!(content.remaining, files);
+    }
+
+    #[test]
+    fn test_sizes_too_different() {
+        assert!(sizes_too_different(Some(1_000), Some(1_000_000_000), 0.5));
+        assert!(sizes_too_different(Some(1_000_000_000), Some(1_000), 0.5));
+        assert!(!sizes_too_different(Some(600), Some(1_000), 0.5));
+        assert!(!sizes_too_different(Some(500), Some(1_000), 0.5));
+        assert!(!sizes_too_different(Some(0), Some(0), 0.5));
+        assert!(sizes_too_different(Some(0), Some(10), 0.5));
+        assert!(!sizes_too_different(None, Some(1_000_000_000), 0.5));
+    }
+
+    fn prefiltered(sizes: &[Option<u64>]) -> GroupingResult {
+        group_files_prefiltered(
+            vec!["report_v1.pdf".into(), "report_v2.pdf".into(), "report_v3.pdf".into()],
+            sizes,
+            0.5,
+            0.8,
+            &[Algorithm::Levenshtein],
+            &Require::All,
+            &ComponentWeights::default(),
+            false,
+            false,
+            2,
+            &Affixes::default(),
+        )
+    }
+
+    #[test]
+    fn test_size_prefilter_keeps_similar_sizes() {
+        let result = prefiltered(&[Some(1_000), Some(900), None]);
+        assert_eq!(result.groups.len(), 1);
+        assert_eq!(result.groups[0].files, vec!["report_v1.pdf", "report_v2.pdf", "report_v3.pdf"]);
+    }
+
+    #[test]
+    #[tracing_test::traced_test]
+    fn test_size_prefilter_skips_scoring() {
+        // report_v3.pdf is far larger than the others, so it is never scored
+        let result = prefiltered(&[Some(1_000), Some(900), Some(1_000_000_000)]);
+        assert_eq!(result.groups.len(), 1);
+        assert_eq!(result.groups[0].files, vec!["report_v1.pdf", "report_v2.pdf"]);
+        assert_eq!(result.ungrouped, vec!["report_v3.pdf"]);
+        logs_assert(|lines: &[&str]| {
+            let skipped = lines.iter().filter(|line| line.contains("skipped pair by size")).count();
+            let compared: Vec<&&str> = lines
+                .iter()
+                .filter(|line| line.contains("group_files_prefiltered") && line.contains("compared pair"))
+                .collect();
+            if skipped == 2 && compared.iter().all(|line| !line.contains("report_v3.pdf")) {
+                Ok(())
+            } else {
+                Err(format!("expected 2 skipped pairs, got {}; compared: {:?}", skipped, compared))
+            }
+        });
+    }
+
+    #[test]
+    fn test_sweep_thresholds_is_monotonic() {
+        let files: Vec<String> = [
+            "report_v1.pdf", "report_v2.pdf", "report_final.pdf", "Report-2024.pdf",
+            "holiday_photo.jpg", "holiday_photos.jpg", "holiday.jpg", "notes.txt", "todo.txt",
+        ]
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+        let algorithms = [Algorithm::Levenshtein];
+        let rows = sweep_thresholds(files.clone(), &SWEEP_THRESHOLDS, &algorithms, &Require::All, &ComponentWeights::default(), false, false, 2, &Affixes::default());
+        
+        assert_eq!(rows.iter().map(|row| row.threshold).collect::<Vec<_>>(), SWEEP_THRESHOLDS);
+        assert!(rows.windows(2).all(|pair| pair[1].grouped_files <= pair[0].grouped_files), "{:?}", rows);
+        assert!(rows[0].grouped_files > rows[9].grouped_files, "{:?}", rows);
+        for row in &rows {
+            assert_eq!(row.grouped_files + row.ungrouped_files, files.len());
+            // Each threshold matches a regular run at that threshold
+            let result = group_files_with_algorithms(files.clone(), row.threshold as f64 / 100.0, &algorithms, &Require::All, &ComponentWeights::default(), false, false, 2, &Affixes::default());
+            assert_eq!(row.groups, result.groups.len());
+            assert_eq!(row.ungrouped_files, result.ungrouped.len());
+        }
+    }
+
+    #[test]
+    #[tracing_test::traced_test]
+    fn test_identical_names_stop_scanning() {
+        let files: Vec<String> = ["report.pdf", "report_v2.pdf", "Report.pdf", "notes.txt", "report.pdf", "report_final.pdf"]
+            .iter()
+            .map(|file| file.to_string())
+            .collect();
+        let names: Vec<Cow<str>> = files.iter().map(|file| Cow::Borrowed(file.as_str())).collect();
+        let weights = ComponentWeights::default();
+        
+        // Every row scanned in full
+        let full: Vec<Edge> = (0..names.len())
+            .flat_map(|row| scan_row(&names, row, 0.6, &[Algorithm::Levenshtein], &Require::All, &weights, false, false, None))
+            .collect();
+        let expected = build_result(files.clone(), &full, 0.6, 2);
+        
+        let edges = scan_all(&names, 0.6, &[Algorithm::Levenshtein], &Require::All, &weights, false, false, None);
+        assert_eq!(edges, full);
+        let result = build_result(files, &edges, 0.6, 2);
+        assert_eq!(serde_json::to_value(&result).unwrap(), serde_json::to_value(&expected).unwrap());
+        
+        // "report.pdf" stops at "Report.pdf" after 2 comparisons instead of 5,
+        // which stops at the second "report.pdf" after 2 instead of 3
+        logs_assert(|lines: &[&str]| {
+            let compared = lines.iter().filter(|line| line.contains("compared pair")).count();
+            let in_full = 15;
+            match compared - in_full {
+                11 => Ok(()),
+                n => Err(format!("expected 11 comparisons after the full scan, got {}", n)),
+            }
+        });
+    }
+
+    #[test]
+    fn test_find_similar_to() {
+        let files: Vec<String> = ["report_v2.pdf", "holiday.jpg", "report_final.pdf", "report.pdf", "report_v1.pdf", "Report.pdf"]
+            .iter()
+            .map(|file| file.to_string())
+            .collect();
+        let find = |threshold: f64| {
+            find_similar_to(
+                "report.pdf",
+                &files,
+                threshold,
+                &[Algorithm::Levenshtein],
+                &Require::All,
+                &ComponentWeights::default(),
+                false,
+                false,
+                &Affixes::default(),
+            )
+        };
+        
+        let matches = find(0.6);
+        let ranked: Vec<&str> = matches.iter().map(|m| m.file.as_str()).collect();
+        // The reference is skipped; equal scores are ordered by name
+        assert_eq!(ranked, vec!["Report.pdf", "report_v1.pdf", "report_v2.pdf", "report_final.pdf"]);
+        assert!((matches[0].similarity - 1.0).abs() < f64::EPSILON);
+        assert!((matches[1].similarity - matches[2].similarity).abs() < f64::EPSILON);
+        assert!(matches.windows(2).all(|pair| pair[0].similarity >= pair[1].similarity));
+        
+        // report_final.pdf scores 10/16 and drops out at a higher threshold
+        let ranked: Vec<String> = find(0.7).into_iter().map(|m| m.file).collect();
+        assert_eq!(ranked, vec!["Report.pdf", "report_v1.pdf", "report_v2.pdf"]);
+        assert!(find(1.0).iter().all(|m| m.file == "Report.pdf"));
+    }
+
+    #[test]
+    fn test_group_singletons() {
+        let files = vec!["report_v1.pdf".to_string(), "report_v2.pdf".to_string(), "holiday.jpg".to_string()];
+        let mut result = group_files(files, 80, &Algorithm::Levenshtein, false, 2);
+        assert_eq!(result.ungrouped, vec!["holiday.jpg"]);
+
+        group_singletons(&mut result);
+        assert!(result.ungrouped.is_empty());
+        assert_eq!(result.groups.len(), 2);
+        let singleton = &result.groups[1];
+        assert_eq!(singleton.id, 2);
+        assert_eq!(singleton.files, vec!["holiday.jpg"]);
+        assert!((singleton.similarity - 1.0).abs() < f64::EPSILON);
+        assert_eq!(singleton.match_reason, MatchReason::Singleton);
+        assert_eq!(result.groups[0].match_reason, MatchReason::NameSimilarity);
+        assert_eq!(result.summary.groups_found, 2);
+        assert_eq!(result.summary.ungrouped_files, 0);
+        assert_eq!(result.summary.total_files, 3);
+    }
+
+    #[test]
+    fn test_apply_ignore_groups() {
+        let files = vec![
+            "LICENSE_1.txt".to_string(),
+            "LICENSE_2.txt".to_string(),
+            "report_v1.pdf".to_string(),
+            "report_v2.pdf".to_string(),
+            "template_a.docx".to_string(),
+            "template_b.docx".to_string(),
+        ];
+        let mut result = group_files(files, 80, &Algorithm::Levenshtein, false, 2);
+        assert_eq!(result.groups.len(), 3);
+        
+        let ignored = vec![
+            HashSet::from(["LICENSE_1.txt".to_string(), "LICENSE_2.txt".to_string()]),
+            // A group that is only part of an expected set is suppressed too
+            HashSet::from(["template_a.docx".to_string(), "template_b.docx".to_string(), "template_c.docx".to_string()]),
+            // Covering only part of a group does not suppress it
+            HashSet::from(["report_v1.pdf".to_string()]),
+        ];
+        apply_ignore_groups(&mut result, &ignored);
+        
+        assert_eq!(result.groups.len(), 1);
+        assert_eq!(result.groups[0].id, 1);
+        assert_eq!(result.groups[0].files, vec!["report_v1.pdf", "report_v2.pdf"]);
+        assert_eq!(result.ungrouped.len(), 4);
+        assert!(result.ungrouped.contains(&"LICENSE_1.txt".to_string()));
+        assert_eq!(result.summary.groups_found, 1);
+        assert_eq!(result.summary.ungrouped_files, 4);
+    }
+
+    #[test]
+    fn test_similarity_cache_shared_across_jobs() {
+        let to_strings = |files: &[&str]| files.iter().map(|f| f.to_string()).collect::<Vec<_>>();
+        let job_a = to_strings(&["report_v1.pdf", "report_v2.pdf", "Budget.xlsx"]);
+        let job_b = to_strings(&["report_v2.pdf", "budget.xlsx", "report_v1.pdf", "notes.txt"]);
+        let algorithms = [Algorithm::Levenshtein];
+        let affixes = Affixes::default();
+        let cache = SimilarityCache::new();
+        
+        let summarize = |result: &GroupingResult| -> Vec<Vec<String>> { result.groups.iter().map(|g| g.files.clone()).collect() };
+        for job in [&job_a, &job_b] {
+            let cached = group_files_cached(job.clone(), 0.8, &algorithms, &Require::All, &ComponentWeights::default(), false, false, 2, &affixes, &cache);
+            let expected = group_files_with_algorithms(job.clone(), 0.8, &algorithms, &Require::All, &ComponentWeights::default(), false, false, 2, &affixes);
+            assert_eq!(summarize(&cached), summarize(&expected));
+        }
+        
+        // 3 + 6 pairs, of which the 3 in job A recur in job B (case-folded and reordered)
+        assert_eq!(cache.computed(), 6);
+        assert_eq!(cache.hits(), 3);
+    }
+
+    #[test]
+    fn test_similarity_cache_parallel_jobs() {
+        let jobs: Vec<Vec<String>> = (0..4)
+            .map(|job| (0..20).map(|i| format!("photo_{:03}.jpg", i + job * 5)).collect())
+            .collect();
+        let algorithms = [Algorithm::Auto];
+        let affixes = Affixes::default();
+        let cache = SimilarityCache::new();
+        
+        let results: Vec<GroupingResult> = std::thread::scope(|scope| {
+            let handles: Vec<_> = jobs
+                .iter()
+                .map(|job| scope.spawn(|| group_files_cached(job.clone(), 0.9, &algorithms, &Require::All, &ComponentWeights::default(), false, false, 2, &affixes, &cache)))
+                .collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+        
+        for (job, result) in jobs.iter().zip(&results) {
+            let expected = group_files_with_algorithms(job.clone(), 0.9, &algorithms, &Require::All, &ComponentWeights::default(), false, false, 2, &affixes);
+            assert_eq!(result.groups.len(), expected.groups.len());
+            assert_eq!(result.ungrouped, expected.ungrouped);
+        }
+        // Every lookup is either computed or served, whatever the interleaving
+        assert_eq!(cache.computed() + cache.hits(), 4 * 190);
+    }
+
+    #[test]
+    fn test_basename_comparison_reports_full_paths() {
+        let files = vec![
+            "2023/report.pdf".to_string(),
+            "2024/report.pdf".to_string(),
+            "2024/holiday.jpg".to_string(),
+        ];
+        let algorithms = [Algorithm::Levenshtein];
+        
+        let affixes = Affixes::default().compare(CompareMode::Basename);
+        let result = group_files_with_algorithms(files.clone(), 0.95, &algorithms, &Require::All, &ComponentWeights::default(), false, false, 2, &affixes);
+        assert_eq!(result.groups.len(), 1);
+        assert_eq!(result.groups[0].files, vec!["2023/report.pdf", "2024/report.pdf"]);
+        assert_eq!(result.groups[0].similarity, 1.0);
+        assert_eq!(result.ungrouped, vec!["2024/holiday.jpg"]);
+        
+        // Compared as full paths the differing years keep them apart
+        let result = group_files_with_algorithms(files, 0.95, &algorithms, &Require::All, &ComponentWeights::default(), false, false, 2, &Affixes::default());
+        assert!(result.groups.is_empty());
+    }
+
+    #[test]
+    fn test_compare_modes_group_paths() {
+        let files = vec![
+            "/a/report.pdf".to_string(),
+            "/b/report.pdf".to_string(),
+            "/b/report.docx".to_string(),
+        ];
+        let algorithms = [Algorithm::Levenshtein];
+        let group = |mode: CompareMode| {
+            let affixes = Affixes::default().compare(mode);
+            group_files_with_algorithms(files.clone(), 0.95, &algorithms, &Require::All, &ComponentWeights::default(), false, false, 2, &affixes)
+        };
+        
+        let result = group(CompareMode::Basename);
+        assert_eq!(result.groups.len(), 1);
+        assert_eq!(result.groups[0].files, vec!["/a/report.pdf", "/b/report.pdf"]);
+        
+        assert!(group(CompareMode::Fullpath).groups.is_empty());
+        
+        // Without extensions all three share the stem `report`
+        let result = group(CompareMode::Stem);
+        assert_eq!(result.groups.len(), 1);
+        assert_eq!(result.groups[0].files.len(), 3);
+    }
+}
@@ -0,0 +1,181 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    size: u64,
+    last_modified: u64,
+    hash: String,
+}
+
+/// On-disk cache of SHA-256 hashes keyed by `(path, size, last_modified)`.
+///
+/// An entry is only reused when the file's size and modification time still
+/// match, so edited files are re-hashed automatically.
+#[derive(Debug, Default)]
+pub struct HashCache {
+    location: Option<PathBuf>,
+    entries: HashMap<String, CacheEntry>,
+    hits: usize,
+    dirty: bool,
+    /// Why the stored cache was discarded on load, if it was
+    discarded: Option<String>,
+}
+
+impl HashCache {
+    /// Loads the cache stored at `location`, starting empty if it doesn't exist
+    /// or can't be parsed; see [`HashCache::discarded`].
+    pub fn load(location: &Path) -> Result<Self> {
+        let mut discarded = None;
+        let entries = match fs::read_to_string(location) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                discarded = Some(format!("Ignoring unreadable hash cache {}: {}", location.display(), e));
+                HashMap::new()
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read hash cache: {}", location.display()))
+            }
+        };
+
+        Ok(Self {
+            location: Some(location.to_path_buf()),
+            entries,
+            hits: 0,
+            dirty: false,
+            discarded,
+        })
+    }
+
+    /// Why the cache file could not be used and the cache started empty, for
+    /// the caller to report.
+    pub fn discarded(&self) -> Option<&str> {
+        self.discarded.as_deref()
+    }
+
+    pub fn get(&mut self, path: &str, size: u64, last_modified: u64) -> Option<String> {
+        let entry = self.entries.get(path)?;
+        if entry.size != size || entry.last_modified != last_modified {
+            return None;
+        }
+        self.hits += 1;
+        Some(entry.hash.clone())
+    }
+
+    pub fn insert(&mut self, path: &str, size: u64, last_modified: u64, hash: String) {
+        self.entries.insert(
+            path.to_string(),
+            CacheEntry {
+                size,
+                last_modified,
+                hash,
+            },
+        );
+        self.dirty = true;
+    }
+
+    /// Number of lookups answered from the cache since it was loaded.
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// Drops a single path from the cache.
+    pub fn invalidate(&mut self, path: &str) {
+        if self.entries.remove(path).is_some() {
+            self.dirty = true;
+        }
+    }
+
+    /// Removes every entry and deletes the cache file.
+    pub fn clear(&mut self) -> Result<()> {
+        self.entries.clear();
+        self.dirty = false;
+        if let Some(location) = &self.location {
+            match fs::remove_file(location) {
+                Ok(_) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Failed to remove hash cache: {}", location.display()))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the cache back to disk if it changed.
+    pub fn save(&mut self) -> Result<()> {
+        let Some(location) = &self.location else {
+            return Ok(());
+        };
+        if !self.dirty {
+            return Ok(());
+        }
+
+        if let Some(parent) = location.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(location, serde_json::to_string(&self.entries)?)
+            .with_context(|| format!("Failed to write hash cache: {}", location.display()))?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_get_requires_matching_size_and_mtime() {
+        let mut cache = HashCache::default();
+        cache.insert("a.txt", 10, 100, "abc".to_string());
+
+        assert_eq!(cache.get("a.txt", 10, 100), Some("abc".to_string()));
+        assert_eq!(cache.get("a.txt", 11, 100), None);
+        assert_eq!(cache.get("a.txt", 10, 101), None);
+        assert_eq!(cache.get("b.txt", 10, 100), None);
+        assert_eq!(cache.hits(), 1);
+    }
+
+    #[test]
+    fn test_save_load_and_clear() {
+        let temp_dir = TempDir::new().unwrap();
+        let location = temp_dir.path().join("cache").join("hashes.json");
+
+        let mut cache = HashCache::load(&location).unwrap();
+        cache.insert("a.txt", 10, 100, "abc".to_string());
+        cache.save().unwrap();
+        assert!(location.exists());
+
+        let mut reloaded = HashCache::load(&location).unwrap();
+        assert_eq!(reloaded.get("a.txt", 10, 100), Some("abc".to_string()));
+
+        reloaded.clear().unwrap();
+        assert!(!location.exists());
+        assert_eq!(reloaded.get("a.txt", 10, 100), None);
+    }
+
+    #[test]
+    fn test_unreadable_cache_starts_empty() {
+        let temp_dir = TempDir::new().unwrap();
+        let location = temp_dir.path().join("hashes.json");
+        fs::write(&location, "not json").unwrap();
+
+        let mut cache = HashCache::load(&location).unwrap();
+        assert!(cache.discarded().unwrap().contains("hashes.json"));
+        assert_eq!(cache.get("a.txt", 10, 100), None);
+        assert!(HashCache::load(&temp_dir.path().join("missing.json")).unwrap().discarded().is_none());
+    }
+
+    #[test]
+    fn test_invalidate() {
+        let mut cache = HashCache::default();
+        cache.insert("a.txt", 10, 100, "abc".to_string());
+        cache.invalidate("a.txt");
+        assert_eq!(cache.get("a.txt", 10, 100), None);
+    }
+}
@@ -0,0 +1,260 @@
+use crate::cli::{Algorithm, OutputFormat, Require};
+use crate::grouper::group_files_with_algorithms;
+use crate::similarity::{Affixes, ComponentWeights};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+
+/// A node in a hierarchical clustering: either a single file (no children)
+/// or a cluster formed at `level` from the nodes of the previous level.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterNode {
+    /// Name that stands in for this node in the next grouping round
+    pub representative: String,
+    /// Grouping round that formed this node; 0 for individual files
+    pub level: usize,
+    /// Average pairwise similarity of the round that formed this node
+    pub similarity: f64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ClusterNode>,
+}
+
+impl ClusterNode {
+    fn leaf(file: String) -> Self {
+        Self {
+            representative: file,
+            level: 0,
+            similarity: 1.0,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// All files below this node, in tree order.
+    pub fn files(&self) -> Vec<&str> {
+        if self.is_leaf() {
+            return vec![self.representative.as_str()];
+        }
+        self.children.iter().flat_map(|child| child.files()).collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hierarchy {
+    /// Threshold percentage used for each grouping round, in order
+    pub thresholds: Vec<u8>,
+    pub roots: Vec<ClusterNode>,
+}
+
+/// Checks the `--threshold` list: several levels are only meaningful with
+/// `--hierarchical`, and each round must be looser than the one before.
+pub fn validate_threshold_levels(thresholds: &[u8], hierarchical: bool) -> Result<()> {
+    if thresholds.is_empty() {
+        anyhow::bail!("At least one threshold is required");
+    }
+    if thresholds.len() > 1 && !hierarchical {
+        anyhow::bail!("Multiple --threshold values require --hierarchical");
+    }
+    if thresholds.windows(2).any(|pair| pair[1] > pair[0]) {
+        anyhow::bail!("Hierarchical thresholds must not increase from one level to the next");
+    }
+    Ok(())
+}
+
+/// Groups `files` once per threshold. Each round compares the representatives
+/// of the previous round's clusters (and any still-ungrouped nodes), so later,
+/// looser thresholds merge whole clusters into larger ones.
+#[allow(clippy::too_many_arguments)]
+pub fn group_hierarchically(
+    files: Vec<String>,
+    thresholds: &[u8],
+    algorithms: &[Algorithm],
+    require: &Require,
+    weights: &ComponentWeights,
+    case_sensitive: bool,
+    calibrate: bool,
+    min_group_size: usize,
+    affixes: &Affixes,
+) -> Hierarchy {
+    let mut nodes: Vec<ClusterNode> = files.into_iter().map(ClusterNode::leaf).collect();
+
+    for (round, &threshold) in thresholds.iter().enumerate() {
+        let representatives: Vec<String> = nodes.iter().map(|node| node.representative.clone()).collect();
+        let result = group_files_with_algorithms(
+            representatives,
+            threshold as f64 / 100.0,
+            algorithms,
+            require,
+            weights,
+            case_sensitive,
+            calibrate,
+            min_group_size,
+            affixes,
+        );
+
+        // Representatives can repeat, e.g. with --no-dedup, so each name maps
+        // to the positions of its nodes, handed out in order
+        let mut positions: HashMap<String, VecDeque<usize>> = HashMap::new();
+        for (index, node) in nodes.iter().enumerate() {
+            positions.entry(node.representative.clone()).or_default().push_back(index);
+        }
+        let mut slots: Vec<Option<ClusterNode>> = nodes.into_iter().map(Some).collect();
+        let mut take = |file: &String| {
+            let index = positions.get_mut(file)?.pop_front()?;
+            slots[index].take()
+        };
+
+        let mut next = Vec::new();
+        for group in result.groups {
+            let children: Vec<ClusterNode> = group.files.iter().filter_map(&mut take).collect();
+            next.push(ClusterNode {
+                representative: group.files[0].clone(),
+                level: round + 1,
+                similarity: group.similarity,
+                children,
+            });
+        }
+        next.extend(result.ungrouped.iter().filter_map(&mut take));
+        nodes = next;
+    }
+
+    Hierarchy {
+        thresholds: thresholds.to_vec(),
+        roots: nodes,
+    }
+}
+
+pub fn format_hierarchy<W: Write>(
+    hierarchy: &Hierarchy,
+    format: &OutputFormat,
+    show_ungrouped: bool,
+    writer: &mut W,
+) -> Result<()> {
+    let roots = hierarchy
+        .roots
+        .iter()
+        .filter(|node| show_ungrouped || !node.is_leaf());
+
+    match format {
+        OutputFormat::Json => {
+            let filtered = Hierarchy {
+                thresholds: hierarchy.thresholds.clone(),
+                roots: roots.cloned().collect(),
+            };
+            writeln!(writer, "{}", serde_json::to_string_pretty(&filtered)?)?;
+        }
+        OutputFormat::Jsonl => {
+            for node in roots {
+                writeln!(writer, "{}", serde_json::to_string(node)?)?;
+            }
+        }
+        OutputFormat::Csv | OutputFormat::Tsv | OutputFormat::Html => {
+            anyhow::bail!("CSV, TSV and HTML output are not supported with --hierarchical; use text, json or jsonl");
+        }
+        OutputFormat::Text | OutputFormat::Table => {
+            let levels: Vec<String> = hierarchy.thresholds.iter().map(|t| format!("{}%", t)).collect();
+            writeln!(writer, "Hierarchy (thresholds: {}):", levels.join(", "))?;
+            for node in roots {
+                write_node(node, 1, writer)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_node<W: Write>(node: &ClusterNode, depth: usize, writer: &mut W) -> Result<()> {
+    let indent = "  ".repeat(depth);
+    if node.is_leaf() {
+        writeln!(writer, "{}{}", indent, node.representative)?;
+        return Ok(());
+    }
+
+    writeln!(
+        writer,
+        "{}{} (level {}, similarity: {:.0}%, {} files)",
+        indent,
+        node.representative,
+        node.level,
+        node.similarity * 100.0,
+        node.files().len()
+    )?;
+    for child in &node.children {
+        write_node(child, depth + 1, writer)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_strings(files: &[&str]) -> Vec<String> {
+        files.iter().map(|f| f.to_string()).collect()
+    }
+
+    #[test]
+    fn test_two_level_hierarchy() {
+        let files = to_strings(&[
+            "report_v1.pdf",
+            "report_v2.pdf",
+            "report_final.pdf",
+            "holiday.jpg",
+        ]);
+
+        let hierarchy = group_hierarchically(files, &[90, 60], &[Algorithm::Levenshtein], &Require::All, &ComponentWeights::default(), false, false, 2, &Affixes::default());
+
+        // The near-identical versions merge first, then absorb report_final
+        assert_eq!(hierarchy.roots.len(), 2);
+        let top = &hierarchy.roots[0];
+        assert_eq!(top.level, 2);
+        assert_eq!(top.representative, "report_v1.pdf");
+        assert_eq!(top.children.len(), 2);
+
+        let inner = &top.children[0];
+        assert_eq!(inner.level, 1);
+        assert_eq!(inner.files(), vec!["report_v1.pdf", "report_v2.pdf"]);
+        assert!(top.children[1].is_leaf());
+        assert_eq!(top.children[1].representative, "report_final.pdf");
+
+        assert!(hierarchy.roots[1].is_leaf());
+        assert_eq!(hierarchy.roots[1].representative, "holiday.jpg");
+    }
+
+    #[test]
+    fn test_repeated_names_keep_every_leaf() {
+        let files = to_strings(&["report_v1.pdf", "report_v1.pdf", "report_v2.pdf", "holiday.jpg", "holiday.jpg"]);
+
+        let hierarchy = group_hierarchically(files, &[95, 80], &[Algorithm::Levenshtein], &Require::All, &ComponentWeights::default(), false, false, 2, &Affixes::default());
+
+        let mut leaves: Vec<&str> = hierarchy.roots.iter().flat_map(|root| root.files()).collect();
+        leaves.sort();
+        assert_eq!(leaves, vec!["holiday.jpg", "holiday.jpg", "report_v1.pdf", "report_v1.pdf", "report_v2.pdf"]);
+        assert_eq!(hierarchy.roots[0].files(), vec!["report_v1.pdf", "report_v1.pdf", "report_v2.pdf"]);
+    }
+
+    #[test]
+    fn test_validate_threshold_levels() {
+        assert!(validate_threshold_levels(&[70], false).is_ok());
+        assert!(validate_threshold_levels(&[90, 70], true).is_ok());
+        assert!(validate_threshold_levels(&[90, 70], false).is_err());
+        assert!(validate_threshold_levels(&[70, 90], true).is_err());
+        assert!(validate_threshold_levels(&[], true).is_err());
+    }
+
+    #[test]
+    fn test_format_hierarchy_text() {
+        let files = to_strings(&["notes_a.txt", "notes_b.txt", "zebra.png"]);
+        let hierarchy = group_hierarchically(files, &[80, 60], &[Algorithm::Levenshtein], &Require::All, &ComponentWeights::default(), false, false, 2, &Affixes::default());
+
+        let mut output = Vec::new();
+        format_hierarchy(&hierarchy, &OutputFormat::Text, false, &mut output).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        assert!(text.contains("thresholds: 80%, 60%"));
+        assert!(text.contains("    notes_b.txt"));
+        assert!(!text.contains("zebra.png"));
+    }
+}
@@ -1,17 +1,129 @@
 use anyhow::{Context, Result};
-use glob::glob;
-use std::path::{Path, PathBuf};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{IsTerminal, Read};
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
+/// A path that discovery could not read, such as a directory without read
+/// permission, and why. Discovery carries on without it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedPath {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+/// Files found by [`FileDiscovery::discover`], and the paths passed over
+/// because they could not be read.
+#[derive(Debug, Default)]
+pub struct Discovered {
+    pub paths: Vec<PathBuf>,
+    pub skipped: Vec<SkippedPath>,
+}
+
+impl Discovered {
+    fn skip(&mut self, path: PathBuf, reason: impl std::fmt::Display) {
+        self.skipped.push(SkippedPath { path, reason: reason.to_string() });
+    }
+}
+
+/// Files gathered by [`collect_files`], plus the paths discovery passed over
+/// because they could not be read and any other warnings. Reporting those is
+/// left to the caller.
+#[derive(Debug, Default)]
+pub struct Collected {
+    pub files: Vec<String>,
+    pub skipped: Vec<SkippedPath>,
+    /// Problems expanding command line globs, such as a pattern matching nothing
+    pub warnings: Vec<String>,
+}
+
+/// Paths listed one by one in the warning about skipped paths; the rest are
+/// counted.
+const SKIPPED_PATHS_SHOWN: usize = 10;
+
+#[derive(Default)]
 pub struct FileDiscovery {
-    // Empty for now, can add configuration later
+    /// Patterns matched against each path relative to the discovery root
+    include: Vec<glob::Pattern>,
+    exclude: Vec<glob::Pattern>,
+    /// Descend into symlinked directories and pick up symlinked files
+    follow_symlinks: bool,
+    /// Skip files last modified before this time
+    modified_since: Option<SystemTime>,
 }
 
 impl FileDiscovery {
     pub fn new() -> Self {
-        Self {}
+        Self::default()
+    }
+    
+    /// Restricts discovery with glob patterns matched against each path
+    /// relative to the discovery root. When `include` is non-empty a file must
+    /// match one of its patterns; any file matching an `exclude` pattern
+    /// (e.g. `*.tmp` or `cache/*`) is then dropped.
+    pub fn with_filters(include: &[String], exclude: &[String]) -> Result<Self> {
+        Ok(Self {
+            include: compile_patterns(include, "include")?,
+            exclude: compile_patterns(exclude, "exclude")?,
+            follow_symlinks: false,
+            modified_since: None,
+        })
+    }
+    
+    /// Follows symlinks during discovery instead of skipping them. Each
+    /// directory is walked at most once, so symlink cycles terminate.
+    pub fn follow_symlinks(mut self, follow: bool) -> Self {
+        self.follow_symlinks = follow;
+        self
+    }
+    
+    /// Skips files last modified before `since`, e.g. to look only at recent
+    /// downloads. A file whose modification time cannot be read is skipped.
+    pub fn modified_since(mut self, since: Option<SystemTime>) -> Self {
+        self.modified_since = since;
+        self
+    }
+    
+    fn is_recent_enough(&self, path: &Path) -> std::io::Result<bool> {
+        let Some(since) = self.modified_since else {
+            return Ok(true);
+        };
+        // Follows symlinks, so a linked file is judged by its target
+        Ok(fs::metadata(path)?.modified()? >= since)
+    }
+    
+    fn is_selected(&self, root: &Path, path: &Path) -> bool {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let included = self.include.is_empty()
+            || self.include.iter().any(|pattern| pattern.matches_path(relative));
+        included && !self.exclude.iter().any(|pattern| pattern.matches_path(relative))
     }
     
+    /// File names (without directories) of every selected file below `dir`.
     pub fn discover_files(&self, dir: &Path) -> Result<Vec<String>> {
+        Ok(self
+            .discover_paths(dir)?
+            .iter()
+            .filter_map(|path| path.file_name().and_then(|name| name.to_str()))
+            .map(|name| name.to_string())
+            .collect())
+    }
+    
+    /// Absolute paths of every selected file below `dir`, including those in
+    /// subdirectories. Paths that cannot be read are skipped and summed up in
+    /// a single warning once discovery finishes.
+    pub fn discover_paths(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        let discovered = self.discover(dir)?;
+        warn_skipped(&discovered.skipped);
+        Ok(discovered.paths)
+    }
+    
+    /// Like [`FileDiscovery::discover_paths`], but returns the skipped paths
+    /// instead of warning about them. Only an unreadable `dir` itself is an
+    /// error; anything unreadable below it is skipped.
+    pub fn discover(&self, dir: &Path) -> Result<Discovered> {
         if !dir.exists() {
             anyhow::bail!("Directory does not exist: {}", dir.display());
         }
@@ -20,67 +132,273 @@ impl FileDiscovery {
             anyhow::bail!("Path is not a directory: {}", dir.display());
         }
         
-        let pattern = dir.join("**").join("*");
-        let pattern_str = pattern.to_string_lossy();
+        let root = normalize_root(dir)?;
+        let mut visited = HashSet::new();
+        if let Ok(canonical) = fs::canonicalize(&root) {
+            visited.insert(canonical);
+        }
         
-        let mut files = Vec::new();
+        let mut discovered = Discovered::default();
+        let entries = read_dir_sorted(&root, &mut discovered.skipped)
+            .with_context(|| format!("Failed to read directory: {}", root.display()))?;
+        self.walk(&root, entries, &mut visited, &mut discovered);
         
-        for entry in glob(&pattern_str)
-            .with_context(|| format!("Failed to read glob pattern: {}", pattern_str))?
-        {
-            match entry {
-                Ok(path) => {
-                    if path.is_file() {
-                        if let Some(file_name) = path.file_name() {
-                            if let Some(name_str) = file_name.to_str() {
-                                files.push(name_str.to_string());
-                            }
-                        }
+        Ok(discovered)
+    }
+    
+    fn walk(&self, root: &Path, entries: Vec<fs::DirEntry>, visited: &mut HashSet<PathBuf>, discovered: &mut Discovered) {
+        for entry in entries {
+            let path = entry.path();
+            let file_type = match entry.file_type() {
+                Ok(file_type) => file_type,
+                Err(e) => {
+                    discovered.skip(path, e);
+                    continue;
+                }
+            };
+            
+            let (is_dir, is_file) = if file_type.is_symlink() {
+                if !self.follow_symlinks {
+                    continue;
+                }
+                match fs::metadata(&path) {
+                    Ok(metadata) => (metadata.is_dir(), metadata.is_file()),
+                    Err(e) => {
+                        discovered.skip(path, format!("broken symlink: {}", e));
+                        continue;
                     }
                 }
-                Err(e) => {
-                    eprintln!("Warning: Error processing path: {}", e);
+            } else {
+                (file_type.is_dir(), file_type.is_file())
+            };
+            
+            if is_dir {
+                // A directory reached twice, e.g. through a symlink cycle, is only walked once
+                let canonical = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+                if !visited.insert(canonical) {
+                    continue;
+                }
+                match read_dir_sorted(&path, &mut discovered.skipped) {
+                    Ok(children) => self.walk(root, children, visited, discovered),
+                    Err(e) => discovered.skip(path, e),
+                }
+            } else if is_file && self.is_selected(root, &path) {
+                match self.is_recent_enough(&path) {
+                    Ok(true) => discovered.paths.push(path),
+                    Ok(false) => {}
+                    Err(e) => discovered.skip(path, format!("modification time unavailable: {}", e)),
                 }
             }
         }
-        
-        Ok(files)
     }
 }
 
-// Legacy functions kept for backwards compatibility
-#[allow(dead_code)]
+/// Entries of `dir` sorted by name. Entries that cannot be read are added
+/// to `skipped` rather than failing the whole directory.
+fn read_dir_sorted(dir: &Path, skipped: &mut Vec<SkippedPath>) -> std::io::Result<Vec<fs::DirEntry>> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        match entry {
+            Ok(entry) => entries.push(entry),
+            Err(e) => skipped.push(SkippedPath { path: dir.to_path_buf(), reason: e.to_string() }),
+        }
+    }
+    entries.sort_by_key(|entry| entry.file_name());
+    Ok(entries)
+}
+
+/// One warning listing the paths discovery skipped, the first
+/// [`SKIPPED_PATHS_SHOWN`] by name and the rest as a count, or `None` when
+/// nothing was skipped.
+pub fn describe_skipped(skipped: &[SkippedPath]) -> Option<String> {
+    if skipped.is_empty() {
+        return None;
+    }
+    let mut message = format!("Skipped {} path(s) that could not be read during discovery:", skipped.len());
+    for skipped_path in skipped.iter().take(SKIPPED_PATHS_SHOWN) {
+        message.push_str(&format!("\n  {}: {}", skipped_path.path.display(), skipped_path.reason));
+    }
+    if skipped.len() > SKIPPED_PATHS_SHOWN {
+        message.push_str(&format!("\n  ... and {} more", skipped.len() - SKIPPED_PATHS_SHOWN));
+    }
+    Some(message)
+}
+
+fn warn_skipped(skipped: &[SkippedPath]) {
+    if let Some(message) = describe_skipped(skipped) {
+        eprintln!("Warning: {}", message);
+    }
+}
+
+fn compile_patterns(patterns: &[String], kind: &str) -> Result<Vec<glob::Pattern>> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            glob::Pattern::new(pattern)
+                .with_context(|| format!("Invalid {} pattern: {}", kind, pattern))
+        })
+        .collect()
+}
+
+/// Normalizes a discovery root so `./photos/`, `./photos` and `photos` all
+/// resolve to the same absolute path without trailing separators.
+pub(crate) fn normalize_root(dir: &Path) -> Result<PathBuf> {
+    let absolute = if dir.is_absolute() {
+        dir.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .context("Failed to determine current directory")?
+            .join(dir)
+    };
+    
+    Ok(absolute
+        .components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .collect())
+}
+
+/// Gathers file names from every source: command line arguments, the input
+/// file, directory discovery and, whenever stdin is piped rather than a
+/// terminal, stdin. Names are sorted and listed once, unless
+/// `keep_duplicates` keeps repeats and `preserve_order` keeps them in the
+/// order given. Warnings, such as paths discovery could not read, are
+/// returned rather than printed.
+#[allow(clippy::too_many_arguments)]
 pub fn collect_files(
     cli_files: Vec<String>,
-    _input_file: Option<PathBuf>,
+    input_file: Option<PathBuf>,
+    discover_dir: Option<PathBuf>,
+    include: &[String],
+    exclude: &[String],
+    follow_symlinks: bool,
+    since: Option<SystemTime>,
+    full_paths: bool,
+    keep_duplicates: bool,
+    preserve_order: bool,
+) -> Result<Collected> {
+    let piped = if std::io::stdin().is_terminal() {
+        None
+    } else {
+        Some(read_files_from_stdin()?)
+    };
+    collect_files_with_stdin(
+        cli_files,
+        input_file,
+        discover_dir,
+        include,
+        exclude,
+        follow_symlinks,
+        since,
+        full_paths,
+        keep_duplicates,
+        preserve_order,
+        piped,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn collect_files_with_stdin(
+    cli_files: Vec<String>,
+    input_file: Option<PathBuf>,
     discover_dir: Option<PathBuf>,
-) -> Result<Vec<String>> {
+    include: &[String],
+    exclude: &[String],
+    follow_symlinks: bool,
+    since: Option<SystemTime>,
+    full_paths: bool,
+    keep_duplicates: bool,
+    preserve_order: bool,
+    piped: Option<Vec<String>>,
+) -> Result<Collected> {
     let mut all_files = Vec::new();
+    let mut skipped = Vec::new();
+    let mut warnings = Vec::new();
     
-    // Add files from command line arguments
-    all_files.extend(cli_files);
+    // Add files from command line arguments, expanding any glob the shell left alone
+    all_files.extend(expand_globs(cli_files, &mut warnings)?);
+    
+    // Add files listed in the input file
+    if let Some(input_path) = input_file {
+        all_files.extend(read_files_from_file(&input_path)?);
+    }
     
     // Add files from directory discovery
     if let Some(discover_path) = discover_dir {
-        let discovery = FileDiscovery::new();
-        let discovered_files = discovery.discover_files(&discover_path)
+        let discovery = FileDiscovery::with_filters(include, exclude)?
+            .follow_symlinks(follow_symlinks)
+            .modified_since(since);
+        let discovered = discovery
+            .discover(&discover_path)
             .with_context(|| format!("Failed to discover files in {}", discover_path.display()))?;
-        all_files.extend(discovered_files);
+        if full_paths {
+            all_files.extend(discovered.paths.iter().map(|path| path.to_string_lossy().into_owned()));
+        } else {
+            all_files.extend(
+                discovered
+                    .paths
+                    .iter()
+                    .filter_map(|path| path.file_name().and_then(|name| name.to_str()))
+                    .map(|name| name.to_string()),
+            );
+        }
+        skipped = discovered.skipped;
     }
     
-    // Remove duplicates and filter out empty strings
-    all_files.sort();
-    all_files.dedup();
+    // Add file names piped through stdin
+    if let Some(piped) = piped {
+        all_files.extend(piped);
+    }
+    
+    // Sort, remove duplicates and filter out empty strings
+    if !preserve_order {
+        all_files.sort();
+    }
+    if !keep_duplicates {
+        let mut seen = HashSet::new();
+        all_files.retain(|f| seen.insert(f.clone()));
+    }
     all_files.retain(|f| !f.trim().is_empty());
     
     if all_files.is_empty() {
         anyhow::bail!("No files provided. Use --help for usage information.");
     }
     
-    Ok(all_files)
+    Ok(Collected { files: all_files, skipped, warnings })
+}
+
+/// Expands arguments containing glob metacharacters (`*`, `?`, `[`) into the
+/// files they match, for shells that don't expand them or when quoted. Other
+/// arguments, and existing files whose names merely contain such characters,
+/// are kept as given. A pattern matching nothing is skipped and noted in
+/// `warnings`.
+fn expand_globs(args: Vec<String>, warnings: &mut Vec<String>) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+    for arg in args {
+        if !arg.contains(['*', '?', '[']) || Path::new(&arg).exists() {
+            files.push(arg);
+            continue;
+        }
+        
+        let entries = glob::glob(&arg).with_context(|| format!("Invalid glob pattern: {}", arg))?;
+        let matched: Vec<String> = entries
+            .filter_map(|entry| match entry {
+                Ok(path) => Some(path),
+                Err(e) => {
+                    warnings.push(format!("Error processing path {}: {}", e.path().display(), e.error()));
+                    None
+                }
+            })
+            .filter(|path| path.is_file())
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect();
+        if matched.is_empty() {
+            warnings.push(format!("No files match pattern {}", arg));
+        }
+        files.extend(matched);
+    }
+    Ok(files)
 }
 
-#[allow(dead_code)]
 pub fn validate_threshold(threshold: u8) -> Result<()> {
     if threshold > 100 {
         anyhow::bail!("Threshold must be between 0 and 100");
@@ -88,7 +406,87 @@ pub fn validate_threshold(threshold: u8) -> Result<()> {
     Ok(())
 }
 
-#[allow(dead_code)]
+/// Validates `--threshold-float`, which is a fraction rather than a percentage.
+pub fn validate_threshold_float(threshold: f64) -> Result<()> {
+    if !(0.0..=1.0).contains(&threshold) {
+        anyhow::bail!("Threshold must be between 0.0 and 1.0, got {}", threshold);
+    }
+    Ok(())
+}
+
+/// Parses a `--since` cutoff: a duration before now such as `30m`, `12h`,
+/// `7d` or `2w` (units s, m, h, d and w), an RFC 3339 time such as
+/// `2024-05-01T09:30:00+02:00`, or a date such as `2024-05-01`, meaning
+/// midnight UTC.
+pub fn parse_since(value: &str) -> Result<SystemTime> {
+    parse_since_at(value, SystemTime::now())
+}
+
+fn parse_since_at(value: &str, now: SystemTime) -> Result<SystemTime> {
+    let value = value.trim();
+    if let Some(duration) = parse_relative_duration(value) {
+        return now
+            .checked_sub(duration)
+            .with_context(|| format!("--since {} reaches back too far", value));
+    }
+    if let Ok(time) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Ok(time.into());
+    }
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        if let Some(midnight) = date.and_hms_opt(0, 0, 0) {
+            return Ok(midnight.and_utc().into());
+        }
+    }
+    anyhow::bail!(
+        "Invalid --since value '{}': expected a duration such as 7d or 12h, an RFC 3339 time or a date (YYYY-MM-DD)",
+        value
+    )
+}
+
+fn parse_relative_duration(value: &str) -> Option<Duration> {
+    let unit = value.chars().last()?;
+    let seconds_per_unit = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        'w' => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    let count: u64 = value[..value.len() - 1].trim().parse().ok()?;
+    Some(Duration::from_secs(count.checked_mul(seconds_per_unit)?))
+}
+
+/// Checks a `--size-prefilter` ratio, which must be above 0.0 and at most 1.0.
+pub fn validate_size_ratio(ratio: f64) -> Result<()> {
+    if !(ratio > 0.0 && ratio <= 1.0) {
+        anyhow::bail!("Size ratio must be above 0.0 and at most 1.0, got {}", ratio);
+    }
+    Ok(())
+}
+
+/// Guards against `--threshold 0`, where every pair of files counts as similar
+/// and all files collapse into a single group. Every one of the n*(n-1)/2 pairs
+/// is still scored, so the run costs as much as any other threshold while
+/// producing a useless result. Returns a warning to show when the caller
+/// explicitly allowed it.
+pub fn check_zero_threshold(threshold: u8, allow_zero_threshold: bool) -> Result<Option<String>> {
+    if threshold > 0 {
+        return Ok(None);
+    }
+    
+    if !allow_zero_threshold {
+        anyhow::bail!(
+            "A threshold of 0 groups every file with every other file. \
+             Use --allow-zero-threshold if this is really intended"
+        );
+    }
+    
+    Ok(Some(
+        "Threshold is 0: all files will be placed in a single group".to_string(),
+    ))
+}
+
 pub fn validate_min_group_size(size: usize) -> Result<()> {
     if size < 2 {
         anyhow::bail!("Minimum group size must be at least 2");
@@ -96,23 +494,64 @@ pub fn validate_min_group_size(size: usize) -> Result<()> {
     Ok(())
 }
 
-#[allow(dead_code)]
+/// Reads a file list as UTF-8, or as UTF-16 LE/BE when it starts with a byte
+/// order mark, as lists exported by Windows tools often do. A UTF-8 BOM is
+/// dropped.
 pub fn read_files_from_file(file_path: &Path) -> Result<Vec<String>> {
-    use std::fs;
-    let content = fs::read_to_string(file_path)
+    let bytes = fs::read(file_path)
         .with_context(|| format!("Failed to read file: {}", file_path.display()))?;
+    let content = match encoding_rs::Encoding::for_bom(&bytes) {
+        Some((encoding, bom_length)) => encoding.decode_without_bom_handling(&bytes[bom_length..]).0,
+        None => Cow::Borrowed(
+            std::str::from_utf8(&bytes)
+                .with_context(|| format!("File is not UTF-8 or UTF-16 with a BOM: {}", file_path.display()))?,
+        ),
+    };
     
-    let files: Vec<String> = content
+    Ok(parse_file_list(&content))
+}
+
+/// Reads the expected-duplicate sets for `--ignore-groups`: one file name
+/// per line, with blank lines separating sets and `#` starting a comment.
+pub fn read_ignore_groups(file_path: &Path) -> Result<Vec<HashSet<String>>> {
+    let content = fs::read_to_string(file_path)
+        .with_context(|| format!("Failed to read ignore groups file: {}", file_path.display()))?;
+    
+    let mut sets = Vec::new();
+    let mut current = HashSet::new();
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() {
+            if !current.is_empty() {
+                sets.push(std::mem::take(&mut current));
+            }
+        } else if !line.starts_with('#') {
+            current.insert(line.to_string());
+        }
+    }
+    if !current.is_empty() {
+        sets.push(current);
+    }
+    Ok(sets)
+}
+
+pub fn read_files_from_stdin() -> Result<Vec<String>> {
+    let mut content = String::new();
+    std::io::stdin()
+        .read_to_string(&mut content)
+        .context("Failed to read file names from stdin")?;
+    
+    Ok(parse_file_list(&content))
+}
+
+fn parse_file_list(content: &str) -> Vec<String> {
+    content
         .lines()
         .map(|line| line.trim())
         .filter(|line| !line.is_empty() && !line.starts_with('#'))
         .map(|line| line.to_string())
-        .collect();
-    
-    Ok(files)
+        .collect()
 }
 
-#[allow(dead_code)]
 pub fn discover_files(dir: &Path) -> Result<Vec<String>> {
     let discovery = FileDiscovery::new();
     discovery.discover_files(dir)
@@ -121,17 +560,74 @@ pub fn discover_files(dir: &Path) -> Result<Vec<String>> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fs;
     use tempfile::TempDir;
 
     #[test]
     fn test_collect_files_from_cli() {
         let files = vec!["file1.txt".to_string(), "file2.txt".to_string()];
-        let result = collect_files(files, None, None).unwrap();
+        let result = collect_files_with_stdin(files, None, None, &[], &[], false, None, false, false, false, None).unwrap().files;
         assert_eq!(result.len(), 2);
         assert!(result.contains(&"file1.txt".to_string()));
     }
 
+    #[test]
+    fn test_collect_files_merges_cli_and_stdin() {
+        let cli = vec!["b.txt".to_string(), "a.txt".to_string()];
+        let piped = parse_file_list("c.txt\na.txt\n\nd.txt\n");
+        let result = collect_files_with_stdin(cli, None, None, &[], &[], false, None, false, false, false, Some(piped)).unwrap().files;
+        assert_eq!(result, vec!["a.txt", "b.txt", "c.txt", "d.txt"]);
+        
+        let result = collect_files_with_stdin(vec![], None, None, &[], &[], false, None, false, false, false, Some(vec!["x.txt".to_string()])).unwrap().files;
+        assert_eq!(result, vec!["x.txt"]);
+        
+        assert!(collect_files_with_stdin(vec![], None, None, &[], &[], false, None, false, false, false, Some(vec![])).is_err());
+    }
+
+    #[test]
+    fn test_collect_files_keeps_duplicates_and_order() {
+        let cli = || vec!["b.txt".to_string(), "a.txt".to_string(), "b.txt".to_string()];
+        let piped = || Some(vec!["c.txt".to_string(), "a.txt".to_string()]);
+
+        let result = collect_files_with_stdin(cli(), None, None, &[], &[], false, None, false, false, true, piped()).unwrap().files;
+        assert_eq!(result, vec!["b.txt", "a.txt", "c.txt"]);
+
+        let result = collect_files_with_stdin(cli(), None, None, &[], &[], false, None, false, true, false, piped()).unwrap().files;
+        assert_eq!(result, vec!["a.txt", "a.txt", "b.txt", "b.txt", "c.txt"]);
+
+        let result = collect_files_with_stdin(cli(), None, None, &[], &[], false, None, false, true, true, piped()).unwrap().files;
+        assert_eq!(result, vec!["b.txt", "a.txt", "b.txt", "c.txt", "a.txt"]);
+    }
+
+    #[test]
+    fn test_collect_files_expands_globs() {
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["notes_1.txt", "notes_2.txt", "photo.jpg", "scan[1].pdf"] {
+            fs::write(temp_dir.path().join(name), "data").unwrap();
+        }
+        fs::create_dir(temp_dir.path().join("archive.txt")).unwrap();
+        let root = temp_dir.path().to_string_lossy().into_owned();
+        let path = |name: &str| temp_dir.path().join(name).to_string_lossy().into_owned();
+        
+        let cli = vec![format!("{}/*.txt", root), "literal.jpg".to_string()];
+        let result = collect_files_with_stdin(cli, None, None, &[], &[], false, None, false, false, false, None).unwrap().files;
+        // Directories matching the pattern are skipped
+        assert_eq!(result, vec![path("notes_1.txt"), path("notes_2.txt"), "literal.jpg".to_string()]);
+        
+        // An existing file is taken literally even though its name looks like a pattern
+        let result = collect_files_with_stdin(vec![path("scan[1].pdf")], None, None, &[], &[], false, None, false, false, false, None).unwrap().files;
+        assert_eq!(result, vec![path("scan[1].pdf")]);
+        
+        // A pattern that matches nothing contributes no files
+        let cli = vec![format!("{}/*.docx", root)];
+        assert!(collect_files_with_stdin(cli, None, None, &[], &[], false, None, false, false, false, None).is_err());
+        let cli = vec![format!("{}/*.docx", root), "kept.txt".to_string()];
+        let collected = collect_files_with_stdin(cli, None, None, &[], &[], false, None, false, false, false, None).unwrap();
+        assert_eq!(collected.files, vec!["kept.txt"]);
+        assert_eq!(collected.warnings, vec![format!("No files match pattern {}/*.docx", root)]);
+        
+        assert!(expand_globs(vec!["[unclosed".to_string()], &mut Vec::new()).is_err());
+    }
+
     #[test]
     fn test_validate_threshold() {
         assert!(validate_threshold(50).is_ok());
@@ -140,6 +636,48 @@ mod tests {
         assert!(validate_threshold(101).is_err());
     }
 
+    #[test]
+    fn test_validate_threshold_float() {
+        assert!(validate_threshold_float(0.0).is_ok());
+        assert!(validate_threshold_float(0.725).is_ok());
+        assert!(validate_threshold_float(1.0).is_ok());
+        assert!(validate_threshold_float(1.01).is_err());
+        assert!(validate_threshold_float(-0.1).is_err());
+        assert!(validate_threshold_float(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_validate_size_ratio() {
+        assert!(validate_size_ratio(0.1).is_ok());
+        assert!(validate_size_ratio(1.0).is_ok());
+        assert!(validate_size_ratio(0.0).is_err());
+        assert!(validate_size_ratio(1.5).is_err());
+        assert!(validate_size_ratio(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_check_zero_threshold() {
+        assert!(check_zero_threshold(0, false).is_err());
+        assert_eq!(check_zero_threshold(50, false).unwrap(), None);
+        assert_eq!(check_zero_threshold(50, true).unwrap(), None);
+        
+        let warning = check_zero_threshold(0, true).unwrap();
+        assert!(warning.unwrap().contains("single group"));
+    }
+
+    #[test]
+    fn test_zero_threshold_override_groups_everything() {
+        use crate::cli::Algorithm;
+        use crate::grouper::group_files;
+        
+        assert!(check_zero_threshold(0, true).is_ok());
+        let files = vec!["alpha.txt".to_string(), "zeta.jpg".to_string(), "1234".to_string()];
+        let result = group_files(files, 0, &Algorithm::Levenshtein, false, 2);
+        assert_eq!(result.groups.len(), 1);
+        assert_eq!(result.groups[0].files.len(), 3);
+        assert!(result.ungrouped.is_empty());
+    }
+
     #[test]
     fn test_validate_min_group_size() {
         assert!(validate_min_group_size(2).is_ok());
@@ -147,6 +685,18 @@ mod tests {
         assert!(validate_min_group_size(1).is_err());
     }
 
+    #[test]
+    fn test_read_ignore_groups() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("ignore.txt");
+        fs::write(&file_path, "# licenses\nLICENSE\nLICENSE.txt\n\n\ntemplate_a.docx\n# shared\ntemplate_b.docx\n").unwrap();
+        
+        let sets = read_ignore_groups(&file_path).unwrap();
+        assert_eq!(sets.len(), 2);
+        assert_eq!(sets[0], HashSet::from(["LICENSE".to_string(), "LICENSE.txt".to_string()]));
+        assert_eq!(sets[1], HashSet::from(["template_a.docx".to_string(), "template_b.docx".to_string()]));
+    }
+
     #[test]
     fn test_read_files_from_file() {
         let temp_dir = TempDir::new().unwrap();
@@ -161,6 +711,35 @@ mod tests {
         assert!(files.contains(&"file3.txt".to_string()));
     }
 
+    #[test]
+    fn test_read_files_from_file_with_bom() {
+        let temp_dir = TempDir::new().unwrap();
+        let expected = vec!["résumé.pdf", "résumé (1).pdf"];
+        let content = "résumé.pdf\r\nrésumé (1).pdf\r\n";
+        
+        let utf16le = temp_dir.path().join("utf16le.txt");
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(content.encode_utf16().flat_map(u16::to_le_bytes));
+        fs::write(&utf16le, bytes).unwrap();
+        assert_eq!(read_files_from_file(&utf16le).unwrap(), expected);
+        
+        let utf16be = temp_dir.path().join("utf16be.txt");
+        let mut bytes = vec![0xFE, 0xFF];
+        bytes.extend(content.encode_utf16().flat_map(u16::to_be_bytes));
+        fs::write(&utf16be, bytes).unwrap();
+        assert_eq!(read_files_from_file(&utf16be).unwrap(), expected);
+        
+        let utf8_bom = temp_dir.path().join("utf8bom.txt");
+        let mut bytes = vec![0xEF, 0xBB, 0xBF];
+        bytes.extend(content.as_bytes());
+        fs::write(&utf8_bom, bytes).unwrap();
+        assert_eq!(read_files_from_file(&utf8_bom).unwrap(), expected);
+        
+        let invalid = temp_dir.path().join("invalid.txt");
+        fs::write(&invalid, [b'a', 0xFF, b'\n']).unwrap();
+        assert!(read_files_from_file(&invalid).is_err());
+    }
+
     #[test]
     fn test_discover_files() {
         let temp_dir = TempDir::new().unwrap();
@@ -175,4 +754,244 @@ mod tests {
         assert!(files.contains(&"test1.txt".to_string()));
         assert!(files.contains(&"test2.txt".to_string()));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_normalize_root_relative_forms() {
+        let expected = normalize_root(Path::new("photos")).unwrap();
+        assert_eq!(normalize_root(Path::new("./photos")).unwrap(), expected);
+        assert_eq!(normalize_root(Path::new("./photos/")).unwrap(), expected);
+        assert_eq!(normalize_root(Path::new("photos/")).unwrap(), expected);
+        assert!(expected.is_absolute());
+    }
+
+    #[test]
+    fn test_discover_files_trailing_slash() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("nested")).unwrap();
+        fs::write(temp_dir.path().join("test1.txt"), "content1").unwrap();
+        fs::write(temp_dir.path().join("nested").join("test2.txt"), "content2").unwrap();
+        
+        let plain = temp_dir.path().to_string_lossy().to_string();
+        let with_slash = format!("{}{}", plain, std::path::MAIN_SEPARATOR);
+        let with_dot = format!("{}.", with_slash);
+        
+        let mut expected = discover_files(Path::new(&plain)).unwrap();
+        expected.sort();
+        assert_eq!(expected, vec!["test1.txt", "test2.txt"]);
+        
+        for form in [with_slash, with_dot] {
+            let mut files = discover_files(Path::new(&form)).unwrap();
+            files.sort();
+            assert_eq!(files, expected, "discovery differed for {}", form);
+        }
+    }
+
+    #[test]
+    fn test_discover_files_with_glob_metacharacters_in_root() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("photos [2024]");
+        fs::create_dir(&root).unwrap();
+        fs::write(root.join("image.jpg"), "data").unwrap();
+        
+        let files = discover_files(&root).unwrap();
+        assert_eq!(files, vec!["image.jpg"]);
+    }
+
+    #[test]
+    fn test_discover_files_with_excludes() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("cache")).unwrap();
+        fs::create_dir(root.join("docs")).unwrap();
+        fs::write(root.join("keep.txt"), "data").unwrap();
+        fs::write(root.join("debug.log"), "data").unwrap();
+        fs::write(root.join("docs").join("notes.txt"), "data").unwrap();
+        fs::write(root.join("docs").join("build.log"), "data").unwrap();
+        fs::write(root.join("cache").join("entry.bin"), "data").unwrap();
+        
+        let discovery = FileDiscovery::with_filters(&[], &["*.log".to_string()]).unwrap();
+        let mut files = discovery.discover_files(root).unwrap();
+        files.sort();
+        assert_eq!(files, vec!["entry.bin", "keep.txt", "notes.txt"]);
+        
+        let discovery = FileDiscovery::with_filters(&[], &["cache/*".to_string()]).unwrap();
+        let mut files = discovery.discover_files(root).unwrap();
+        files.sort();
+        assert_eq!(files, vec!["build.log", "debug.log", "keep.txt", "notes.txt"]);
+    }
+
+    #[test]
+    fn test_invalid_exclude_pattern() {
+        assert!(FileDiscovery::with_filters(&[], &["[".to_string()]).is_err());
+        assert!(FileDiscovery::with_filters(&["[".to_string()], &[]).is_err());
+    }
+
+    #[test]
+    fn test_discover_files_with_includes() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("raw")).unwrap();
+        fs::write(root.join("beach.jpg"), "data").unwrap();
+        fs::write(root.join("logo.png"), "data").unwrap();
+        fs::write(root.join("notes.txt"), "data").unwrap();
+        fs::write(root.join("raw").join("sunset.jpg"), "data").unwrap();
+        
+        let include = vec!["*.jpg".to_string(), "*.png".to_string()];
+        let discovery = FileDiscovery::with_filters(&include, &[]).unwrap();
+        let mut files = discovery.discover_files(root).unwrap();
+        files.sort();
+        assert_eq!(files, vec!["beach.jpg", "logo.png", "sunset.jpg"]);
+        
+        // Exclusion is applied after inclusion
+        let discovery = FileDiscovery::with_filters(&include, &["raw/*".to_string()]).unwrap();
+        let mut files = discovery.discover_files(root).unwrap();
+        files.sort();
+        assert_eq!(files, vec!["beach.jpg", "logo.png"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_discover_skips_unreadable_paths() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+        
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("open").join("nested")).unwrap();
+        fs::create_dir(root.join("locked")).unwrap();
+        fs::write(root.join("top.txt"), "data").unwrap();
+        fs::write(root.join("open").join("nested").join("deep.txt"), "data").unwrap();
+        fs::write(root.join("locked").join("secret.txt"), "data").unwrap();
+        symlink(root.join("missing.txt"), root.join("dangling.txt")).unwrap();
+        fs::set_permissions(root.join("locked"), fs::Permissions::from_mode(0o000)).unwrap();
+        // Root reads any directory regardless of its permissions
+        let locked_readable = fs::read_dir(root.join("locked")).is_ok();
+        
+        let discovered = FileDiscovery::new().follow_symlinks(true).discover(root).unwrap();
+        fs::set_permissions(root.join("locked"), fs::Permissions::from_mode(0o755)).unwrap();
+        
+        let names: Vec<&str> = discovered
+            .paths
+            .iter()
+            .filter_map(|path| path.file_name().and_then(|name| name.to_str()))
+            .collect();
+        assert!(names.contains(&"top.txt") && names.contains(&"deep.txt"), "{:?}", names);
+        let skipped: Vec<&Path> = discovered.skipped.iter().map(|skipped| skipped.path.as_path()).collect();
+        assert!(skipped.contains(&root.join("dangling.txt").as_path()), "{:?}", discovered.skipped);
+        if !locked_readable {
+            assert!(!names.contains(&"secret.txt"));
+            assert!(skipped.contains(&root.join("locked").as_path()), "{:?}", discovered.skipped);
+            assert!(discovered.skipped.iter().any(|skipped| skipped.reason.contains("ermission denied")));
+        }
+        
+        // Collecting hands the skipped paths back instead of printing them
+        let collected = collect_files_with_stdin(vec![], None, Some(root.to_path_buf()), &[], &[], true, None, false, false, false, None).unwrap();
+        assert!(collected.files.contains(&"top.txt".to_string()));
+        assert_eq!(collected.skipped.len(), discovered.skipped.len());
+        let message = describe_skipped(&collected.skipped).unwrap();
+        assert!(message.contains(&root.join("dangling.txt").display().to_string()), "{}", message);
+        assert!(describe_skipped(&[]).is_none());
+        
+        // Only an unreadable root is an error
+        assert!(FileDiscovery::new().discover(&root.join("missing")).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_discover_files_symlink_cycle_terminates() {
+        use std::os::unix::fs::symlink;
+        
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("a")).unwrap();
+        fs::write(root.join("a").join("photo.jpg"), "data").unwrap();
+        fs::write(root.join("notes.txt"), "data").unwrap();
+        // a/loop -> root, so following links naively never ends
+        symlink(root, root.join("a").join("loop")).unwrap();
+        symlink(root.join("notes.txt"), root.join("notes_link.txt")).unwrap();
+        
+        let files = discover_files(root).unwrap();
+        assert_eq!(files, vec!["photo.jpg", "notes.txt"]);
+        
+        let discovery = FileDiscovery::new().follow_symlinks(true);
+        let mut files = discovery.discover_files(root).unwrap();
+        files.sort();
+        assert_eq!(files, vec!["notes.txt", "notes_link.txt", "photo.jpg"]);
+    }
+
+    #[test]
+    fn test_discover_files_modified_since() {
+        let temp_dir = TempDir::new().unwrap();
+        let now = SystemTime::now();
+        let day = Duration::from_secs(24 * 60 * 60);
+        for (name, age) in [("old_report.pdf", 30), ("last_week.pdf", 6), ("today.pdf", 0)] {
+            let path = temp_dir.path().join(name);
+            fs::write(&path, "data").unwrap();
+            fs::File::options().write(true).open(&path).unwrap().set_modified(now - day * age).unwrap();
+        }
+        
+        let discovery = FileDiscovery::new().modified_since(Some(now - day * 7));
+        let mut files = discovery.discover_files(temp_dir.path()).unwrap();
+        files.sort();
+        assert_eq!(files, vec!["last_week.pdf", "today.pdf"]);
+        
+        let discovery = FileDiscovery::new().modified_since(Some(parse_since("1d").unwrap()));
+        assert_eq!(discovery.discover_files(temp_dir.path()).unwrap(), vec!["today.pdf"]);
+        
+        let root = Some(temp_dir.path().to_path_buf());
+        let since = Some(now - day * 60);
+        let files = collect_files_with_stdin(vec![], None, root, &[], &[], false, since, false, false, false, None).unwrap().files;
+        assert_eq!(files.len(), 3);
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        let ago = |seconds| now - Duration::from_secs(seconds);
+        assert_eq!(parse_since_at("45s", now).unwrap(), ago(45));
+        assert_eq!(parse_since_at("30m", now).unwrap(), ago(30 * 60));
+        assert_eq!(parse_since_at("12h", now).unwrap(), ago(12 * 60 * 60));
+        assert_eq!(parse_since_at("7d", now).unwrap(), ago(7 * 24 * 60 * 60));
+        assert_eq!(parse_since_at(" 2w ", now).unwrap(), ago(14 * 24 * 60 * 60));
+        
+        let at = |seconds| SystemTime::UNIX_EPOCH + Duration::from_secs(seconds);
+        assert_eq!(parse_since_at("2024-05-01T00:00:00Z", now).unwrap(), at(1_714_521_600));
+        assert_eq!(parse_since_at("2024-05-01T02:00:00+02:00", now).unwrap(), at(1_714_521_600));
+        assert_eq!(parse_since_at("2024-05-01", now).unwrap(), at(1_714_521_600));
+        
+        for invalid in ["", "d", "7", "7y", "-7d", "yesterday", "2024-13-01", "05/01/2024"] {
+            assert!(parse_since_at(invalid, now).is_err(), "{}", invalid);
+        }
+        assert!(parse_since_at("30500000000000000w", now).is_err());
+    }
+
+    #[test]
+    fn test_discover_paths_includes_subdirectories() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir_all(temp_dir.path().join("2024").join("june")).unwrap();
+        fs::write(temp_dir.path().join("top.txt"), "data").unwrap();
+        fs::write(temp_dir.path().join("2024").join("june").join("beach.jpg"), "data").unwrap();
+        
+        let paths = FileDiscovery::new().discover_paths(temp_dir.path()).unwrap();
+        assert_eq!(paths.len(), 2);
+        assert!(paths.iter().all(|path| path.is_absolute() && path.is_file()));
+        assert!(paths.iter().any(|path| path.ends_with("2024/june/beach.jpg")));
+    }
+
+    #[test]
+    fn test_collect_files_full_paths_keeps_same_named_files() {
+        let temp_dir = TempDir::new().unwrap();
+        for year in ["2023", "2024"] {
+            fs::create_dir(temp_dir.path().join(year)).unwrap();
+            fs::write(temp_dir.path().join(year).join("report.pdf"), "data").unwrap();
+        }
+        let root = Some(temp_dir.path().to_path_buf());
+        
+        let names = collect_files_with_stdin(vec![], None, root.clone(), &[], &[], false, None, false, false, false, None).unwrap().files;
+        assert_eq!(names, vec!["report.pdf"]);
+        
+        let paths = collect_files_with_stdin(vec![], None, root, &[], &[], false, None, true, false, false, None).unwrap().files;
+        assert_eq!(paths.len(), 2);
+        assert!(paths[0].ends_with("2023/report.pdf"));
+        assert!(paths[1].ends_with("2024/report.pdf"));
+    }
+}
@@ -0,0 +1,172 @@
+use crate::grouper::Group;
+use anyhow::Result;
+use std::collections::HashSet;
+use std::io::{BufRead, Write};
+
+/// What to do with the files of one group, as answered at the prompt.
+#[derive(Debug, Clone, PartialEq)]
+enum Selection {
+    KeepAll,
+    KeepFirst,
+    /// Zero-based indices of the files to trash
+    Trash(Vec<usize>),
+    Quit,
+}
+
+/// Parses an answer for a group of `len` files. An empty answer keeps every
+/// file. Selecting every file is rejected, so a group always keeps one.
+fn parse_selection(answer: &str, len: usize) -> Result<Selection, String> {
+    match answer.trim().to_lowercase().as_str() {
+        "" | "k" | "keep" => return Ok(Selection::KeepAll),
+        "f" | "first" => return Ok(Selection::KeepFirst),
+        "q" | "quit" => return Ok(Selection::Quit),
+        _ => {}
+    }
+
+    let mut indices = Vec::new();
+    for part in answer.split([',', ' ']).filter(|part| !part.trim().is_empty()) {
+        let number: usize = part
+            .trim()
+            .parse()
+            .map_err(|_| format!("'{}' is not a file number", part.trim()))?;
+        if number == 0 || number > len {
+            return Err(format!("{} is not between 1 and {}", number, len));
+        }
+        if !indices.contains(&(number - 1)) {
+            indices.push(number - 1);
+        }
+    }
+    if indices.len() == len {
+        return Err("At least one file of the group must be kept".to_string());
+    }
+    Ok(Selection::Trash(indices))
+}
+
+/// Walks through `groups`, asking on `output` which files of each to move to
+/// the trash and reading answers from `input`. Nothing is selected unless
+/// asked for: an empty answer, `q` or the end of input keeps the files.
+/// Returns the chosen files once the whole selection is confirmed with `y`,
+/// and nothing otherwise.
+pub fn select_files_to_trash<R: BufRead, W: Write>(groups: &[Group], input: &mut R, output: &mut W) -> Result<Vec<String>> {
+    let mut selected: Vec<String> = Vec::new();
+
+    'groups: for group in groups.iter().filter(|group| group.files.len() > 1) {
+        writeln!(output, "\nGroup {} (similarity: {:.0}%):", group.id, group.similarity * 100.0)?;
+        for (index, file) in group.files.iter().enumerate() {
+            writeln!(output, "  [{}] {}", index + 1, file)?;
+        }
+
+        loop {
+            write!(output, "Files to trash: numbers (e.g. 2,3), f = keep first, k = keep all (default), q = stop: ")?;
+            output.flush()?;
+            let mut answer = String::new();
+            if input.read_line(&mut answer)? == 0 {
+                break 'groups;
+            }
+            match parse_selection(&answer, group.files.len()) {
+                Ok(Selection::KeepAll) => break,
+                Ok(Selection::KeepFirst) => {
+                    selected.extend(group.files[1..].iter().cloned());
+                    break;
+                }
+                Ok(Selection::Trash(indices)) => {
+                    selected.extend(indices.into_iter().map(|index| group.files[index].clone()));
+                    break;
+                }
+                Ok(Selection::Quit) => break 'groups,
+                Err(message) => writeln!(output, "{}", message)?,
+            }
+        }
+    }
+
+    // Transitive groups never share files, but don't list a file twice
+    let mut seen = HashSet::new();
+    selected.retain(|file| seen.insert(file.clone()));
+    if selected.is_empty() {
+        writeln!(output, "\nNo files selected.")?;
+        return Ok(selected);
+    }
+
+    writeln!(output, "\nFiles selected for the trash:")?;
+    for file in &selected {
+        writeln!(output, "  - {}", file)?;
+    }
+    write!(output, "Move {} file(s) to the trash? [y/N]: ", selected.len())?;
+    output.flush()?;
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    if matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+        Ok(selected)
+    } else {
+        writeln!(output, "Nothing was moved.")?;
+        Ok(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(id: usize, files: &[&str]) -> Group {
+        Group::fixture(id, files, 0.9)
+    }
+
+    fn run(groups: &[Group], script: &str) -> (Vec<String>, String) {
+        let mut output = Vec::new();
+        let selected = select_files_to_trash(groups, &mut script.as_bytes(), &mut output).unwrap();
+        (selected, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_parse_selection() {
+        assert_eq!(parse_selection("\n", 3), Ok(Selection::KeepAll));
+        assert_eq!(parse_selection("K", 3), Ok(Selection::KeepAll));
+        assert_eq!(parse_selection("f", 3), Ok(Selection::KeepFirst));
+        assert_eq!(parse_selection("q", 3), Ok(Selection::Quit));
+        assert_eq!(parse_selection("3, 1 3", 3), Ok(Selection::Trash(vec![2, 0])));
+        assert!(parse_selection("4", 3).is_err());
+        assert!(parse_selection("0", 3).is_err());
+        assert!(parse_selection("two", 3).is_err());
+        assert!(parse_selection("1,2,3", 3).is_err());
+    }
+
+    #[test]
+    fn test_select_files_to_trash() {
+        let groups = [
+            group(1, &["a_v1.txt", "a_v2.txt", "a_v3.txt"]),
+            group(2, &["b_v1.txt", "b_v2.txt"]),
+            group(3, &["c_v1.txt", "c_v2.txt"]),
+        ];
+
+        // Per-index selection, keep first, keep all by default, then confirm
+        let (selected, output) = run(&groups, "2,3\nf\n\ny\n");
+        assert_eq!(selected, vec!["a_v2.txt", "a_v3.txt", "b_v2.txt"]);
+        assert!(output.contains("  [3] a_v3.txt"));
+        assert!(output.contains("Move 3 file(s) to the trash?"));
+
+        // An invalid answer asks again
+        let (selected, output) = run(&groups, "1,2,3\n5\n1\nq\ny\n");
+        assert_eq!(selected, vec!["a_v1.txt"]);
+        assert!(output.contains("At least one file of the group must be kept"));
+        assert!(output.contains("5 is not between 1 and 3"));
+    }
+
+    #[test]
+    fn test_select_files_to_trash_requires_confirmation() {
+        let groups = [group(1, &["a_v1.txt", "a_v2.txt"])];
+
+        let (selected, output) = run(&groups, "f\n\n");
+        assert!(selected.is_empty());
+        assert!(output.contains("Nothing was moved."));
+
+        let (selected, _) = run(&groups, "f\nno\n");
+        assert!(selected.is_empty());
+
+        // Running out of input keeps everything
+        let (selected, _) = run(&groups, "f\n");
+        assert!(selected.is_empty());
+        let (selected, output) = run(&groups, "");
+        assert!(selected.is_empty());
+        assert!(output.contains("No files selected."));
+    }
+}
@@ -1,10 +1,30 @@
 use serde::{Deserialize, Serialize};
 use anyhow::Result;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 // Import CLI modules
-mod cli;
-mod input;
-mod file_info;
+pub mod baseline;
+pub mod checkpoint;
+pub mod cli;
+pub mod config;
+pub mod explain;
+pub mod file_info;
+pub mod grouper;
+pub mod hash_cache;
+pub mod hierarchy;
+pub mod input;
+pub mod interactive;
+pub mod output;
+pub mod rename;
+pub mod scheme;
+pub mod similarity;
+pub mod tree;
+
+// Public library API
+pub use cli::Algorithm;
+pub use grouper::{group_files, Group, GroupingResult, MatchReason, Summary};
+pub use similarity::{calculate_similarity, calculate_similarity_with, AlgorithmWeights, AutoWeights, ComponentWeights, Similarity};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FileInfoResult {
@@ -17,9 +37,35 @@ fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
+/// Cancellation flag shared by the running analysis and [`cancel_analysis`].
+#[derive(Default)]
+struct AnalysisCancel(Arc<AtomicBool>);
+
+impl AnalysisCancel {
+    /// Clears any earlier cancellation and returns the flag for a new run.
+    fn start(&self) -> Arc<AtomicBool> {
+        self.0.store(false, Ordering::Relaxed);
+        Arc::clone(&self.0)
+    }
+    
+    fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
 #[tauri::command]
-async fn analyze_files_advanced(file_paths: Vec<String>) -> Result<FileInfoResult, String> {
-    use crate::file_info::{FileInfo, group_similar_files};
+fn cancel_analysis(cancel: tauri::State<'_, AnalysisCancel>) {
+    cancel.cancel();
+}
+
+#[tauri::command]
+async fn analyze_files_advanced(
+    app: tauri::AppHandle,
+    cancel: tauri::State<'_, AnalysisCancel>,
+    file_paths: Vec<String>,
+    config: Option<file_info::GroupingConfig>,
+) -> Result<FileInfoResult, String> {
+    use crate::file_info::{FileInfo, group_similar_files_with_progress};
     use std::path::Path;
     
     // Convert file paths to FileInfo objects
@@ -35,71 +81,311 @@ async fn analyze_files_advanced(file_paths: Vec<String>) -> Result<FileInfoResul
     }
     
     // Group similar files
-    let groups = group_similar_files(files).await
+    let cancel_flag = cancel.start();
+    let mut config = config.unwrap_or_default();
+    config.hash_cache_path = hash_cache_path(&app);
+    let groups = group_similar_files_with_progress(files, &config, |event| emit_progress(&app, event), &cancel_flag).await
         .map_err(|e| format!("Failed to group files: {}", e))?;
     
     Ok(FileInfoResult { groups })
 }
 
 #[tauri::command]
-async fn analyze_folder(folder_path: String) -> Result<FileInfoResult, String> {
-    use crate::input::FileDiscovery;
-    use crate::file_info::{FileInfo, group_similar_files};
-    use std::path::Path;
+async fn analyze_folder(
+    app: tauri::AppHandle,
+    cancel: tauri::State<'_, AnalysisCancel>,
+    folder_path: String,
+    since: Option<String>,
+    config: Option<file_info::GroupingConfig>,
+) -> Result<FileInfoResult, String> {
+    use crate::file_info::group_similar_files_with_progress;
+
+    let since = since
+        .as_deref()
+        .map(crate::input::parse_since)
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    let files = discover_folder_files(std::path::Path::new(&folder_path), since)?;
+
+    // Group similar files
+    let cancel_flag = cancel.start();
+    let mut config = config.unwrap_or_default();
+    config.hash_cache_path = hash_cache_path(&app);
+    let groups = group_similar_files_with_progress(files, &config, |event| emit_progress(&app, event), &cancel_flag).await
+        .map_err(|e| format!("Failed to group files: {}", e))?;
+
+    Ok(FileInfoResult { groups })
+}
 
-    // Use embedded CLI logic instead of external binary
-    let folder_path_buf = Path::new(&folder_path);
+/// Discovers every file below `folder_path`, subdirectories included, using
+/// the same discovery as the CLI. Each [`file_info::FileInfo`] carries the
+/// file's full path rather than a name joined onto the folder. With `since`,
+/// files last modified before it are skipped.
+fn discover_folder_files(
+    folder_path: &std::path::Path,
+    since: Option<std::time::SystemTime>,
+) -> Result<Vec<file_info::FileInfo>, String> {
+    use crate::input::FileDiscovery;
+    use crate::file_info::FileInfo;
 
-    // Discover files
-    let file_discovery = FileDiscovery::new();
-    let file_paths = file_discovery.discover_files(folder_path_buf)
+    let file_paths = FileDiscovery::new().modified_since(since).discover_paths(folder_path)
         .map_err(|e| format!("Failed to discover files: {}", e))?;
 
-    // Convert file paths to FileInfo objects
     let mut files = Vec::new();
-    for path_str in file_paths {
-        let path = folder_path_buf.join(&path_str);
-        if path.exists() && path.is_file() {
-            match FileInfo::from_path(&path) {
-                Ok(file_info) => files.push(file_info),
-                Err(e) => eprintln!("Warning: Failed to process file {}: {}", path.display(), e),
-            }
+    for path in file_paths {
+        match FileInfo::from_path(&path) {
+            Ok(file_info) => files.push(file_info),
+            Err(e) => eprintln!("Warning: Failed to process file {}: {}", path.display(), e),
         }
     }
+    Ok(files)
+}
 
-    // Group similar files
-    let groups = group_similar_files(files).await
-        .map_err(|e| format!("Failed to group files: {}", e))?;
+/// Forwards grouping progress to the frontend as `analysis-progress` events.
+fn emit_progress(app: &tauri::AppHandle, event: file_info::ProgressEvent) {
+    use tauri::Emitter;
+    if let Err(e) = app.emit("analysis-progress", event) {
+        eprintln!("Warning: Failed to emit progress event: {}", e);
+    }
+}
 
-    Ok(FileInfoResult { groups })
+/// Location of the persistent SHA-256 cache in the app data directory.
+fn hash_cache_path(app: &tauri::AppHandle) -> Option<std::path::PathBuf> {
+    use tauri::Manager;
+    app.path().app_data_dir().ok().map(|dir| dir.join("hash_cache.json"))
 }
 
 #[tauri::command]
-async fn delete_files(file_paths: Vec<String>) -> Result<String, String> {
-    let mut deleted_count = 0;
-    let mut errors = Vec::new();
+fn clear_hash_cache(app: tauri::AppHandle) -> Result<(), String> {
+    let Some(path) = hash_cache_path(&app) else {
+        return Ok(());
+    };
+    hash_cache::HashCache::load(&path)
+        .and_then(|mut cache| cache.clear())
+        .map_err(|e| format!("Failed to clear hash cache: {}", e))
+}
 
-    for path in file_paths {
-        match trash::delete(&path) {
-            Ok(_) => deleted_count += 1,
-            Err(e) => errors.push(format!("Failed to delete '{}': {}", path, e)),
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DeleteResult {
+    pub deleted: Vec<String>,
+    /// `(path, error message)` for every file that could not be deleted
+    pub failed: Vec<(String, String)>,
+}
+
+impl DeleteResult {
+    /// Runs `delete` on every path, carrying on past failures.
+    pub fn from_paths<F>(file_paths: Vec<String>, mut delete: F) -> Self
+    where
+        F: FnMut(&str) -> Result<(), String>,
+    {
+        let mut result = DeleteResult::default();
+        for path in file_paths {
+            match delete(&path) {
+                Ok(_) => result.deleted.push(path),
+                Err(e) => result.failed.push((path, e)),
+            }
         }
+        result
     }
 
-    if !errors.is_empty() {
-        Err(format!("Some files could not be deleted: {}", errors.join(", ")))
-    } else {
-        Ok(format!("Successfully deleted {} file(s) to trash", deleted_count))
+    /// Runs `delete` on every file of `groups` except the one `policy` keeps
+    /// in each group. Groups of a single file are skipped, and a file kept
+    /// in any group is never deleted, so every group keeps a survivor.
+    fn from_redundant<F>(groups: &[file_info::SimilarityGroup], policy: file_info::KeepPolicy, delete: F) -> Self
+    where
+        F: FnMut(&str) -> Result<(), String>,
+    {
+        let groups: Vec<_> = groups.iter().filter(|group| group.files.len() > 1).cloned().collect();
+        let suggestions = file_info::suggest_keepers(&groups, policy);
+        let kept: std::collections::HashSet<&str> = suggestions.iter().map(|s| s.keep.path.as_str()).collect();
+        let redundant = suggestions
+            .iter()
+            .flat_map(|suggestion| &suggestion.redundant)
+            .map(|file| file.path.clone())
+            .filter(|path| !kept.contains(path.as_str()))
+            .collect();
+        Self::from_paths(redundant, delete)
     }
 }
 
+#[tauri::command]
+async fn delete_files(file_paths: Vec<String>) -> Result<DeleteResult, String> {
+    Ok(DeleteResult::from_paths(file_paths, |path| {
+        trash::delete(path).map_err(|e| e.to_string())
+    }))
+}
+
+/// Moves every file but the one `policy` keeps in each group to the trash,
+/// in one call.
+#[tauri::command]
+async fn trash_redundant(
+    groups: Vec<file_info::SimilarityGroup>,
+    policy: file_info::KeepPolicy,
+) -> Result<DeleteResult, String> {
+    Ok(DeleteResult::from_redundant(&groups, policy, |path| {
+        trash::delete(path).map_err(|e| e.to_string())
+    }))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeletePreview {
+    pub path: String,
+    pub exists: bool,
+    pub size: u64,
+    pub writable: bool,
+}
+
+impl DeletePreview {
+    fn for_path(path: &str) -> Self {
+        match std::fs::metadata(path) {
+            Ok(metadata) => DeletePreview {
+                path: path.to_string(),
+                exists: true,
+                size: metadata.len(),
+                writable: !metadata.permissions().readonly(),
+            },
+            Err(_) => DeletePreview {
+                path: path.to_string(),
+                exists: false,
+                size: 0,
+                writable: false,
+            },
+        }
+    }
+}
+
+/// Reports what `delete_files` would remove without touching anything, so the
+/// frontend can confirm the deletion and show the space it reclaims.
+#[tauri::command]
+fn preview_delete(file_paths: Vec<String>) -> Result<Vec<DeletePreview>, String> {
+    Ok(file_paths.iter().map(|path| DeletePreview::for_path(path)).collect())
+}
+
+/// Proposes which file of each group to keep and which are redundant, for
+/// preselecting files in the delete flow.
+#[tauri::command]
+fn suggest_keepers(
+    groups: Vec<file_info::SimilarityGroup>,
+    policy: file_info::KeepPolicy,
+) -> Vec<file_info::KeepSuggestion> {
+    file_info::suggest_keepers(&groups, policy)
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
-        .invoke_handler(tauri::generate_handler![greet, analyze_folder, analyze_files_advanced, delete_files])
+        .manage(AnalysisCancel::default())
+        .invoke_handler(tauri::generate_handler![greet, analyze_folder, analyze_files_advanced, delete_files, trash_redundant, preview_delete, suggest_keepers, clear_hash_cache, cancel_analysis])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_preview_delete() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("keep_me.txt");
+        fs::write(&file_path, "hello").unwrap();
+        let missing_path = temp_dir.path().join("missing.txt");
+        
+        let previews = preview_delete(vec![
+            file_path.to_string_lossy().to_string(),
+            missing_path.to_string_lossy().to_string(),
+        ]).unwrap();
+        
+        assert_eq!(previews.len(), 2);
+        assert!(previews[0].exists);
+        assert_eq!(previews[0].size, 5);
+        assert!(previews[0].writable);
+        assert!(!previews[1].exists);
+        assert_eq!(previews[1].size, 0);
+        
+        // Nothing was deleted
+        assert!(file_path.exists());
+    }
+
+    #[test]
+    fn test_delete_result_collects_successes_and_failures() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("delete_me.txt");
+        fs::write(&file_path, "bye").unwrap();
+        let valid = file_path.to_string_lossy().to_string();
+        let bogus = temp_dir.path().join("does_not_exist.txt").to_string_lossy().to_string();
+        
+        let result = DeleteResult::from_paths(vec![bogus.clone(), valid.clone()], |path| {
+            fs::remove_file(path).map_err(|e| e.to_string())
+        });
+        
+        assert_eq!(result.deleted, vec![valid]);
+        assert_eq!(result.failed.len(), 1);
+        assert_eq!(result.failed[0].0, bogus);
+        assert!(!result.failed[0].1.is_empty());
+        assert!(!file_path.exists());
+    }
+
+    #[test]
+    fn test_trash_redundant_keeps_one_file_per_group() {
+        use crate::file_info::{FileInfo, KeepPolicy, SimilarityGroup, SimilarityType};
+        
+        let temp_dir = TempDir::new().unwrap();
+        let file = |name: &str, last_modified: u64| {
+            let path = temp_dir.path().join(name);
+            fs::write(&path, name).unwrap();
+            FileInfo { last_modified, ..FileInfo::from_path(&path).unwrap() }
+        };
+        let group = |id: &str, files: Vec<FileInfo>| SimilarityGroup {
+            id: id.to_string(),
+            files,
+            similarity_type: SimilarityType::Name,
+            similarity_score: 0.9,
+            matches: Vec::new(),
+        };
+        let groups = vec![
+            group("group-0", vec![file("report_v1.pdf", 10), file("report_v3.pdf", 30), file("report_v2.pdf", 20)]),
+            group("group-1", vec![file("photo_old.jpg", 5), file("photo_new.jpg", 50)]),
+            group("group-2", vec![file("alone.txt", 1)]),
+        ];
+        
+        let mut attempted = Vec::new();
+        let result = DeleteResult::from_redundant(&groups, KeepPolicy::Newest, |path| {
+            attempted.push(path.to_string());
+            fs::remove_file(path).map_err(|e| e.to_string())
+        });
+        
+        let path = |name: &str| temp_dir.path().join(name).to_string_lossy().to_string();
+        assert_eq!(attempted, vec![path("report_v1.pdf"), path("report_v2.pdf"), path("photo_old.jpg")]);
+        assert_eq!(result.deleted, attempted);
+        assert!(result.failed.is_empty());
+        for survivor in ["report_v3.pdf", "photo_new.jpg", "alone.txt"] {
+            assert!(temp_dir.path().join(survivor).exists(), "{} was deleted", survivor);
+        }
+        
+        // A file listed twice is never deleted if it is kept anywhere
+        let twice = vec![group("group-0", vec![file("a.txt", 2), file("b.txt", 1)]), group("group-1", vec![file("c.txt", 3), file("a.txt", 2)])];
+        let result = DeleteResult::from_redundant(&twice, KeepPolicy::Newest, |_| Ok(()));
+        assert_eq!(result.deleted, vec![path("b.txt")]);
+    }
+
+    #[test]
+    fn test_discover_folder_files_resolves_nested_paths() {
+        let temp_dir = TempDir::new().unwrap();
+        let nested = temp_dir.path().join("albums").join("summer");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(nested.join("beach.jpg"), "data").unwrap();
+        fs::write(temp_dir.path().join("cover.jpg"), "data").unwrap();
+        
+        let files = discover_folder_files(temp_dir.path(), None).unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(files.iter().all(|file| std::path::Path::new(&file.path).is_file()));
+        let beach = files.iter().find(|file| file.name == "beach.jpg").unwrap();
+        assert_eq!(std::path::Path::new(&beach.path), nested.join("beach.jpg"));
+    }
+}
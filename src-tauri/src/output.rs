@@ -1,8 +1,15 @@
-use crate::cli::OutputFormat;
-use crate::grouper::GroupingResult;
-use anyhow::Result;
+use crate::cli::{Algorithm, OutputFormat, Require};
+use crate::file_info::FileInfo;
+use crate::grouper::{GroupingResult, ReferenceMatch, Summary, SweepRow};
+use crate::similarity::{calculate_combined_similarity, tokenize, Affixes, ComponentWeights};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
 use console::style;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
 use std::io::Write;
+use std::path::{Path, PathBuf};
 
 impl OutputFormat {
     pub fn format(&self, result: &GroupingResult, show_ungrouped: bool) -> Result<String> {
@@ -10,6 +17,139 @@ impl OutputFormat {
         format_output(result, self, &mut output, show_ungrouped)?;
         Ok(String::from_utf8(output)?)
     }
+    
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Text | OutputFormat::Table => "txt",
+            OutputFormat::Json => "json",
+            OutputFormat::Jsonl => "jsonl",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Tsv => "tsv",
+            OutputFormat::Html => "html",
+        }
+    }
+    
+    /// Table writer for `csv` and `tsv` output, splitting fields with a tab
+    /// for `tsv` and a comma otherwise.
+    pub fn table_writer<W: Write>(&self, writer: W) -> csv::Writer<W> {
+        let delimiter = if matches!(self, OutputFormat::Tsv) { b'\t' } else { b',' };
+        csv::WriterBuilder::new().delimiter(delimiter).from_writer(writer)
+    }
+}
+
+/// Index written next to paginated output, listing the page files in order.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PageIndex {
+    pub page_size: usize,
+    pub total_groups: usize,
+    pub pages: Vec<String>,
+    pub summary: Summary,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ungrouped: Vec<String>,
+}
+
+/// Splits `result` into `report.001.<ext>`, `report.002.<ext>`, ... in `dir`,
+/// each holding up to `page_size` groups, plus a `report.index.json` that
+/// references them. Ungrouped files are listed only in the index. Returns the
+/// path of the index.
+pub fn write_pages(
+    result: &GroupingResult,
+    format: &OutputFormat,
+    show_ungrouped: bool,
+    page_size: usize,
+    dir: &Path,
+) -> Result<PathBuf> {
+    if page_size == 0 {
+        anyhow::bail!("Page size must be at least 1");
+    }
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create output directory: {}", dir.display()))?;
+    
+    let mut pages = Vec::new();
+    for (index, chunk) in result.groups.chunks(page_size).enumerate() {
+        let page = GroupingResult {
+            groups: chunk.to_vec(),
+            ungrouped: Vec::new(),
+            summary: result.summary.clone(),
+        };
+        let name = format!("report.{:03}.{}", index + 1, format.extension());
+        let path = dir.join(&name);
+        fs::write(&path, format.format(&page, false)?)
+            .with_context(|| format!("Failed to write page: {}", path.display()))?;
+        pages.push(name);
+    }
+    
+    let index = PageIndex {
+        page_size,
+        total_groups: result.groups.len(),
+        pages,
+        summary: result.summary.clone(),
+        ungrouped: if show_ungrouped { result.ungrouped.clone() } else { Vec::new() },
+    };
+    let index_path = dir.join("report.index.json");
+    fs::write(&index_path, serde_json::to_string_pretty(&index)?)
+        .with_context(|| format!("Failed to write page index: {}", index_path.display()))?;
+    Ok(index_path)
+}
+
+/// Writes each group of `result` to its own `group-<id>.<ext>` in `dir`
+/// (e.g. `group-001.txt`), plus the ungrouped files to `ungrouped.<ext>`
+/// when `show_ungrouped` is set. Each file is a complete result of its own,
+/// with a summary covering just its files. `dir` is created if missing and
+/// must be empty unless `force` is set, in which case files of the same
+/// name are replaced.
+pub fn write_split(
+    result: &GroupingResult,
+    format: &OutputFormat,
+    show_ungrouped: bool,
+    dir: &Path,
+    force: bool,
+) -> Result<Vec<PathBuf>> {
+    if !force && dir.is_dir() {
+        let mut entries = fs::read_dir(dir)
+            .with_context(|| format!("Failed to read output directory: {}", dir.display()))?;
+        if entries.next().is_some() {
+            anyhow::bail!("Output directory {} is not empty; pass --force to write into it anyway", dir.display());
+        }
+    }
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create output directory: {}", dir.display()))?;
+
+    let summary = |total_files: usize, groups_found: usize, ungrouped_files: usize| Summary {
+        total_files,
+        groups_found,
+        ungrouped_files,
+        threshold_used: result.summary.threshold_used,
+    };
+    let mut parts: Vec<(String, GroupingResult)> = result
+        .groups
+        .iter()
+        .map(|group| {
+            let part = GroupingResult {
+                groups: vec![group.clone()],
+                ungrouped: Vec::new(),
+                summary: summary(group.files.len(), 1, 0),
+            };
+            (format!("group-{:03}", group.id), part)
+        })
+        .collect();
+    if show_ungrouped {
+        let part = GroupingResult {
+            groups: Vec::new(),
+            ungrouped: result.ungrouped.clone(),
+            summary: summary(result.ungrouped.len(), 0, result.ungrouped.len()),
+        };
+        parts.push(("ungrouped".to_string(), part));
+    }
+
+    let mut paths = Vec::new();
+    for (stem, part) in parts {
+        let path = dir.join(format!("{}.{}", stem, format.extension()));
+        fs::write(&path, format.format(&part, show_ungrouped)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        paths.push(path);
+    }
+    Ok(paths)
 }
 
 pub fn format_output<W: Write>(
@@ -20,8 +160,12 @@ pub fn format_output<W: Write>(
 ) -> Result<()> {
     match format {
         OutputFormat::Text => format_text(result, writer, show_ungrouped),
+        OutputFormat::Table => format_table(result, writer, show_ungrouped, table_width()),
         OutputFormat::Json => format_json(result, writer, show_ungrouped),
+        OutputFormat::Jsonl => format_jsonl(result, writer, show_ungrouped),
         OutputFormat::Csv => format_csv(result, writer, show_ungrouped),
+        OutputFormat::Tsv => format_tsv(result, writer, show_ungrouped),
+        OutputFormat::Html => format_html(result, writer, show_ungrouped),
     }
 }
 
@@ -30,13 +174,18 @@ fn format_text<W: Write>(result: &GroupingResult, writer: &mut W, show_ungrouped
         writeln!(writer, "{}", style("No similar file groups found.").yellow())?;
     } else {
         for group in &result.groups {
+            let case_label = if group.case_only { ", case-only variants" } else { "" };
+            let name_label = group.label.as_ref().map(|label| format!(" [{}]", label)).unwrap_or_default();
             writeln!(
                 writer,
                 "{}",
                 style(format!(
-                    "Group {} (similarity: {:.0}%):",
+                    "Group {}{} (similarity: {:.0}%, {}{}):",
                     group.id,
-                    group.similarity * 100.0
+                    name_label,
+                    group.similarity * 100.0,
+                    group.match_reason.label(),
+                    case_label
                 ))
                 .green()
                 .bold()
@@ -57,13 +206,144 @@ fn format_text<W: Write>(result: &GroupingResult, writer: &mut W, show_ungrouped
         writeln!(writer)?;
     }
     
-    // Summary
+    write_text_summary(&result.summary, writer)
+}
+
+fn write_text_summary<W: Write>(summary: &Summary, writer: &mut W) -> Result<()> {
     writeln!(writer, "{}", style("Summary:").blue().bold())?;
-    writeln!(writer, "  Total files: {}", result.summary.total_files)?;
-    writeln!(writer, "  Groups found: {}", result.summary.groups_found)?;
-    writeln!(writer, "  Ungrouped files: {}", result.summary.ungrouped_files)?;
-    writeln!(writer, "  Threshold used: {:.0}%", result.summary.threshold_used * 100.0)?;
-    
+    writeln!(writer, "  Total files: {}", summary.total_files)?;
+    writeln!(writer, "  Groups found: {}", summary.groups_found)?;
+    writeln!(writer, "  Ungrouped files: {}", summary.ungrouped_files)?;
+    writeln!(writer, "  Threshold used: {:.0}%", summary.threshold_used * 100.0)?;
+    Ok(())
+}
+
+/// Writes the JSON Schema of `--format json` output, i.e. of
+/// [`GroupingResult`], so consumers can validate what they parse.
+pub fn format_schema<W: Write>(writer: &mut W) -> Result<()> {
+    let schema = schemars::schema_for!(GroupingResult);
+    writeln!(writer, "{}", serde_json::to_string_pretty(&schema)?)?;
+    Ok(())
+}
+
+/// Writes only the summary counts, for cheap periodic polling: the text
+/// summary block, `{"summary": ...}` as JSON, the closing summary line of the
+/// JSON Lines stream, or a one-row CSV.
+pub fn format_summary<W: Write>(summary: &Summary, format: &OutputFormat, writer: &mut W) -> Result<()> {
+    match format {
+        OutputFormat::Text | OutputFormat::Table => write_text_summary(summary, writer)?,
+        OutputFormat::Json => {
+            let output = serde_json::json!({ "summary": summary });
+            writeln!(writer, "{}", serde_json::to_string_pretty(&output)?)?;
+        }
+        OutputFormat::Jsonl => {
+            let mut line = serde_json::to_value(summary)?;
+            line["type"] = serde_json::json!("summary");
+            writeln!(writer, "{}", serde_json::to_string(&line)?)?;
+        }
+        OutputFormat::Csv | OutputFormat::Tsv => {
+            let mut csv_writer = format.table_writer(writer);
+            csv_writer.write_record(["total_files", "groups_found", "ungrouped_files", "threshold_used"])?;
+            csv_writer.write_record([
+                summary.total_files.to_string(),
+                summary.groups_found.to_string(),
+                summary.ungrouped_files.to_string(),
+                format_score(summary.threshold_used),
+            ])?;
+            csv_writer.flush()?;
+        }
+        OutputFormat::Html => {
+            anyhow::bail!("HTML output is not supported with --summary-only; use text, json, jsonl, csv or tsv");
+        }
+    }
+    Ok(())
+}
+
+/// Writes the result of `--reference`: the files similar to `reference`,
+/// most similar first.
+pub fn format_reference<W: Write>(
+    reference: &str,
+    threshold: f64,
+    matches: &[ReferenceMatch],
+    format: &OutputFormat,
+    writer: &mut W,
+) -> Result<()> {
+    match format {
+        OutputFormat::Text | OutputFormat::Table => {
+            if matches.is_empty() {
+                writeln!(writer, "No files similar to {} at {:.0}% or above", reference, threshold * 100.0)?;
+            } else {
+                writeln!(writer, "Files similar to {} ({:.0}% or above):", reference, threshold * 100.0)?;
+                for found in matches {
+                    writeln!(writer, "  {:>3.0}%  {}", found.similarity * 100.0, found.file)?;
+                }
+            }
+        }
+        OutputFormat::Json => {
+            let output = serde_json::json!({
+                "reference": reference,
+                "threshold_used": threshold,
+                "matches": matches,
+            });
+            writeln!(writer, "{}", serde_json::to_string_pretty(&output)?)?;
+        }
+        OutputFormat::Jsonl => {
+            for found in matches {
+                writeln!(writer, "{}", serde_json::to_string(found)?)?;
+            }
+        }
+        OutputFormat::Csv | OutputFormat::Tsv => {
+            let mut csv_writer = format.table_writer(writer);
+            csv_writer.write_record(["file", "similarity"])?;
+            for found in matches {
+                csv_writer.write_record([found.file.clone(), format!("{:.4}", found.similarity)])?;
+            }
+            csv_writer.flush()?;
+        }
+        OutputFormat::Html => {
+            anyhow::bail!("HTML output is not supported with --reference; use text, json, jsonl, csv or tsv");
+        }
+    }
+    Ok(())
+}
+
+/// Writes the result of `--sweep`: a table for text, csv and tsv, an array
+/// for json and one object per line for jsonl.
+pub fn format_sweep<W: Write>(rows: &[SweepRow], format: &OutputFormat, writer: &mut W) -> Result<()> {
+    match format {
+        OutputFormat::Text | OutputFormat::Table => {
+            writeln!(writer, "{:>9}  {:>6}  {:>7}  {:>9}", "Threshold", "Groups", "Grouped", "Ungrouped")?;
+            for row in rows {
+                writeln!(
+                    writer,
+                    "{:>8}%  {:>6}  {:>7}  {:>9}",
+                    row.threshold, row.groups, row.grouped_files, row.ungrouped_files
+                )?;
+            }
+        }
+        OutputFormat::Json => writeln!(writer, "{}", serde_json::to_string_pretty(rows)?)?,
+        OutputFormat::Jsonl => {
+            for row in rows {
+                writeln!(writer, "{}", serde_json::to_string(row)?)?;
+            }
+        }
+        OutputFormat::Csv | OutputFormat::Tsv => {
+            let mut csv_writer = format.table_writer(writer);
+            csv_writer.write_record(["threshold", "groups", "grouped_files", "ungrouped_files"])?;
+            for row in rows {
+                csv_writer.write_record([
+                    row.threshold.to_string(),
+                    row.groups.to_string(),
+                    row.grouped_files.to_string(),
+                    row.ungrouped_files.to_string(),
+                ])?;
+            }
+            csv_writer.flush()?;
+        }
+        OutputFormat::Html => {
+            anyhow::bail!("HTML output is not supported with --sweep; use text, json, jsonl, csv or tsv");
+        }
+    }
     Ok(())
 }
 
@@ -86,20 +366,272 @@ fn format_json<W: Write>(result: &GroupingResult, writer: &mut W, show_ungrouped
     Ok(())
 }
 
-fn format_csv<W: Write>(result: &GroupingResult, writer: &mut W, show_ungrouped: bool) -> Result<()> {
-    let mut csv_writer = csv::Writer::from_writer(writer);
+/// Metadata of one file in `--enrich` output. `size` and `modified` (seconds
+/// since the Unix epoch) are `None` when the file could not be read.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnrichedFile {
+    pub name: String,
+    pub size: Option<u64>,
+    pub modified: Option<u64>,
+    pub extension: String,
+}
+
+impl EnrichedFile {
+    /// Reads the metadata of `path`, listed in the result as `name`.
+    pub fn from_path(name: &str, path: &Path) -> Self {
+        match FileInfo::from_path(path) {
+            Ok(info) => Self {
+                name: name.to_string(),
+                size: Some(info.size),
+                modified: Some(info.last_modified),
+                extension: info.file_type,
+            },
+            Err(_) => Self {
+                name: name.to_string(),
+                size: None,
+                modified: None,
+                extension: Path::new(name).extension().and_then(|ext| ext.to_str()).unwrap_or("").to_string(),
+            },
+        }
+    }
+}
+
+/// Reads the metadata of discovered `paths`, keyed by the name each is listed
+/// under: the full path with `full_paths`, its file name otherwise.
+pub fn enrich_paths(paths: &[PathBuf], full_paths: bool) -> HashMap<String, EnrichedFile> {
+    paths
+        .iter()
+        .map(|path| {
+            let name = if full_paths {
+                path.to_string_lossy().into_owned()
+            } else {
+                path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+            };
+            let file = EnrichedFile::from_path(&name, path);
+            (name, file)
+        })
+        .collect()
+}
+
+/// Like the JSON output, but every file, grouped or not, is an object with
+/// its [`EnrichedFile`] metadata instead of a name (`--enrich`). Files
+/// missing from `metadata` are looked up on disk as listed.
+pub fn format_enriched_json<W: Write>(
+    result: &GroupingResult,
+    metadata: &HashMap<String, EnrichedFile>,
+    writer: &mut W,
+    show_ungrouped: bool,
+) -> Result<()> {
+    let enrich = |files: &[String]| -> Result<serde_json::Value> {
+        let files: Vec<EnrichedFile> = files
+            .iter()
+            .map(|file| metadata.get(file).cloned().unwrap_or_else(|| EnrichedFile::from_path(file, Path::new(file))))
+            .collect();
+        Ok(serde_json::to_value(files)?)
+    };
+
+    let mut output = serde_json::json!({
+        "groups": result.groups,
+        "summary": result.summary
+    });
+    if let Some(groups) = output["groups"].as_array_mut() {
+        for (value, group) in groups.iter_mut().zip(&result.groups) {
+            value["files"] = enrich(&group.files)?;
+        }
+    }
+    if show_ungrouped {
+        output["ungrouped"] = enrich(&result.ungrouped)?;
+    }
+
+    writeln!(writer, "{}", serde_json::to_string_pretty(&output)?)?;
+    Ok(())
+}
+
+fn format_jsonl<W: Write>(result: &GroupingResult, writer: &mut W, show_ungrouped: bool) -> Result<()> {
+    use serde_json::json;
     
+    // One compact object per line so the output can be streamed
+    for group in &result.groups {
+        let mut line = serde_json::to_value(group)?;
+        line["type"] = json!("group");
+        writeln!(writer, "{}", serde_json::to_string(&line)?)?;
+    }
+    
+    if show_ungrouped {
+        for file in &result.ungrouped {
+            writeln!(writer, "{}", json!({ "type": "ungrouped", "file": file }))?;
+        }
+    }
+    
+    let mut summary = serde_json::to_value(&result.summary)?;
+    summary["type"] = json!("summary");
+    writeln!(writer, "{}", serde_json::to_string(&summary)?)?;
+    Ok(())
+}
+
+/// Formats a similarity score with two decimals. Rust's float formatting never
+/// consults the process locale, so the decimal separator is always `.` and
+/// the field cannot be split by a comma-decimal locale.
+fn format_score(value: f64) -> String {
+    format!("{:.2}", value)
+}
+
+/// Width `table` output is fitted to when stdout is not a terminal.
+const DEFAULT_TABLE_WIDTH: usize = 100;
+
+/// Narrowest the file column of `table` output gets, however narrow the
+/// terminal.
+const MIN_FILE_COLUMN_WIDTH: usize = 12;
+
+/// Width of the terminal on stdout, or [`DEFAULT_TABLE_WIDTH`] when output
+/// is piped or redirected.
+fn table_width() -> usize {
+    console::Term::stdout()
+        .size_checked()
+        .map_or(DEFAULT_TABLE_WIDTH, |(_, columns)| columns as usize)
+}
+
+/// Renders groups as a bordered table with Group, Files and Similarity
+/// columns, one file per row, followed by the ungrouped files in a table of
+/// their own when `show_ungrouped`. The table is at most `width` columns
+/// wide; file names that don't fit are cut short with an ellipsis.
+fn format_table<W: Write>(result: &GroupingResult, writer: &mut W, show_ungrouped: bool, width: usize) -> Result<()> {
+    if result.groups.is_empty() {
+        writeln!(writer, "No similar file groups found.")?;
+    } else {
+        // One block per group, with its id and score on the first file's row
+        let blocks: Vec<Vec<[String; 3]>> = result
+            .groups
+            .iter()
+            .map(|group| {
+                group
+                    .files
+                    .iter()
+                    .enumerate()
+                    .map(|(index, file)| match index {
+                        0 => [group.id.to_string(), file.clone(), format!("{:.0}%", group.similarity * 100.0)],
+                        _ => [String::new(), file.clone(), String::new()],
+                    })
+                    .collect()
+            })
+            .collect();
+        write_bordered_table(writer, ["Group", "Files", "Similarity"], 1, &blocks, width)?;
+    }
+    
+    if show_ungrouped && !result.ungrouped.is_empty() {
+        writeln!(writer)?;
+        let rows: Vec<[String; 1]> = result.ungrouped.iter().map(|file| [file.clone()]).collect();
+        write_bordered_table(writer, ["Ungrouped files"], 0, &[rows], width)?;
+    }
+    
+    writeln!(writer)?;
+    write_text_summary(&result.summary, writer)
+}
+
+/// Writes the rows of `blocks` under `headers`, with a separator line
+/// between blocks. Every column is sized to its widest cell except
+/// `flexible`, which takes whatever is left of `width` and truncates longer
+/// cells.
+fn write_bordered_table<W: Write, const N: usize>(
+    writer: &mut W,
+    headers: [&str; N],
+    flexible: usize,
+    blocks: &[Vec<[String; N]>],
+    width: usize,
+) -> Result<()> {
+    let mut widths: [usize; N] = headers.map(console::measure_text_width);
+    for row in blocks.iter().flatten() {
+        for (column, cell) in row.iter().enumerate() {
+            widths[column] = widths[column].max(console::measure_text_width(cell));
+        }
+    }
+    // Each column takes its width plus "| " before it and " " after it,
+    // and the table is closed by a final "|"
+    let fixed: usize = widths.iter().enumerate().filter(|&(column, _)| column != flexible).map(|(_, width)| width + 3).sum();
+    let budget = width.saturating_sub(fixed + 4).max(MIN_FILE_COLUMN_WIDTH);
+    widths[flexible] = widths[flexible].min(budget);
+    
+    let border: String = widths.iter().map(|width| format!("+{}", "-".repeat(width + 2))).collect::<String>() + "+";
+    let write_row = |writer: &mut W, cells: [&str; N]| -> Result<()> {
+        for (cell, &width) in cells.iter().zip(&widths) {
+            // truncate_str also shortens a cell exactly `width` wide
+            let cell = if console::measure_text_width(cell) > width {
+                console::truncate_str(cell, width, "…")
+            } else {
+                std::borrow::Cow::Borrowed(*cell)
+            };
+            write!(writer, "| {} ", console::pad_str(&cell, width, console::Alignment::Left, None))?;
+        }
+        writeln!(writer, "|")?;
+        Ok(())
+    };
+    
+    writeln!(writer, "{}", border)?;
+    write_row(writer, headers)?;
+    writeln!(writer, "{}", border)?;
+    for block in blocks {
+        for row in block {
+            write_row(writer, row.each_ref().map(String::as_str))?;
+        }
+        writeln!(writer, "{}", border)?;
+    }
+    Ok(())
+}
+
+/// Writes `# key=value` lines describing the run, for `--csv-comment-header`:
+/// the threshold as a percentage, the algorithms (comma-separated) and when
+/// the report was `generated`. CSV has no comment syntax, so readers must be
+/// told to skip lines starting with `#` (e.g. pandas' `comment="#"`).
+pub fn format_comment_header<W: Write>(
+    threshold: f64,
+    algorithms: &[Algorithm],
+    generated: chrono::DateTime<chrono::Utc>,
+    writer: &mut W,
+) -> Result<()> {
+    let names: Vec<String> = algorithms
+        .iter()
+        .filter_map(|algorithm| algorithm.to_possible_value())
+        .map(|value| value.get_name().to_string())
+        .collect();
+    // Rounded to a tenth so 0.725 reads 72.5 rather than 72.49999999999999
+    writeln!(writer, "# threshold={}", (threshold * 1000.0).round() / 10.0)?;
+    writeln!(writer, "# algorithm={}", names.join(","))?;
+    writeln!(writer, "# generated={}", generated.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))?;
+    Ok(())
+}
+
+fn format_csv<W: Write>(result: &GroupingResult, writer: &mut W, show_ungrouped: bool) -> Result<()> {
+    write_table(result, csv::Writer::from_writer(writer), show_ungrouped)
+}
+
+/// Like [`format_csv`] but tab-separated, so names containing commas need no
+/// quoting.
+fn format_tsv<W: Write>(result: &GroupingResult, writer: &mut W, show_ungrouped: bool) -> Result<()> {
+    let tsv_writer = csv::WriterBuilder::new().delimiter(b'\t').from_writer(writer);
+    write_table(result, tsv_writer, show_ungrouped)
+}
+
+fn write_table<W: Write>(result: &GroupingResult, mut csv_writer: csv::Writer<W>, show_ungrouped: bool) -> Result<()> {
     // Write header
-    csv_writer.write_record(&["group_id", "file_name", "similarity", "status"])?;
+    csv_writer.write_record([
+        "group_id",
+        "file_name",
+        "similarity",
+        "status",
+        "min_similarity",
+        "max_similarity",
+    ])?;
     
     // Write grouped files
     for group in &result.groups {
         for file in &group.files {
-            csv_writer.write_record(&[
+            csv_writer.write_record([
                 group.id.to_string(),
                 file.clone(),
-                format!("{:.2}", group.similarity),
+                format_score(group.similarity),
                 "grouped".to_string(),
+                format_score(group.min_similarity),
+                format_score(group.max_similarity),
             ])?;
         }
     }
@@ -107,11 +639,13 @@ fn format_csv<W: Write>(result: &GroupingResult, writer: &mut W, show_ungrouped:
     // Write ungrouped files only if show_ungrouped is true
     if show_ungrouped {
         for file in &result.ungrouped {
-            csv_writer.write_record(&[
+            csv_writer.write_record([
                 "".to_string(),
                 file.clone(),
                 "".to_string(),
                 "ungrouped".to_string(),
+                "".to_string(),
+                "".to_string(),
             ])?;
         }
     }
@@ -120,28 +654,313 @@ fn format_csv<W: Write>(result: &GroupingResult, writer: &mut W, show_ungrouped:
     Ok(())
 }
 
+/// Inputs above this size make `--matrix` slow and its output unwieldy.
+pub const MATRIX_WARN_FILES: usize = 500;
+
+/// Writes the pairwise similarity of every file against every other as CSV,
+/// with the file names as row and column headers. Scores are symmetric and
+/// the diagonal is always 1.
+#[allow(clippy::too_many_arguments)]
+pub fn format_matrix<W: Write>(
+    files: &[String],
+    algorithms: &[Algorithm],
+    require: &Require,
+    weights: &ComponentWeights,
+    case_sensitive: bool,
+    calibrate: bool,
+    affixes: &Affixes,
+    writer: &mut W,
+) -> Result<()> {
+    let names: Vec<_> = files.iter().map(|file| affixes.strip(file)).collect();
+    let mut scores = vec![vec![1.0; files.len()]; files.len()];
+    for i in 0..names.len() {
+        for j in (i + 1)..names.len() {
+            let similarity = calculate_combined_similarity(&names[i], &names[j], algorithms, require, weights, case_sensitive, calibrate);
+            scores[i][j] = similarity;
+            scores[j][i] = similarity;
+        }
+    }
+    
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    let mut header = vec!["file".to_string()];
+    header.extend(files.iter().cloned());
+    csv_writer.write_record(&header)?;
+    for (file, row) in files.iter().zip(&scores) {
+        let mut record = vec![file.clone()];
+        // More precision than the grouped output, to compare against fractional thresholds
+        record.extend(row.iter().map(|score| format!("{:.4}", score)));
+        csv_writer.write_record(&record)?;
+    }
+    
+    csv_writer.flush()?;
+    Ok(())
+}
+
+/// Tokens of one file name, as listed by `--dump-tokens`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileTokens {
+    pub file: String,
+    pub tokens: Vec<String>,
+}
+
+/// Lists how each file name is tokenized after affix stripping and, unless
+/// `case_sensitive`, lowercasing, i.e. exactly what the token algorithm
+/// compares. JSON formats emit [`FileTokens`]; others print one line per file.
+pub fn format_tokens<W: Write>(
+    files: &[String],
+    case_sensitive: bool,
+    affixes: &Affixes,
+    format: &OutputFormat,
+    writer: &mut W,
+) -> Result<()> {
+    let entries: Vec<FileTokens> = files
+        .iter()
+        .map(|file| {
+            let name = affixes.strip(file);
+            let name = if case_sensitive { name.into_owned() } else { name.to_lowercase() };
+            FileTokens {
+                file: file.clone(),
+                tokens: tokenize(&name),
+            }
+        })
+        .collect();
+    
+    match format {
+        OutputFormat::Json => writeln!(writer, "{}", serde_json::to_string_pretty(&entries)?)?,
+        OutputFormat::Jsonl => {
+            for entry in &entries {
+                writeln!(writer, "{}", serde_json::to_string(entry)?)?;
+            }
+        }
+        _ => {
+            for entry in &entries {
+                writeln!(writer, "{}: {}", entry.file, entry.tokens.join(" | "))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+const HTML_STYLE: &str = "body { font-family: sans-serif; margin: 2em; }
+details { border: 1px solid #ccc; border-radius: 4px; margin: 0.5em 0; padding: 0.5em; }
+summary { cursor: pointer; font-weight: bold; }
+ul { list-style: none; padding-left: 1em; }
+.meta { color: #666; margin-left: 0.5em; }
+textarea { width: 100%; height: 8em; }";
+
+const HTML_SCRIPT: &str = "function selectedPaths() {
+  return Array.from(document.querySelectorAll('input.file-select:checked')).map(function (box) { return box.dataset.path; });
+}
+function updateSelection() {
+  document.getElementById('selected-paths').value = selectedPaths().join('\\n');
+}
+document.querySelectorAll('input.file-select').forEach(function (box) { box.addEventListener('change', updateSelection); });
+document.getElementById('copy-selected').addEventListener('click', function () {
+  updateSelection();
+  var text = document.getElementById('selected-paths').value;
+  if (navigator.clipboard) { navigator.clipboard.writeText(text); }
+});";
+
+/// Renders a standalone page with one collapsible section per group, a
+/// checkbox per file and a button that copies the selected paths. Sizes are
+/// shown for files that can be found on disk.
+fn format_html<W: Write>(result: &GroupingResult, writer: &mut W, show_ungrouped: bool) -> Result<()> {
+    writeln!(writer, "<!DOCTYPE html>")?;
+    writeln!(writer, "<html lang=\"en\">")?;
+    writeln!(writer, "<head>\n<meta charset=\"utf-8\">\n<title>Similarity report</title>")?;
+    writeln!(writer, "<style>\n{}\n</style>\n</head>\n<body>", HTML_STYLE)?;
+    writeln!(writer, "<h1>Similarity report</h1>")?;
+    writeln!(
+        writer,
+        "<p>{} files, {} groups, {} ungrouped (threshold: {:.0}%)</p>",
+        result.summary.total_files,
+        result.summary.groups_found,
+        result.summary.ungrouped_files,
+        result.summary.threshold_used * 100.0
+    )?;
+    
+    for group in &result.groups {
+        let label = group
+            .label
+            .as_ref()
+            .map(|label| format!(" [{}]", escape_html(label)))
+            .unwrap_or_default();
+        writeln!(writer, "<details class=\"group\" open>")?;
+        writeln!(
+            writer,
+            "<summary>Group {}{} (similarity: {:.0}%, {}) &mdash; {} files</summary>",
+            group.id,
+            label,
+            group.similarity * 100.0,
+            group.match_reason.label(),
+            group.files.len()
+        )?;
+        writeln!(writer, "<ul>")?;
+        for file in &group.files {
+            let size = fs::metadata(file)
+                .map(|metadata| format!("<span class=\"meta\">{}</span>", format_size(metadata.len())))
+                .unwrap_or_default();
+            writeln!(
+                writer,
+                "<li><label><input type=\"checkbox\" class=\"file-select\" data-path=\"{}\"> {}</label>{}</li>",
+                escape_html(file),
+                escape_html(file),
+                size
+            )?;
+        }
+        writeln!(writer, "</ul>\n</details>")?;
+    }
+    
+    if show_ungrouped && !result.ungrouped.is_empty() {
+        writeln!(writer, "<details class=\"ungrouped\">")?;
+        writeln!(writer, "<summary>Ungrouped files ({})</summary>\n<ul>", result.ungrouped.len())?;
+        for file in &result.ungrouped {
+            writeln!(writer, "<li>{}</li>", escape_html(file))?;
+        }
+        writeln!(writer, "</ul>\n</details>")?;
+    }
+    
+    writeln!(writer, "<p><button type=\"button\" id=\"copy-selected\">Copy selected paths</button></p>")?;
+    writeln!(writer, "<textarea id=\"selected-paths\" readonly></textarea>")?;
+    writeln!(writer, "<script>\n{}\n</script>\n</body>\n</html>", HTML_SCRIPT)?;
+    Ok(())
+}
+
+fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for ch in s.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::grouper::{Group, Summary};
+    use crate::grouper::{Group, MatchReason};
 
     fn create_test_result() -> GroupingResult {
-        GroupingResult {
-            groups: vec![
-                Group {
-                    id: 1,
-                    files: vec!["file1.txt".to_string(), "file2.txt".to_string()],
-                    similarity: 0.85,
-                },
-            ],
-            ungrouped: vec!["different.doc".to_string()],
-            summary: Summary {
-                total_files: 3,
-                groups_found: 1,
-                ungrouped_files: 1,
-                threshold_used: 0.7,
-            },
-        }
+        let mut group = Group::fixture(1, &["file1.txt", "file2.txt"], 0.85);
+        group.min_similarity = 0.8;
+        group.max_similarity = 0.9;
+        GroupingResult::fixture(vec![group], &["different.doc"])
+    }
+
+    #[test]
+    fn test_format_table() {
+        let mut result = create_test_result();
+        let long_name = format!("{}_v2.txt", "quarterly_report".repeat(8));
+        result.groups[0].files.push(long_name.clone());
+        let mut output = Vec::new();
+        format_table(&result, &mut output, true, 60).unwrap();
+        
+        let table = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[1].split('|').map(str::trim).collect::<Vec<_>>(), ["", "Group", "Files", "Similarity", ""]);
+        assert!(lines[3].starts_with("| 1     | file1.txt") && lines[3].contains("| 85%        |"), "{}", table);
+        assert!(table.contains("| Ungrouped files |") && table.contains("| different.doc   |"));
+        
+        // Every row of the group table fits the width, the long name cut short
+        let group_rows: Vec<&str> = lines.iter().take_while(|line| !line.is_empty()).copied().collect();
+        assert!(group_rows.iter().all(|line| console::measure_text_width(line) == 60), "{}", table);
+        let truncated = group_rows.iter().find(|line| line.contains("quarterly_report")).unwrap();
+        assert!(truncated.contains("…") && !truncated.contains(&long_name));
+        
+        // Without the long name the table only takes the room it needs
+        result.groups[0].files.pop();
+        let mut output = Vec::new();
+        format_table(&result, &mut output, false, 60).unwrap();
+        let table = String::from_utf8(output).unwrap();
+        assert!(table.lines().next().unwrap().len() < 60);
+        assert!(!table.contains("…") && !table.contains("| Ungrouped files |"));
+    }
+
+    #[test]
+    fn test_format_reference() {
+        let matches = vec![
+            ReferenceMatch { file: "report_v2.pdf".to_string(), similarity: 0.9 },
+            ReferenceMatch { file: "report_final.pdf".to_string(), similarity: 0.625 },
+        ];
+        
+        let mut output = Vec::new();
+        format_reference("report.pdf", 0.6, &matches, &OutputFormat::Text, &mut output).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        assert_eq!(text, "Files similar to report.pdf (60% or above):\n   90%  report_v2.pdf\n   62%  report_final.pdf\n");
+        
+        let mut output = Vec::new();
+        format_reference("report.pdf", 0.6, &matches, &OutputFormat::Json, &mut output).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(json["reference"], "report.pdf");
+        assert_eq!(json["matches"][1]["file"], "report_final.pdf");
+        
+        let mut output = Vec::new();
+        format_reference("report.pdf", 0.95, &[], &OutputFormat::Text, &mut output).unwrap();
+        assert!(String::from_utf8(output).unwrap().starts_with("No files similar to report.pdf at 95% or above"));
+        
+        assert!(format_reference("report.pdf", 0.6, &matches, &OutputFormat::Html, &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_format_enriched_json() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("nested")).unwrap();
+        fs::write(temp_dir.path().join("file1.txt"), "hello").unwrap();
+        fs::write(temp_dir.path().join("nested").join("file2.txt"), "hello world").unwrap();
+        fs::write(temp_dir.path().join("different.doc"), "").unwrap();
+        let paths = crate::input::FileDiscovery::new().discover_paths(temp_dir.path()).unwrap();
+
+        let metadata = enrich_paths(&paths, false);
+        let mut output = Vec::new();
+        format_enriched_json(&create_test_result(), &metadata, &mut output, true).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+
+        let files = json["groups"][0]["files"].as_array().unwrap();
+        assert_eq!(files[0]["name"], "file1.txt");
+        assert_eq!(files[0]["size"], 5);
+        assert_eq!(files[0]["extension"], "txt");
+        assert_eq!(files[1]["name"], "file2.txt");
+        assert_eq!(files[1]["size"], 11);
+        let modified = fs::metadata(temp_dir.path().join("file1.txt"))
+            .unwrap()
+            .modified()
+            .unwrap()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        assert_eq!(files[0]["modified"], modified);
+        assert_eq!(json["groups"][0]["similarity"], 0.85);
+        assert_eq!(json["ungrouped"][0]["name"], "different.doc");
+        assert_eq!(json["ungrouped"][0]["size"], 0);
+        assert_eq!(json["ungrouped"][0]["extension"], "doc");
+
+        // Full paths are keyed as listed; unknown files keep their name
+        let metadata = enrich_paths(&paths, true);
+        let listed = temp_dir.path().join("file1.txt").to_string_lossy().into_owned();
+        assert_eq!(metadata[&listed].size, Some(5));
+        let missing = EnrichedFile::from_path("gone.pdf", Path::new("/nonexistent/gone.pdf"));
+        assert_eq!(missing.size, None);
+        assert_eq!(missing.extension, "pdf");
     }
 
     #[test]
@@ -154,6 +973,69 @@ mod tests {
         assert!(json_str.contains("\"id\": 1"));
         assert!(json_str.contains("\"file1.txt\""));
         assert!(json_str.contains("\"ungrouped\""));
+        assert!(json_str.contains("\"match_reason\": \"name_similarity\""));
+        assert!(json_str.contains("\"min_similarity\": 0.8"));
+        assert!(json_str.contains("\"max_similarity\": 0.9"));
+    }
+
+    #[test]
+    fn test_format_text_shows_match_reason() {
+        let mut result = create_test_result();
+        result.groups[0].match_reason = MatchReason::IdenticalContent;
+        
+        let mut output = Vec::new();
+        format_text(&result, &mut output, false).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        assert!(text.contains("Group 1 (similarity: 85%, identical content):"));
+    }
+
+    #[test]
+    fn test_format_jsonl() {
+        let result = create_test_result();
+        let mut output = Vec::new();
+        format_jsonl(&result, &mut output, false).unwrap();
+        
+        let jsonl_str = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = jsonl_str.lines().collect();
+        assert_eq!(lines.len(), result.groups.len() + 1);
+        
+        let group: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(group["type"], "group");
+        assert_eq!(group["id"], 1);
+        
+        let summary: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(summary["type"], "summary");
+        assert_eq!(summary["total_files"], 3);
+    }
+
+    #[test]
+    fn test_format_jsonl_with_ungrouped() {
+        let result = create_test_result();
+        let mut output = Vec::new();
+        format_jsonl(&result, &mut output, true).unwrap();
+        
+        let jsonl_str = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = jsonl_str.lines().collect();
+        assert_eq!(lines.len(), result.groups.len() + result.ungrouped.len() + 1);
+        assert!(lines[1].contains("\"type\":\"ungrouped\""));
+        assert!(lines[1].contains("different.doc"));
+    }
+
+    #[test]
+    fn test_format_text_labels_case_only_groups() {
+        let mut result = create_test_result();
+        result.groups[0].files = vec!["Photo.JPG".to_string(), "photo.jpg".to_string()];
+        result.groups[0].case_only = true;
+        
+        let mut output = Vec::new();
+        format_text(&result, &mut output, false).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        assert!(text.contains("case-only variants"));
+        
+        let mut output = Vec::new();
+        format_json(&result, &mut output, false).unwrap();
+        let json_str = String::from_utf8(output).unwrap();
+        assert!(json_str.contains("\"case_only\": true"));
     }
 
     #[test]
@@ -164,7 +1046,246 @@ mod tests {
         
         let csv_str = String::from_utf8(output).unwrap();
         assert!(csv_str.contains("group_id,file_name,similarity,status"));
-        assert!(csv_str.contains("1,file1.txt,0.85,grouped"));
-        assert!(csv_str.contains(",different.doc,,ungrouped"));
+        assert!(csv_str.contains("1,file1.txt,0.85,grouped,0.80,0.90"));
+        assert!(csv_str.contains(",different.doc,,ungrouped,,"));
+        assert!(csv_str.contains("min_similarity,max_similarity"));
+    }
+
+    #[test]
+    fn test_format_comment_header() {
+        let generated = chrono::DateTime::parse_from_rfc3339("2024-05-01T09:30:00Z").unwrap().to_utc();
+        let mut output = Vec::new();
+        format_comment_header(0.7, &[Algorithm::Auto], generated, &mut output).unwrap();
+        format_csv(&create_test_result(), &mut output, false).unwrap();
+        
+        let csv_str = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = csv_str.lines().collect();
+        assert_eq!(lines[..4], ["# threshold=70", "# algorithm=auto", "# generated=2024-05-01T09:30:00Z", "group_id,file_name,similarity,status,min_similarity,max_similarity"]);
+        
+        // Readers told about the comment prefix see the table as before
+        let mut reader = csv::ReaderBuilder::new().comment(Some(b'#')).from_reader(csv_str.as_bytes());
+        assert_eq!(&reader.headers().unwrap()[0], "group_id");
+        assert_eq!(reader.records().count(), 2);
+        
+        let mut output = Vec::new();
+        format_comment_header(0.725, &[Algorithm::Levenshtein, Algorithm::Jaro], generated, &mut output).unwrap();
+        let header = String::from_utf8(output).unwrap();
+        assert!(header.starts_with("# threshold=72.5\n# algorithm=levenshtein,jaro\n"));
+    }
+
+    #[test]
+    fn test_format_csv_is_locale_independent() {
+        let mut result = create_test_result();
+        result.groups[0].files = vec!["a,b.txt".to_string(), "a;b.txt".to_string()];
+        result.groups[0].similarity = 0.5;
+        let mut output = Vec::new();
+        format_csv(&result, &mut output, false).unwrap();
+        
+        let mut reader = csv::Reader::from_reader(output.as_slice());
+        let records: Vec<csv::StringRecord> = reader.records().map(|r| r.unwrap()).collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(&records[0][1], "a,b.txt");
+        assert_eq!(&records[0][2], "0.50");
+        assert_eq!(format_score(1234.5), "1234.50");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_format_tsv() {
+        let mut result = create_test_result();
+        result.groups[0].files = vec!["report, final.pdf".to_string(), "report, draft.pdf".to_string()];
+        let mut output = Vec::new();
+        format_tsv(&result, &mut output, true).unwrap();
+        
+        let tsv_str = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = tsv_str.lines().collect();
+        assert_eq!(lines[0], "group_id\tfile_name\tsimilarity\tstatus\tmin_similarity\tmax_similarity");
+        // Commas are plain characters here, so the name is not quoted
+        assert_eq!(lines[1], "1\treport, final.pdf\t0.85\tgrouped\t0.80\t0.90");
+        assert!(lines.contains(&"\tdifferent.doc\t\tungrouped\t\t"));
+        assert!(lines.iter().all(|line| line.split('\t').count() == 6));
+        assert_eq!(OutputFormat::Tsv.extension(), "tsv");
+    }
+
+    #[test]
+    fn test_write_pages() {
+        let mut result = create_test_result();
+        let template = result.groups[0].clone();
+        result.groups = (1..=5)
+            .map(|id| Group { id, files: vec![format!("file{}.txt", id)], ..template.clone() })
+            .collect();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        
+        let index_path = write_pages(&result, &OutputFormat::Json, true, 2, temp_dir.path()).unwrap();
+        let index: PageIndex = serde_json::from_str(&std::fs::read_to_string(&index_path).unwrap()).unwrap();
+        assert_eq!(index.pages, vec!["report.001.json", "report.002.json", "report.003.json"]);
+        assert_eq!(index.total_groups, 5);
+        assert_eq!(index.ungrouped, vec!["different.doc"]);
+        
+        let mut ids = Vec::new();
+        for page in &index.pages {
+            let content = std::fs::read_to_string(temp_dir.path().join(page)).unwrap();
+            let value: serde_json::Value = serde_json::from_str(&content).unwrap();
+            ids.extend(value["groups"].as_array().unwrap().iter().map(|g| g["id"].as_u64().unwrap()));
+        }
+        assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+        
+        assert!(write_pages(&result, &OutputFormat::Json, true, 0, temp_dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_write_split() {
+        let mut result = create_test_result();
+        result.groups.push(Group { id: 2, files: vec!["photo.jpg".to_string(), "photo (1).jpg".to_string()], ..result.groups[0].clone() });
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path().join("groups");
+
+        let paths = write_split(&result, &OutputFormat::Text, true, &dir, false).unwrap();
+        assert_eq!(paths, vec![dir.join("group-001.txt"), dir.join("group-002.txt"), dir.join("ungrouped.txt")]);
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+
+        let first = read("group-001.txt");
+        assert!(first.contains("Group 1 (similarity: 85%"));
+        assert!(first.contains("file1.txt") && first.contains("file2.txt"));
+        assert!(!first.contains("photo.jpg") && !first.contains("different.doc"));
+        let second = read("group-002.txt");
+        assert!(second.contains("Group 2 (similarity: 85%"));
+        assert!(second.contains("photo (1).jpg"));
+        assert!(!second.contains("file1.txt"));
+        let ungrouped = read("ungrouped.txt");
+        assert!(ungrouped.contains("different.doc"));
+        assert!(!ungrouped.contains("file1.txt"));
+
+        // The directory now has files in it
+        let error = write_split(&result, &OutputFormat::Json, true, &dir, false).unwrap_err().to_string();
+        assert!(error.contains("--force"), "{}", error);
+        write_split(&result, &OutputFormat::Json, false, &dir, true).unwrap();
+        let part: GroupingResult = serde_json::from_str(&read("group-002.json")).unwrap();
+        assert_eq!(part.groups[0].files, vec!["photo.jpg", "photo (1).jpg"]);
+        assert_eq!(part.summary.total_files, 2);
+        assert!(!dir.join("ungrouped.json").exists());
+    }
+
+    #[test]
+    fn test_format_html() {
+        let mut result = create_test_result();
+        result.groups[0].files.push("a&b <draft>.txt".to_string());
+        let mut output = Vec::new();
+        format_html(&result, &mut output, true).unwrap();
+        
+        let html = String::from_utf8(output).unwrap();
+        assert!(html.contains("<details class=\"group\" open>"));
+        assert!(html.contains("Group 1 (similarity: 85%, name similarity)"));
+        assert!(html.contains("id=\"copy-selected\""));
+        assert!(html.contains("navigator.clipboard"));
+        assert_eq!(html.matches("class=\"file-select\"").count(), 3);
+        for file in ["file1.txt", "file2.txt", "different.doc"] {
+            assert!(html.contains(file), "missing {}", file);
+        }
+        assert!(html.contains("data-path=\"a&amp;b &lt;draft&gt;.txt\""));
+        assert!(!html.contains("<draft>"));
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MB");
+    }
+
+    #[test]
+    fn test_format_matrix() {
+        let files = vec!["report_v1.pdf".to_string(), "report_v2.pdf".to_string(), "holiday.jpg".to_string()];
+        let mut output = Vec::new();
+        format_matrix(&files, &[Algorithm::Levenshtein], &Require::All, &ComponentWeights::default(), false, false, &Affixes::default(), &mut output).unwrap();
+        
+        let mut reader = csv::Reader::from_reader(output.as_slice());
+        let header: Vec<String> = reader.headers().unwrap().iter().map(String::from).collect();
+        assert_eq!(header, vec!["file", "report_v1.pdf", "report_v2.pdf", "holiday.jpg"]);
+        
+        let rows: Vec<csv::StringRecord> = reader.records().map(|r| r.unwrap()).collect();
+        assert_eq!(rows.len(), 3);
+        let score = |i: usize, j: usize| rows[i][j + 1].parse::<f64>().unwrap();
+        for (i, file) in files.iter().enumerate() {
+            assert_eq!(&rows[i][0], file);
+            assert_eq!(score(i, i), 1.0);
+            for j in 0..files.len() {
+                assert_eq!(score(i, j), score(j, i));
+            }
+        }
+        assert!(score(0, 1) > 0.9);
+        assert!(score(0, 2) < 0.5);
+    }
+
+    #[test]
+    fn test_format_tokens_matches_tokenize() {
+        let files: Vec<String> = ["Report_2024-final.pdf", "IMG 0042.JPG", "notes", "a..b__c"]
+            .iter()
+            .map(|f| f.to_string())
+            .collect();
+        let mut output = Vec::new();
+        format_tokens(&files, false, &Affixes::default(), &OutputFormat::Json, &mut output).unwrap();
+        
+        let entries: Vec<FileTokens> = serde_json::from_slice(&output).unwrap();
+        assert_eq!(entries.len(), files.len());
+        for (entry, file) in entries.iter().zip(&files) {
+            assert_eq!(&entry.file, file);
+            assert_eq!(entry.tokens, tokenize(&file.to_lowercase()));
+        }
+        assert_eq!(entries[0].tokens, vec!["report", "2024", "final", "pdf"]);
+        
+        let mut output = Vec::new();
+        format_tokens(&files[..1], true, &Affixes::default(), &OutputFormat::Text, &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "Report_2024-final.pdf: Report | 2024 | final | pdf\n");
+    }
+
+    #[test]
+    fn test_format_schema() {
+        let mut output = Vec::new();
+        format_schema(&mut output).unwrap();
+        let schema: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        
+        assert_eq!(schema["title"], "GroupingResult");
+        let properties = schema["properties"].as_object().unwrap();
+        for name in ["groups", "ungrouped", "summary"] {
+            assert!(properties.contains_key(name), "missing {}", name);
+        }
+        // --show-ungrouped false leaves `ungrouped` out
+        let required = schema["required"].as_array().unwrap();
+        assert!(!required.contains(&serde_json::json!("ungrouped")));
+        
+        let group = &schema["definitions"]["Group"]["properties"];
+        for name in ["id", "files", "similarity", "min_similarity", "max_similarity", "match_reason"] {
+            assert!(group.get(name).is_some(), "missing Group.{}", name);
+        }
+        let summary = &schema["definitions"]["Summary"]["properties"];
+        for name in ["total_files", "groups_found", "ungrouped_files", "threshold_used"] {
+            assert!(summary.get(name).is_some(), "missing Summary.{}", name);
+        }
+    }
+
+    #[test]
+    fn test_format_summary_json() {
+        let result = create_test_result();
+        let mut output = Vec::new();
+        format_summary(&result.summary, &OutputFormat::Json, &mut output).unwrap();
+        
+        let value: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert!(value.get("groups").is_none());
+        assert!(value.get("ungrouped").is_none());
+        assert_eq!(value.as_object().unwrap().len(), 1);
+        
+        let summary: Summary = serde_json::from_value(value["summary"].clone()).unwrap();
+        assert_eq!(summary.total_files, result.summary.total_files);
+        assert_eq!(summary.groups_found, result.summary.groups_found);
+        assert_eq!(summary.ungrouped_files, result.summary.ungrouped_files);
+        assert_eq!(summary.threshold_used, result.summary.threshold_used);
+        
+        let mut output = Vec::new();
+        format_summary(&result.summary, &OutputFormat::Text, &mut output).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        assert!(text.contains("Groups found: 1"));
+        assert!(!text.contains("file1.txt"));
+        
+        assert!(format_summary(&result.summary, &OutputFormat::Html, &mut Vec::new()).is_err());
+    }
+}
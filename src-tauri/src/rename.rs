@@ -0,0 +1,197 @@
+use crate::cli::{OutputFormat, RenamePolicy};
+use crate::grouper::GroupingResult;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenameProposal {
+    pub group_id: usize,
+    pub old_name: String,
+    pub new_name: String,
+}
+
+/// Proposes a consistent `<base>_<n>.<ext>` name for every grouped file.
+///
+/// The base is picked from the group's file stems according to `policy`;
+/// each file keeps its own directory and extension. Nothing on disk is touched.
+pub fn propose_renames(result: &GroupingResult, policy: &RenamePolicy) -> Vec<RenameProposal> {
+    let mut proposals = Vec::new();
+
+    for group in &result.groups {
+        let stems: Vec<String> = group.files.iter().map(|file| file_stem(file)).collect();
+        let base = choose_base(&stems, policy);
+
+        for (index, file) in group.files.iter().enumerate() {
+            let path = Path::new(file);
+            let mut new_name = format!("{}_{}", base, index + 1);
+            if let Some(ext) = path.extension().and_then(|ext| ext.to_str()) {
+                new_name.push('.');
+                new_name.push_str(ext);
+            }
+
+            let new_path = match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => {
+                    parent.join(&new_name).to_string_lossy().to_string()
+                }
+                _ => new_name,
+            };
+
+            proposals.push(RenameProposal {
+                group_id: group.id,
+                old_name: file.clone(),
+                new_name: new_path,
+            });
+        }
+    }
+
+    proposals
+}
+
+fn file_stem(file: &str) -> String {
+    Path::new(file)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(file)
+        .to_string()
+}
+
+fn choose_base(stems: &[String], policy: &RenamePolicy) -> String {
+    let shortest = || {
+        stems
+            .iter()
+            .min_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+            .cloned()
+            .unwrap_or_default()
+    };
+
+    match policy {
+        RenamePolicy::First => stems.first().cloned().unwrap_or_default(),
+        RenamePolicy::Shortest => shortest(),
+        RenamePolicy::MostCommon => {
+            let mut counts: HashMap<&String, usize> = HashMap::new();
+            for stem in stems {
+                *counts.entry(stem).or_insert(0) += 1;
+            }
+
+            // Ties (including all-distinct stems) fall back to the shortest name
+            let max_count = counts.values().copied().max().unwrap_or(0);
+            if max_count <= 1 {
+                return shortest();
+            }
+            counts
+                .into_iter()
+                .filter(|(_, count)| *count == max_count)
+                .map(|(stem, _)| stem)
+                .min_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+                .cloned()
+                .unwrap_or_default()
+        }
+    }
+}
+
+pub fn format_rename_preview<W: Write>(
+    proposals: &[RenameProposal],
+    format: &OutputFormat,
+    writer: &mut W,
+) -> Result<()> {
+    match format {
+        OutputFormat::Json => {
+            writeln!(writer, "{}", serde_json::to_string_pretty(proposals)?)?;
+        }
+        OutputFormat::Jsonl => {
+            for proposal in proposals {
+                writeln!(writer, "{}", serde_json::to_string(proposal)?)?;
+            }
+        }
+        OutputFormat::Csv | OutputFormat::Tsv => {
+            let mut csv_writer = format.table_writer(writer);
+            csv_writer.write_record(["group_id", "old_name", "new_name"])?;
+            for proposal in proposals {
+                csv_writer.write_record([
+                    proposal.group_id.to_string(),
+                    proposal.old_name.clone(),
+                    proposal.new_name.clone(),
+                ])?;
+            }
+            csv_writer.flush()?;
+        }
+        OutputFormat::Html => {
+            anyhow::bail!("HTML output is not supported with --rename-preview; use text, json, jsonl, csv or tsv");
+        }
+        OutputFormat::Text | OutputFormat::Table => {
+            let width = proposals.iter().map(|p| p.old_name.chars().count()).max().unwrap_or(0);
+            let mut current_group = None;
+            for proposal in proposals {
+                if current_group != Some(proposal.group_id) {
+                    if current_group.is_some() {
+                        writeln!(writer)?;
+                    }
+                    writeln!(writer, "Group {}:", proposal.group_id)?;
+                    current_group = Some(proposal.group_id);
+                }
+                writeln!(writer, "  {:<width$} -> {}", proposal.old_name, proposal.new_name, width = width)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grouper::Group;
+    use std::collections::HashSet;
+
+    fn create_test_result(files: Vec<&str>) -> GroupingResult {
+        GroupingResult::fixture(vec![Group::fixture(1, &files, 0.9)], &[])
+    }
+
+    #[test]
+    fn test_propose_renames_unique_within_group() {
+        let result = create_test_result(vec!["report.pdf", "report.pdf", "report (1).pdf", "report_final.docx"]);
+        let proposals = propose_renames(&result, &RenamePolicy::MostCommon);
+
+        assert_eq!(proposals.len(), 4);
+        let names: HashSet<&String> = proposals.iter().map(|p| &p.new_name).collect();
+        assert_eq!(names.len(), 4);
+        assert_eq!(proposals[0].new_name, "report_1.pdf");
+        assert_eq!(proposals[3].new_name, "report_4.docx");
+    }
+
+    #[test]
+    fn test_rename_policies() {
+        let stems = vec!["summary_draft".to_string(), "summary".to_string(), "summary_v2".to_string()];
+        assert_eq!(choose_base(&stems, &RenamePolicy::First), "summary_draft");
+        assert_eq!(choose_base(&stems, &RenamePolicy::Shortest), "summary");
+        // All stems distinct, so most-common falls back to shortest
+        assert_eq!(choose_base(&stems, &RenamePolicy::MostCommon), "summary");
+
+        let stems = vec!["notes_a".to_string(), "notes_long".to_string(), "notes_long".to_string()];
+        assert_eq!(choose_base(&stems, &RenamePolicy::MostCommon), "notes_long");
+    }
+
+    #[test]
+    fn test_propose_renames_keeps_directory() {
+        let result = create_test_result(vec!["docs/report_v1.pdf", "docs/report_v2.pdf"]);
+        let proposals = propose_renames(&result, &RenamePolicy::First);
+
+        let expected_first = Path::new("docs").join("report_v1_1.pdf").to_string_lossy().to_string();
+        assert_eq!(proposals[0].new_name, expected_first);
+    }
+
+    #[test]
+    fn test_format_rename_preview_text() {
+        let result = create_test_result(vec!["a_v1.txt", "a_v2.txt"]);
+        let proposals = propose_renames(&result, &RenamePolicy::Shortest);
+
+        let mut output = Vec::new();
+        format_rename_preview(&proposals, &OutputFormat::Text, &mut output).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        assert!(text.contains("Group 1:"));
+        assert!(text.contains("a_v1.txt -> a_v1_1.txt"));
+        assert!(text.contains("a_v2.txt -> a_v1_2.txt"));
+    }
+}
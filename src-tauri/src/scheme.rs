@@ -0,0 +1,192 @@
+use crate::grouper::{Group, GroupingResult, MatchReason, Summary};
+use anyhow::{Context, Result};
+use regex::{Regex, RegexBuilder};
+use std::path::Path;
+
+/// A camera or phone file naming template, e.g. `IMG_1234.JPG`.
+#[derive(Debug, Clone)]
+pub struct NamingScheme {
+    pub name: String,
+    pub pattern: Regex,
+}
+
+/// Built-in templates as `(name, regex)` pairs, matched case-insensitively
+/// against the file's base name. The first matching template wins.
+pub const DEFAULT_SCHEMES: &[(&str, &str)] = &[
+    ("IMG", r"^IMG[_-]\d+"),
+    ("PXL", r"^PXL_\d{8}_\d+"),
+    ("DSC", r"^DSC[FN]?_?\d+"),
+    ("VID", r"^VID[_-]\d+"),
+    ("MVI", r"^MVI_\d+"),
+    ("GOPRO", r"^G[OX]PR?\d+"),
+    ("Screenshot", r"^Screen ?shot[ _-]"),
+];
+
+impl NamingScheme {
+    pub fn new(name: &str, pattern: &str) -> Result<Self> {
+        let pattern = RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .with_context(|| format!("Invalid naming scheme pattern for {}: {}", name, pattern))?;
+        Ok(Self {
+            name: name.to_string(),
+            pattern,
+        })
+    }
+
+    /// Parses a user-supplied `NAME=REGEX` template.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (name, pattern) = spec
+            .split_once('=')
+            .with_context(|| format!("Naming scheme must be NAME=REGEX, got: {}", spec))?;
+        if name.trim().is_empty() {
+            anyhow::bail!("Naming scheme name cannot be empty: {}", spec);
+        }
+        Self::new(name.trim(), pattern)
+    }
+
+    pub fn matches(&self, file: &str) -> bool {
+        let base_name = Path::new(file)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(file);
+        self.pattern.is_match(base_name)
+    }
+}
+
+/// Returns the built-in templates preceded by `custom` ones, so user
+/// templates take priority.
+pub fn naming_schemes(custom: &[String]) -> Result<Vec<NamingScheme>> {
+    let mut schemes = custom
+        .iter()
+        .map(|spec| NamingScheme::parse(spec))
+        .collect::<Result<Vec<_>>>()?;
+    for (name, pattern) in DEFAULT_SCHEMES {
+        schemes.push(NamingScheme::new(name, pattern)?);
+    }
+    Ok(schemes)
+}
+
+/// Groups files by the naming scheme they follow, ignoring the numeric id.
+/// Files that match no scheme are reported as ungrouped.
+pub fn group_by_naming_scheme(
+    files: Vec<String>,
+    schemes: &[NamingScheme],
+    min_group_size: usize,
+) -> GroupingResult {
+    let mut buckets: Vec<Vec<String>> = vec![Vec::new(); schemes.len()];
+    let mut ungrouped = Vec::new();
+
+    for file in &files {
+        match schemes.iter().position(|scheme| scheme.matches(file)) {
+            Some(idx) => buckets[idx].push(file.clone()),
+            None => ungrouped.push(file.clone()),
+        }
+    }
+
+    let mut groups = Vec::new();
+    for (scheme, members) in schemes.iter().zip(buckets) {
+        if members.is_empty() {
+            continue;
+        }
+        if members.len() < min_group_size {
+            ungrouped.extend(members);
+            continue;
+        }
+        groups.push(Group {
+            id: groups.len() + 1,
+            files: members,
+            similarity: 1.0,
+            min_similarity: 1.0,
+            max_similarity: 1.0,
+            case_only: false,
+            match_reason: MatchReason::NamingScheme,
+            label: Some(scheme.name.clone()),
+        });
+    }
+
+    let summary = Summary {
+        total_files: files.len(),
+        groups_found: groups.len(),
+        ungrouped_files: ungrouped.len(),
+        threshold_used: 1.0,
+    };
+
+    GroupingResult {
+        groups,
+        ungrouped,
+        summary,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_strings(files: &[&str]) -> Vec<String> {
+        files.iter().map(|f| f.to_string()).collect()
+    }
+
+    #[test]
+    fn test_group_by_naming_scheme() {
+        let files = to_strings(&[
+            "IMG_1234.JPG",
+            "PXL_20240101_120000.jpg",
+            "DSC00123.JPG",
+            "img_5678.jpg",
+            "PXL_20240315_083012123.jpg",
+            "DSC00456.JPG",
+            "holiday.png",
+        ]);
+        let schemes = naming_schemes(&[]).unwrap();
+
+        let result = group_by_naming_scheme(files, &schemes, 2);
+
+        assert_eq!(result.groups.len(), 3);
+        assert_eq!(result.groups[0].label.as_deref(), Some("IMG"));
+        assert_eq!(result.groups[0].files, to_strings(&["IMG_1234.JPG", "img_5678.jpg"]));
+        assert_eq!(result.groups[1].label.as_deref(), Some("PXL"));
+        assert_eq!(result.groups[1].files.len(), 2);
+        assert_eq!(result.groups[2].label.as_deref(), Some("DSC"));
+        assert_eq!(result.groups[2].files, to_strings(&["DSC00123.JPG", "DSC00456.JPG"]));
+        assert_eq!(result.ungrouped, to_strings(&["holiday.png"]));
+        assert_eq!(result.summary.groups_found, 3);
+    }
+
+    #[test]
+    fn test_custom_scheme_takes_priority() {
+        let files = to_strings(&["IMG_0001.jpg", "IMG_0002.jpg", "scan_001.pdf", "scan_002.pdf"]);
+        let schemes = naming_schemes(&["Scanner=^scan_\\d+".to_string()]).unwrap();
+
+        let result = group_by_naming_scheme(files, &schemes, 2);
+
+        assert_eq!(result.groups.len(), 2);
+        assert_eq!(result.groups[0].label.as_deref(), Some("Scanner"));
+        assert_eq!(result.groups[1].label.as_deref(), Some("IMG"));
+    }
+
+    #[test]
+    fn test_scheme_matches_base_name_only() {
+        let scheme = NamingScheme::new("IMG", r"^IMG_\d+").unwrap();
+        assert!(scheme.matches("photos/2024/IMG_0001.jpg"));
+        assert!(!scheme.matches("IMG_folder/holiday.jpg"));
+    }
+
+    #[test]
+    fn test_parse_invalid_scheme() {
+        assert!(NamingScheme::parse("no-equals-sign").is_err());
+        assert!(NamingScheme::parse("=^IMG").is_err());
+        assert!(NamingScheme::parse("Bad=(").is_err());
+    }
+
+    #[test]
+    fn test_small_scheme_groups_are_ungrouped() {
+        let files = to_strings(&["IMG_0001.jpg", "IMG_0002.jpg", "VID_0001.mp4"]);
+        let schemes = naming_schemes(&[]).unwrap();
+
+        let result = group_by_naming_scheme(files, &schemes, 2);
+
+        assert_eq!(result.groups.len(), 1);
+        assert_eq!(result.ungrouped, to_strings(&["VID_0001.mp4"]));
+    }
+}
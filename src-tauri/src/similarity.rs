@@ -1,24 +1,388 @@
-use crate::cli::Algorithm;
-use std::collections::HashSet;
+use crate::cli::{Algorithm, CompareMode, Require};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use unicode_normalization::UnicodeNormalization;
 
-pub fn calculate_similarity(s1: &str, s2: &str, algorithm: &Algorithm, case_sensitive: bool) -> f64 {
+/// Duplicate markers removed by `--strip-copy-suffixes`, each anchored to
+/// the end of the stem and matched case-insensitively: a parenthesized
+/// number (`report (1)`), ` - Copy`, ` copy` with an optional number
+/// (`report copy 2`) and `_1` to `_9`. Markers are removed repeatedly, so
+/// `report - Copy (2)` becomes `report`.
+pub const COPY_SUFFIX_PATTERNS: [&str; 4] = [r"\s*\(\d+\)", r"\s+-\s+copy", r"\s+copy(?:\s+\d+)?", r"_[1-9]"];
+
+/// Noise such as export timestamps or user ids that is cut from the start
+/// or end of a file stem before names are compared. Together with
+/// [`Affixes::compare`] this extracts the comparison key of a file,
+/// while groups keep reporting the file as given.
+#[derive(Debug, Clone, Default)]
+pub struct Affixes {
+    prefix: Option<Regex>,
+    suffix: Option<Regex>,
+    copy_suffixes: Option<Regex>,
+    normalize_unicode: bool,
+    fold_case: bool,
+    compare: CompareMode,
+}
+
+impl Affixes {
+    /// Builds the patterns for `--strip-prefix` and `--strip-suffix`; the
+    /// prefix is anchored to the start of the stem and the suffix to its end.
+    pub fn new(prefix: Option<&str>, suffix: Option<&str>) -> Result<Self> {
+        let prefix = prefix
+            .map(|re| Regex::new(&format!("^(?:{})", re)).with_context(|| format!("Invalid --strip-prefix pattern: {}", re)))
+            .transpose()?;
+        let suffix = suffix
+            .map(|re| Regex::new(&format!("(?:{})$", re)).with_context(|| format!("Invalid --strip-suffix pattern: {}", re)))
+            .transpose()?;
+        Ok(Self {
+            prefix,
+            suffix,
+            copy_suffixes: None,
+            normalize_unicode: false,
+            fold_case: false,
+            compare: CompareMode::Fullpath,
+        })
+    }
+
+    /// Brings names into Unicode NFKC form before anything else, so
+    /// precomposed and decomposed accents (`café` written either way) and
+    /// compatibility forms such as full-width letters compare equal. Unless
+    /// `case_sensitive`, names are also case-folded with Unicode's default,
+    /// locale-independent rules, which fold `ß` to `ss` but leave the Turkish
+    /// dotless `ı` distinct from `i`.
+    pub fn normalize_unicode(mut self, enabled: bool, case_sensitive: bool) -> Self {
+        self.normalize_unicode = enabled;
+        self.fold_case = enabled && !case_sensitive;
+        self
+    }
+
+    /// Also strips the [`COPY_SUFFIX_PATTERNS`] that downloads and file
+    /// managers append to duplicates, so `report.pdf`, `report (1).pdf` and
+    /// `report copy.pdf` compare as the same name.
+    pub fn strip_copy_suffixes(mut self, enabled: bool) -> Self {
+        self.copy_suffixes = enabled.then(|| {
+            Regex::new(&format!("(?i)(?:{})$", COPY_SUFFIX_PATTERNS.join("|"))).expect("copy suffix patterns are valid")
+        });
+        self
+    }
+
+    /// Chooses which part of each path is compared; with
+    /// [`CompareMode::Basename`], `2023/report.pdf` and `2024/report.pdf` are
+    /// treated as the same name.
+    pub fn compare(mut self, mode: CompareMode) -> Self {
+        self.compare = mode;
+        self
+    }
+
+    /// Stable description of every setting that changes the compared names,
+    /// so checkpoints know which names their scores were taken from.
+    pub(crate) fn fingerprint(&self) -> String {
+        format!(
+            "prefix={:?}|suffix={:?}|copy_suffixes={}|normalize_unicode={}|fold_case={}|compare={:?}",
+            self.prefix.as_ref().map(Regex::as_str),
+            self.suffix.as_ref().map(Regex::as_str),
+            self.copy_suffixes.is_some(),
+            self.normalize_unicode,
+            self.fold_case,
+            self.compare,
+        )
+    }
+
+    /// Removes the configured prefix and suffix from the stem of `name`,
+    /// keeping its extension and directory unless the compare mode drops
+    /// them. Names whose stem would become empty keep their stem.
+    pub fn strip<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        if !self.normalize_unicode {
+            return self.strip_normalized(name);
+        }
+        let mut normalized: String = name.nfkc().collect();
+        if self.fold_case {
+            normalized = caseless::default_case_fold_str(&normalized).nfkc().collect();
+        }
+        Cow::Owned(self.strip_normalized(&normalized).into_owned())
+    }
+
+    fn strip_normalized<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        let base_start = name.rfind(['/', '\\']).map_or(0, |pos| pos + 1);
+        let (name, base_start) = match self.compare {
+            CompareMode::Fullpath => (name, base_start),
+            CompareMode::Basename => (&name[base_start..], 0),
+            CompareMode::Stem => {
+                let base = &name[base_start..];
+                match base.rfind('.') {
+                    Some(dot_pos) if dot_pos > 0 => (&base[..dot_pos], 0),
+                    _ => (base, 0),
+                }
+            }
+        };
+        if self.prefix.is_none() && self.suffix.is_none() && self.copy_suffixes.is_none() {
+            return Cow::Borrowed(name);
+        }
+
+        let (dir, base) = name.split_at(base_start);
+        let (stem, ext) = match base.rfind('.') {
+            Some(dot_pos) if dot_pos > 0 => base.split_at(dot_pos),
+            _ => (base, ""),
+        };
+
+        let mut stripped = stem;
+        // Copy markers come last in a stem, so they go before a --strip-suffix
+        // pattern gets to match
+        if let Some(re) = &self.copy_suffixes {
+            while let Some(m) = re.find(stripped).filter(|m| m.start() > 0) {
+                stripped = &stripped[..m.start()];
+            }
+        }
+        if let Some(m) = self.prefix.as_ref().and_then(|re| re.find(stripped)) {
+            stripped = &stripped[m.end()..];
+        }
+        if let Some(m) = self.suffix.as_ref().and_then(|re| re.find(stripped)) {
+            stripped = &stripped[..m.start()];
+        }
+
+        if stripped.is_empty() || stripped.len() == stem.len() {
+            Cow::Borrowed(name)
+        } else {
+            Cow::Owned(format!("{}{}{}", dir, stripped, ext))
+        }
+    }
+}
+
+/// A similarity function for file names. The built-in [`Algorithm`]s
+/// implement it, and so does any `Fn(&str, &str) -> f64`, so library users
+/// can group files with a scorer of their own.
+pub trait Similarity {
+    /// Scores how similar `a` and `b` are, from `0.0` (unrelated) to `1.0`
+    /// (identical). Names arrive already lowercased unless the comparison is
+    /// case-sensitive. Grouping assumes identical names score `1.0`.
+    fn score(&self, a: &str, b: &str) -> f64;
+}
+
+/// [`Algorithm::Weighted`] scored this way uses the default weights; pass a
+/// [`ComponentWeights`] instead to weight its components differently.
+impl Similarity for Algorithm {
+    fn score(&self, a: &str, b: &str) -> f64 {
+        score_with(a, b, self, &AutoWeights::default())
+    }
+}
+
+/// Scores a pair as [`Algorithm::Weighted`] does, with these weights.
+impl Similarity for ComponentWeights {
+    fn score(&self, a: &str, b: &str) -> f64 {
+        self.combine(|component| component.score(a, b))
+    }
+}
+
+impl<F: Fn(&str, &str) -> f64> Similarity for F {
+    fn score(&self, a: &str, b: &str) -> f64 {
+        self(a, b)
+    }
+}
+
+/// Scores how similar two strings are with `scorer`, from `0.0` (unrelated)
+/// to `1.0` (identical).
+///
+/// Unless `case_sensitive` is set, both strings are lowercased before comparison.
+pub fn calculate_similarity<S: Similarity + ?Sized>(s1: &str, s2: &str, scorer: &S, case_sensitive: bool) -> f64 {
+    if case_sensitive {
+        scorer.score(s1, s2)
+    } else {
+        scorer.score(&s1.to_lowercase(), &s2.to_lowercase())
+    }
+}
+
+/// Like [`calculate_similarity`], but [`Algorithm::Auto`] blends its
+/// algorithms with `weights` instead of the defaults.
+pub fn calculate_similarity_with(
+    s1: &str,
+    s2: &str,
+    algorithm: &Algorithm,
+    case_sensitive: bool,
+    weights: &AutoWeights,
+) -> f64 {
     let (s1, s2) = if case_sensitive {
         (s1.to_string(), s2.to_string())
     } else {
         (s1.to_lowercase(), s2.to_lowercase())
     };
+    score_with(&s1, &s2, algorithm, weights)
+}
+
+fn score_with(s1: &str, s2: &str, algorithm: &Algorithm, weights: &AutoWeights) -> f64 {
+    let score = match algorithm {
+        Algorithm::Levenshtein => levenshtein_similarity(s1, s2),
+        Algorithm::Jaro => jaro_similarity(s1, s2),
+        Algorithm::Hamming => hamming_similarity(s1, s2),
+        Algorithm::Token => token_similarity(s1, s2),
+        Algorithm::TokenSort => token_sort_similarity(s1, s2),
+        Algorithm::Substring => substring_similarity(s1, s2),
+        Algorithm::Dice => dice_similarity(s1, s2),
+        Algorithm::Phonetic => phonetic_similarity(s1, s2),
+        Algorithm::Lcs => lcs_similarity(s1, s2),
+        Algorithm::Partial => partial_similarity(s1, s2),
+        Algorithm::Auto => auto_similarity(s1, s2, weights),
+        Algorithm::Weighted => ComponentWeights::default()
+            .combine(|component| score_with(s1, s2, component, weights)),
+    };
+    tracing::trace!(s1 = %s1, s2 = %s2, ?algorithm, score, "scored pair");
+    score
+}
+
+/// Combines the scores of several algorithms into one.
+///
+/// With [`Require::All`] the lowest score is kept, so a pair only clears a
+/// threshold when every algorithm agrees; with [`Require::Any`] the highest
+/// score is kept. [`Algorithm::Weighted`] blends its components by `weights`.
+/// With `calibrate`, each algorithm's score goes through [`calibrate_score`]
+/// first, components of a weighted blend included.
+pub fn calculate_combined_similarity(
+    s1: &str,
+    s2: &str,
+    algorithms: &[Algorithm],
+    require: &Require,
+    weights: &ComponentWeights,
+    case_sensitive: bool,
+    calibrate: bool,
+) -> f64 {
+    if algorithms.is_empty() {
+        return 0.0;
+    }
+    
+    let score = |algorithm: &Algorithm| {
+        let score = calculate_similarity(s1, s2, algorithm, case_sensitive);
+        if calibrate { calibrate_score(algorithm, score) } else { score }
+    };
+    let scores = algorithms.iter().map(|algorithm| match algorithm {
+        Algorithm::Weighted => weights.combine(score),
+        _ => score(algorithm),
+    });
+    
+    match require {
+        Require::All => scores.fold(f64::INFINITY, f64::min),
+        Require::Any => scores.fold(0.0, f64::max),
+    }
+}
+
+/// Pairwise scores shared by several grouping runs, such as the jobs of one
+/// batch invocation, so a pair of names that appears in more than one job is
+/// only scored once per algorithm. Safe to share between threads.
+#[derive(Debug, Default)]
+pub struct SimilarityCache {
+    scores: Mutex<HashMap<(Algorithm, bool, String, String), f64>>,
+    computed: AtomicUsize,
+    hits: AtomicUsize,
+}
 
+impl SimilarityCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cached [`calculate_similarity`]. Names are keyed after case folding
+    /// and in a fixed order, since every algorithm is symmetric.
+    pub fn similarity(&self, s1: &str, s2: &str, algorithm: &Algorithm, case_sensitive: bool) -> f64 {
+        let (a, b) = if case_sensitive {
+            (s1.to_string(), s2.to_string())
+        } else {
+            (s1.to_lowercase(), s2.to_lowercase())
+        };
+        let key = if a <= b { (algorithm.clone(), case_sensitive, a, b) } else { (algorithm.clone(), case_sensitive, b, a) };
+
+        if let Some(&score) = self.scores.lock().unwrap().get(&key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return score;
+        }
+        // Scored outside the lock so parallel jobs don't serialize on it; a
+        // pair raced by two jobs is simply scored twice
+        let score = calculate_similarity(&key.2, &key.3, algorithm, true);
+        self.computed.fetch_add(1, Ordering::Relaxed);
+        self.scores.lock().unwrap().insert(key, score);
+        score
+    }
+
+    /// Cached [`calculate_combined_similarity`]. [`Algorithm::Weighted`] is
+    /// cached per component, so runs with different weights share scores.
+    /// Scores are cached before calibration.
+    #[allow(clippy::too_many_arguments)]
+    pub fn combined_similarity(
+        &self,
+        s1: &str,
+        s2: &str,
+        algorithms: &[Algorithm],
+        require: &Require,
+        weights: &ComponentWeights,
+        case_sensitive: bool,
+        calibrate: bool,
+    ) -> f64 {
+        if algorithms.is_empty() {
+            return 0.0;
+        }
+
+        let score = |algorithm: &Algorithm| {
+            let score = self.similarity(s1, s2, algorithm, case_sensitive);
+            if calibrate { calibrate_score(algorithm, score) } else { score }
+        };
+        let scores = algorithms.iter().map(|algorithm| match algorithm {
+            Algorithm::Weighted => weights.combine(score),
+            _ => score(algorithm),
+        });
+
+        match require {
+            Require::All => scores.fold(f64::INFINITY, f64::min),
+            Require::Any => scores.fold(0.0, f64::max),
+        }
+    }
+
+    /// Number of scores actually computed.
+    pub fn computed(&self) -> usize {
+        self.computed.load(Ordering::Relaxed)
+    }
+
+    /// Number of scores served from the cache.
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+}
+
+/// Typical score of two unrelated file names under `algorithm`: the mean
+/// over every pair of a set of everyday names such as `invoice_march.pdf`,
+/// `holiday_beach.jpg` and `budget_2023.xlsx`, rounded to 0.05. Jaro-Winkler
+/// rarely drops below 0.5 for such pairs, while token and substring scores
+/// are already near 0. [`Algorithm::Weighted`] calibrates its components
+/// instead, so it has no floor of its own.
+pub fn noise_floor(algorithm: &Algorithm) -> f64 {
     match algorithm {
-        Algorithm::Levenshtein => levenshtein_similarity(&s1, &s2),
-        Algorithm::Jaro => jaro_similarity(&s1, &s2),
-        Algorithm::Token => token_similarity(&s1, &s2),
-        Algorithm::Substring => substring_similarity(&s1, &s2),
-        Algorithm::Auto => auto_similarity(&s1, &s2),
+        Algorithm::Levenshtein => 0.2,
+        Algorithm::Jaro => 0.5,
+        Algorithm::Hamming => 0.2,
+        Algorithm::Token => 0.0,
+        Algorithm::TokenSort => 0.15,
+        Algorithm::Substring => 0.0,
+        Algorithm::Dice => 0.05,
+        Algorithm::Phonetic => 0.0,
+        Algorithm::Lcs => 0.25,
+        Algorithm::Partial => 0.15,
+        Algorithm::Auto => 0.2,
+        Algorithm::Weighted => 0.0,
     }
 }
 
+/// Rescales a raw `score` of `algorithm` so that its [`noise_floor`] maps to
+/// `0.0` while `1.0` stays `1.0`, making a threshold mean roughly the same
+/// confidence whichever algorithm is used (`--calibrate`).
+pub fn calibrate_score(algorithm: &Algorithm, score: f64) -> f64 {
+    let floor = noise_floor(algorithm);
+    ((score - floor) / (1.0 - floor)).clamp(0.0, 1.0)
+}
+
 fn levenshtein_similarity(s1: &str, s2: &str) -> f64 {
-    let distance = strsim::levenshtein(s1, s2);
+    let distance = levenshtein_distance(s1, s2);
     let max_len = s1.len().max(s2.len());
     if max_len == 0 {
         1.0
@@ -27,6 +391,41 @@ fn levenshtein_similarity(s1: &str, s2: &str) -> f64 {
     }
 }
 
+/// Edit distance in characters. With the `fast-levenshtein` feature, ASCII
+/// names go through triple_accel's SIMD implementation, where bytes are
+/// characters and the distance is the same; other names use strsim.
+#[cfg(feature = "fast-levenshtein")]
+fn levenshtein_distance(s1: &str, s2: &str) -> usize {
+    if s1.is_ascii() && s2.is_ascii() {
+        triple_accel::levenshtein(s1.as_bytes(), s2.as_bytes()) as usize
+    } else {
+        strsim::levenshtein(s1, s2)
+    }
+}
+
+#[cfg(not(feature = "fast-levenshtein"))]
+fn levenshtein_distance(s1: &str, s2: &str) -> usize {
+    strsim::levenshtein(s1, s2)
+}
+
+/// Share of positions holding the same character, for names of equal length
+/// such as `log_20240101.txt` and `log_20240102.txt`. Names of different
+/// lengths have no positionwise alignment, so they are scored with
+/// Levenshtein instead, which counts the missing characters as edits.
+fn hamming_similarity(s1: &str, s2: &str) -> f64 {
+    let chars1: Vec<char> = s1.chars().collect();
+    let chars2: Vec<char> = s2.chars().collect();
+    if chars1.len() != chars2.len() {
+        return levenshtein_similarity(s1, s2);
+    }
+    if chars1.is_empty() {
+        return 1.0;
+    }
+    
+    let mismatches = chars1.iter().zip(&chars2).filter(|(a, b)| a != b).count();
+    1.0 - mismatches as f64 / chars1.len() as f64
+}
+
 fn jaro_similarity(s1: &str, s2: &str) -> f64 {
     strsim::jaro_winkler(s1, s2)
 }
@@ -55,18 +454,30 @@ fn token_similarity(s1: &str, s2: &str) -> f64 {
     }
 }
 
-fn tokenize(s: &str) -> Vec<String> {
+/// Levenshtein similarity of the two names with their tokens sorted
+/// alphabetically and rejoined, like fuzzywuzzy's `token_sort_ratio`, so
+/// `final_report_2024` and `2024_report_final` compare as equal.
+fn token_sort_similarity(s1: &str, s2: &str) -> f64 {
+    let sorted = |s: &str| {
+        let mut tokens = tokenize(s);
+        tokens.sort_unstable();
+        tokens.join(" ")
+    };
+    levenshtein_similarity(&sorted(s1), &sorted(s2))
+}
+
+/// Splits `s` into its runs of alphanumeric characters, as compared by the
+/// token algorithm; everything else (`_`, `-`, spaces, dots) separates tokens.
+pub fn tokenize(s: &str) -> Vec<String> {
     let mut tokens = Vec::new();
     let mut current_token = String::new();
     
     for ch in s.chars() {
         if ch.is_alphanumeric() {
             current_token.push(ch);
-        } else {
-            if !current_token.is_empty() {
-                tokens.push(current_token.clone());
-                current_token.clear();
-            }
+        } else if !current_token.is_empty() {
+            tokens.push(current_token.clone());
+            current_token.clear();
         }
     }
     
@@ -118,22 +529,437 @@ fn substring_similarity(s1: &str, s2: &str) -> f64 {
     }
 }
 
-fn auto_similarity(s1: &str, s2: &str) -> f64 {
-    // Use a combination of algorithms and take the maximum
-    let levenshtein = levenshtein_similarity(s1, s2);
-    let jaro = jaro_similarity(s1, s2);
-    let token = token_similarity(s1, s2);
+/// Longest-common-subsequence ratio `2 * LCS / (len1 + len2)` over the
+/// normalized names, so text inserted or removed anywhere in a name only
+/// costs its own length.
+fn lcs_similarity(s1: &str, s2: &str) -> f64 {
+    let chars1: Vec<char> = normalize_for_comparison(s1).chars().collect();
+    let chars2: Vec<char> = normalize_for_comparison(s2).chars().collect();
+    
+    let total = chars1.len() + chars2.len();
+    if total == 0 {
+        return 1.0;
+    }
+    
+    // Two rows of the classic dynamic programming table
+    let mut previous = vec![0usize; chars2.len() + 1];
+    let mut current = vec![0usize; chars2.len() + 1];
+    for &c1 in &chars1 {
+        for (j, &c2) in chars2.iter().enumerate() {
+            current[j + 1] = if c1 == c2 {
+                previous[j] + 1
+            } else {
+                current[j].max(previous[j + 1])
+            };
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    
+    2.0 * previous[chars2.len()] as f64 / total as f64
+}
+
+/// Most windows [`partial_similarity`] scores; longer names are sampled at
+/// evenly spaced offsets so a pair costs at most this many Levenshtein runs.
+const MAX_PARTIAL_WINDOWS: usize = 64;
+
+/// Best Levenshtein similarity between the shorter normalized name and any
+/// window of the same length in the longer one, like fuzzywuzzy's
+/// `partial_ratio`, so `annual_report` matches `2024_annual_report` fully.
+fn partial_similarity(s1: &str, s2: &str) -> f64 {
+    let chars1: Vec<char> = normalize_for_comparison(s1).chars().collect();
+    let chars2: Vec<char> = normalize_for_comparison(s2).chars().collect();
+    let (shorter, longer) = if chars1.len() <= chars2.len() {
+        (&chars1, &chars2)
+    } else {
+        (&chars2, &chars1)
+    };
+    
+    if longer.is_empty() {
+        return 1.0;
+    }
+    if shorter.is_empty() {
+        return 0.0;
+    }
+    
+    let windows = longer.len() - shorter.len() + 1;
+    let offsets: Vec<usize> = if windows <= MAX_PARTIAL_WINDOWS {
+        (0..windows).collect()
+    } else {
+        (0..MAX_PARTIAL_WINDOWS).map(|k| k * (windows - 1) / (MAX_PARTIAL_WINDOWS - 1)).collect()
+    };
+    
+    let mut best = 0.0_f64;
+    for offset in offsets {
+        let window = &longer[offset..offset + shorter.len()];
+        let distance = strsim::generic_levenshtein(shorter, &window.to_vec());
+        best = best.max(1.0 - distance as f64 / shorter.len() as f64);
+        if best >= 1.0 {
+            break;
+        }
+    }
+    best
+}
+
+/// Sørensen–Dice coefficient over the multisets of character bigrams.
+fn dice_similarity(s1: &str, s2: &str) -> f64 {
+    let chars1: Vec<char> = s1.chars().collect();
+    let chars2: Vec<char> = s2.chars().collect();
+    
+    // Strings shorter than two characters have no bigrams
+    if chars1.len() < 2 || chars2.len() < 2 {
+        return if s1 == s2 { 1.0 } else { 0.0 };
+    }
+    
+    let mut bigrams: HashMap<(char, char), usize> = HashMap::new();
+    for pair in chars1.windows(2) {
+        *bigrams.entry((pair[0], pair[1])).or_insert(0) += 1;
+    }
+    
+    let mut intersection = 0;
+    for pair in chars2.windows(2) {
+        if let Some(count) = bigrams.get_mut(&(pair[0], pair[1])) {
+            if *count > 0 {
+                *count -= 1;
+                intersection += 1;
+            }
+        }
+    }
+    
+    let total = (chars1.len() - 1) + (chars2.len() - 1);
+    2.0 * intersection as f64 / total as f64
+}
+
+/// Jaccard similarity over the Metaphone codes of each name's tokens, so
+/// spellings that sound alike (`Jonson`/`Johnson`) compare as equal.
+/// Tokens that are not purely alphabetic are compared verbatim.
+fn phonetic_similarity(s1: &str, s2: &str) -> f64 {
+    let codes = |s: &str| -> HashSet<String> {
+        let without_ext = s.rfind('.').map_or(s, |dot_pos| &s[..dot_pos]);
+        tokenize(without_ext)
+            .into_iter()
+            .map(|token| {
+                if token.chars().all(|c| c.is_ascii_alphabetic()) {
+                    metaphone(&token)
+                } else {
+                    token
+                }
+            })
+            .collect()
+    };
+    
+    let set1 = codes(s1);
+    let set2 = codes(s2);
+    if set1.is_empty() && set2.is_empty() {
+        return 1.0;
+    }
+    
+    let intersection = set1.intersection(&set2).count();
+    let union = set1.union(&set2).count();
+    intersection as f64 / union as f64
+}
+
+/// Encodes an ASCII word with the original Metaphone rules.
+fn metaphone(word: &str) -> String {
+    let mut chars: Vec<char> = word.to_ascii_uppercase().chars().collect();
+    // Adjacent duplicates collapse, except C (as in "ACCEPT")
+    chars.dedup_by(|b, a| a == b && *a != 'C');
+    
+    // Initial letter exceptions
+    match chars.as_slice() {
+        ['A', 'E', ..] | ['G', 'N', ..] | ['K', 'N', ..] | ['P', 'N', ..] | ['W', 'R', ..] => {
+            chars.remove(0);
+        }
+        ['X', ..] => chars[0] = 'S',
+        ['W', 'H', ..] => {
+            chars.remove(1);
+        }
+        _ => {}
+    }
+    
+    let is_vowel = |c: Option<&char>| matches!(c, Some('A' | 'E' | 'I' | 'O' | 'U'));
+    let mut code = String::new();
+    
+    for i in 0..chars.len() {
+        let c = chars[i];
+        let prev = if i > 0 { chars.get(i - 1) } else { None };
+        let next = chars.get(i + 1);
+        let after_next = chars.get(i + 2);
+        let next_is_front_vowel = matches!(next, Some('E' | 'I' | 'Y'));
+        
+        match c {
+            'A' | 'E' | 'I' | 'O' | 'U' => {
+                if i == 0 {
+                    code.push(c);
+                }
+            }
+            'B' => {
+                // Silent in a trailing "MB" as in "DUMB"
+                if !(prev == Some(&'M') && next.is_none()) {
+                    code.push('B');
+                }
+            }
+            'C' => {
+                if next == Some(&'I') && after_next == Some(&'A') {
+                    code.push('X');
+                } else if next == Some(&'H') {
+                    code.push(if prev == Some(&'S') { 'K' } else { 'X' });
+                } else if next_is_front_vowel {
+                    if prev != Some(&'S') {
+                        code.push('S');
+                    }
+                } else {
+                    code.push('K');
+                }
+            }
+            'D' => {
+                if next == Some(&'G') && matches!(after_next, Some('E' | 'I' | 'Y')) {
+                    code.push('J');
+                } else {
+                    code.push('T');
+                }
+            }
+            'G' => {
+                let silent_gh = next == Some(&'H') && after_next.is_some() && !is_vowel(after_next);
+                let silent_gn = next == Some(&'N')
+                    && (after_next.is_none() || (after_next == Some(&'E') && chars.get(i + 3) == Some(&'D')));
+                if silent_gh || silent_gn || (prev == Some(&'D') && next_is_front_vowel) {
+                    continue;
+                }
+                code.push(if next_is_front_vowel { 'J' } else { 'K' });
+            }
+            'H' => {
+                let after_modifier = matches!(prev, Some('C' | 'S' | 'P' | 'T' | 'G'));
+                // Silent after a vowel unless another vowel follows
+                if !after_modifier && (!is_vowel(prev) || is_vowel(next)) {
+                    code.push('H');
+                }
+            }
+            'K' => {
+                if prev != Some(&'C') {
+                    code.push('K');
+                }
+            }
+            'P' => code.push(if next == Some(&'H') { 'F' } else { 'P' }),
+            'Q' => code.push('K'),
+            'S' => {
+                if next == Some(&'H') || (next == Some(&'I') && matches!(after_next, Some('O' | 'A'))) {
+                    code.push('X');
+                } else {
+                    code.push('S');
+                }
+            }
+            'T' => {
+                if next == Some(&'I') && matches!(after_next, Some('O' | 'A')) {
+                    code.push('X');
+                } else if next == Some(&'H') {
+                    code.push('0');
+                } else if !(next == Some(&'C') && after_next == Some(&'H')) {
+                    code.push('T');
+                }
+            }
+            'V' => code.push('F'),
+            'W' | 'Y' => {
+                if is_vowel(next) {
+                    code.push(c);
+                }
+            }
+            'X' => code.push_str("KS"),
+            'Z' => code.push('S'),
+            _ => code.push(c),
+        }
+    }
     
-    // Weight the algorithms based on string characteristics
+    code
+}
+
+/// Average name length (in characters) at which [`Algorithm::Auto`] sits
+/// halfway between its character- and token-oriented weightings.
+pub const DEFAULT_AUTO_CROSSOVER_LENGTH: f64 = 16.0;
+
+/// Share of each algorithm in one [`Algorithm::Auto`] weighting.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlgorithmWeights {
+    pub token: f64,
+    pub jaro: f64,
+    pub levenshtein: f64,
+}
+
+/// How [`Algorithm::Auto`] blends token, Jaro-Winkler and Levenshtein scores.
+///
+/// `plain` suits short names without delimiters and `delimited` long,
+/// structured ones. Each pair is weighted somewhere between the two: names
+/// containing `_`, `-` or a space start halfway toward `delimited`, and
+/// longer names move further toward it, reaching the midpoint of their range
+/// when their average length equals `crossover_length`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutoWeights {
+    pub plain: AlgorithmWeights,
+    pub delimited: AlgorithmWeights,
+    pub crossover_length: f64,
+}
+
+impl Default for AutoWeights {
+    fn default() -> Self {
+        Self {
+            plain: AlgorithmWeights { token: 0.2, jaro: 0.5, levenshtein: 0.3 },
+            delimited: AlgorithmWeights { token: 0.6, jaro: 0.3, levenshtein: 0.1 },
+            crossover_length: DEFAULT_AUTO_CROSSOVER_LENGTH,
+        }
+    }
+}
+
+/// [`Algorithm::Auto`] comparing `s1` and `s2` as given, without case folding.
+pub fn auto_similarity(s1: &str, s2: &str, weights: &AutoWeights) -> f64 {
+    auto_components(s1, s2, weights).iter().map(|(_, contribution)| contribution).sum()
+}
+
+/// Weights [`Algorithm::Auto`] gives to token, Jaro-Winkler and Levenshtein
+/// scores for a pair; see [`AutoWeights`].
+pub fn auto_weights(s1: &str, s2: &str, weights: &AutoWeights) -> [(Algorithm, f64); 3] {
     let has_delimiters = s1.contains('_') || s1.contains('-') || s1.contains(' ') ||
                         s2.contains('_') || s2.contains('-') || s2.contains(' ');
+    let length = (s1.chars().count() + s2.chars().count()) as f64 / 2.0;
+    let length_factor = if weights.crossover_length > 0.0 { length / (length + weights.crossover_length) } else { 1.0 };
     
-    if has_delimiters {
-        // Prefer token-based for structured names
-        token * 0.6 + jaro * 0.3 + levenshtein * 0.1
+    // 0.0 is fully `plain`, 1.0 fully `delimited`
+    let bias = if has_delimiters { 0.5 + 0.5 * length_factor } else { 0.5 * length_factor };
+    let blend = |plain: f64, delimited: f64| plain + (delimited - plain) * bias;
+    [
+        (Algorithm::Token, blend(weights.plain.token, weights.delimited.token)),
+        (Algorithm::Jaro, blend(weights.plain.jaro, weights.delimited.jaro)),
+        (Algorithm::Levenshtein, blend(weights.plain.levenshtein, weights.delimited.levenshtein)),
+    ]
+}
+
+/// The weighted contribution of each algorithm blended by [`Algorithm::Auto`].
+fn auto_components(s1: &str, s2: &str, weights: &AutoWeights) -> [(Algorithm, f64); 3] {
+    auto_weights(s1, s2, weights).map(|(algorithm, weight)| {
+        let score = auto_component_score(&algorithm, s1, s2);
+        (algorithm, score * weight)
+    })
+}
+
+/// Unweighted score of one of the algorithms [`Algorithm::Auto`] blends.
+fn auto_component_score(algorithm: &Algorithm, s1: &str, s2: &str) -> f64 {
+    match algorithm {
+        Algorithm::Token => token_similarity(s1, s2),
+        Algorithm::Jaro => jaro_similarity(s1, s2),
+        _ => levenshtein_similarity(s1, s2),
+    }
+}
+
+/// The algorithm that drives the [`Algorithm::Auto`] score of a pair: the
+/// one scoring highest for the weight it carries in the blend, i.e. its
+/// score divided by its weight. Comparing raw contributions would nearly
+/// always name Jaro-Winkler, which has the largest weight and scores close
+/// to 1.0 for most related names.
+pub fn dominant_auto_algorithm(s1: &str, s2: &str, case_sensitive: bool) -> Algorithm {
+    let (s1, s2) = if case_sensitive {
+        (s1.to_string(), s2.to_string())
     } else {
-        // Prefer character-based for simple names
-        jaro * 0.5 + levenshtein * 0.3 + token * 0.2
+        (s1.to_lowercase(), s2.to_lowercase())
+    };
+    
+    auto_weights(&s1, &s2, &AutoWeights::default())
+        .into_iter()
+        .filter(|(_, weight)| *weight > 0.0)
+        .map(|(algorithm, weight)| {
+            let score = auto_component_score(&algorithm, &s1, &s2);
+            (algorithm, score / weight)
+        })
+        .fold(None, |best: Option<(Algorithm, f64)>, (algorithm, strength)| match best {
+            Some((_, best_strength)) if best_strength >= strength => best,
+            _ => Some((algorithm, strength)),
+        })
+        .map(|(algorithm, _)| algorithm)
+        .unwrap_or(Algorithm::Auto)
+}
+
+/// How far the weights of [`ComponentWeights`] may sum from 1.0.
+const WEIGHT_SUM_TOLERANCE: f64 = 0.01;
+
+/// The algorithms [`Algorithm::Weighted`] sums and the weight of each, as
+/// given by `--weights levenshtein=0.5,jaro=0.3,token=0.2`. Weights are
+/// scaled to sum to exactly 1.0, so the weighted score stays within 0.0-1.0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentWeights(Vec<(Algorithm, f64)>);
+
+impl ComponentWeights {
+    /// Parses comma-separated `algorithm=weight` pairs, naming algorithms as
+    /// `--algorithm` does.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut components: Vec<(Algorithm, f64)> = Vec::new();
+        for pair in spec.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+            let (name, weight) = pair
+                .split_once('=')
+                .with_context(|| format!("Invalid weight '{}': expected algorithm=weight", pair))?;
+            let name = name.trim();
+            let algorithm = Algorithm::from_str(name, true).map_err(|_| {
+                let known: Vec<String> = Algorithm::value_variants()
+                    .iter()
+                    .filter(|algorithm| **algorithm != Algorithm::Weighted)
+                    .filter_map(|algorithm| algorithm.to_possible_value())
+                    .map(|value| value.get_name().to_string())
+                    .collect();
+                anyhow::anyhow!("Unknown algorithm '{}' in weights; expected one of: {}", name, known.join(", "))
+            })?;
+            if algorithm == Algorithm::Weighted {
+                anyhow::bail!("'weighted' cannot be one of its own components");
+            }
+            if components.iter().any(|(existing, _)| *existing == algorithm) {
+                anyhow::bail!("Algorithm '{}' is weighted more than once", name);
+            }
+            let weight: f64 = weight
+                .trim()
+                .parse()
+                .with_context(|| format!("Invalid weight '{}' for {}", weight.trim(), name))?;
+            if !weight.is_finite() || weight < 0.0 {
+                anyhow::bail!("Weight for {} must be a non-negative number, got {}", name, weight);
+            }
+            components.push((algorithm, weight));
+        }
+
+        if components.is_empty() {
+            anyhow::bail!("No weights given; expected e.g. levenshtein=0.5,jaro=0.3,token=0.2");
+        }
+        let sum: f64 = components.iter().map(|(_, weight)| weight).sum();
+        // Allow for the rounding of the sum itself, so e.g. 0.51 + 0.5 passes
+        if (sum - 1.0).abs() > WEIGHT_SUM_TOLERANCE + f64::EPSILON {
+            anyhow::bail!("Weights must sum to 1.0, got {}", sum);
+        }
+        // Absorb the tolerance so identical names still score exactly 1.0
+        for (_, weight) in &mut components {
+            *weight /= sum;
+        }
+        Ok(Self(components))
+    }
+
+    pub fn components(&self) -> &[(Algorithm, f64)] {
+        &self.0
+    }
+
+    /// Sums the score of each component, as given by `score`, times its weight.
+    fn combine<F>(&self, mut score: F) -> f64
+    where
+        F: FnMut(&Algorithm) -> f64,
+    {
+        self.0.iter().map(|(algorithm, weight)| score(algorithm) * weight).sum()
+    }
+}
+
+impl Default for ComponentWeights {
+    fn default() -> Self {
+        Self(vec![(Algorithm::Levenshtein, 0.5), (Algorithm::Jaro, 0.3), (Algorithm::Token, 0.2)])
+    }
+}
+
+impl fmt::Display for ComponentWeights {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (algorithm, weight)) in self.0.iter().enumerate() {
+            let name = algorithm.to_possible_value().map(|value| value.get_name().to_string()).unwrap_or_default();
+            write!(f, "{}{}={}", if i == 0 { "" } else { "," }, name, weight)?;
+        }
+        Ok(())
     }
 }
 
@@ -148,6 +974,48 @@ mod tests {
         assert!((levenshtein_similarity("abc", "xyz") - 0.0).abs() < 0.1);
     }
 
+    #[cfg(feature = "fast-levenshtein")]
+    #[test]
+    fn test_fast_levenshtein_matches_strsim() {
+        let long = "quarterly_financial_report_".repeat(6);
+        let mut corpus = vec![
+            String::new(),
+            "a".to_string(),
+            "report_v1.pdf".to_string(),
+            "report_v2.pdf".to_string(),
+            "Report_V2.PDF".to_string(),
+            "v2_report.pdf".to_string(),
+            "2024_annual_report".to_string(),
+            "annual_report".to_string(),
+            "IMG_20240101_120000.jpg".to_string(),
+            "IMG_20240102_120001.jpg".to_string(),
+            "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa".to_string(),
+            "abababababababababababababababababababab".to_string(),
+            format!("{}draft.docx", long),
+            format!("{}final.docx", long),
+            format!("draft_{}", long),
+        ];
+        // Longer than the SIMD vector width, in steps that cross it
+        for len in [15, 16, 17, 31, 32, 33, 63, 64, 65] {
+            corpus.push(long.chars().take(len).collect());
+            corpus.push(long.chars().rev().take(len).collect());
+        }
+
+        for a in &corpus {
+            for b in &corpus {
+                let fast = levenshtein_distance(a, b);
+                assert_eq!(fast, strsim::levenshtein(a, b), "{:?} / {:?}", a, b);
+                let max_len = a.len().max(b.len());
+                let expected = if max_len == 0 { 1.0 } else { 1.0 - strsim::levenshtein(a, b) as f64 / max_len as f64 };
+                assert_eq!(levenshtein_similarity(a, b).to_bits(), expected.to_bits());
+            }
+        }
+
+        // Non-ASCII names fall back to strsim, which counts characters
+        assert_eq!(levenshtein_distance("café_v1", "cafe_v1"), 1);
+        assert_eq!(levenshtein_distance("報告書_v1", "報告書_v2"), 1);
+    }
+
     #[test]
     fn test_jaro_similarity() {
         assert!(jaro_similarity("hello", "hello") > 0.9);
@@ -160,6 +1028,293 @@ mod tests {
         assert!((token_similarity("file_name_test", "file_name_prod") - 0.5).abs() < 0.1);
     }
 
+    #[test]
+    fn test_calculate_combined_similarity() {
+        let algorithms = [Algorithm::Levenshtein, Algorithm::Token];
+        let lev = calculate_similarity("report_v1.pdf", "report_v2.pdf", &Algorithm::Levenshtein, false);
+        let token = calculate_similarity("report_v1.pdf", "report_v2.pdf", &Algorithm::Token, false);
+        
+        let weights = ComponentWeights::default();
+        let all = calculate_combined_similarity("report_v1.pdf", "report_v2.pdf", &algorithms, &Require::All, &weights, false, false);
+        let any = calculate_combined_similarity("report_v1.pdf", "report_v2.pdf", &algorithms, &Require::Any, &weights, false, false);
+        
+        assert!((all - lev.min(token)).abs() < f64::EPSILON);
+        assert!((any - lev.max(token)).abs() < f64::EPSILON);
+        assert!((calculate_combined_similarity("a", "a", &[], &Require::Any, &weights, false, false) - 0.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_parse_component_weights() {
+        let weights = ComponentWeights::parse("levenshtein=0.5, jaro=0.3,token=0.2").unwrap();
+        assert_eq!(
+            weights.components(),
+            [(Algorithm::Levenshtein, 0.5), (Algorithm::Jaro, 0.3), (Algorithm::Token, 0.2)]
+        );
+        assert_eq!(weights.to_string(), "levenshtein=0.5,jaro=0.3,token=0.2");
+        assert_eq!(ComponentWeights::parse("lev=0.7,token-sort=0.3").unwrap().components()[1].0, Algorithm::TokenSort);
+        // Within rounding of 1.0, and scaled to sum to 1.0
+        assert!(ComponentWeights::parse("jaro=0.333,token=0.333,lcs=0.333").is_ok());
+        let over = ComponentWeights::parse("levenshtein=0.51,jaro=0.5").unwrap();
+        assert_eq!(calculate_similarity("report.pdf", "report.pdf", &over, false), 1.0);
+        let identical = calculate_combined_similarity("report.pdf", "report.pdf", &[Algorithm::Weighted], &Require::All, &over, false, false);
+        assert_eq!(identical, 1.0);
+
+        let error = ComponentWeights::parse("levenshtein=0.5,cosine=0.5").unwrap_err().to_string();
+        assert!(error.contains("Unknown algorithm 'cosine'"), "{}", error);
+        assert!(error.contains("token-sort"), "{}", error);
+        let error = ComponentWeights::parse("levenshtein=0.5,jaro=0.3").unwrap_err().to_string();
+        assert!(error.contains("must sum to 1.0"), "{}", error);
+        assert!(ComponentWeights::parse("").is_err());
+        assert!(ComponentWeights::parse("levenshtein").is_err());
+        assert!(ComponentWeights::parse("levenshtein=half,jaro=0.5").is_err());
+        assert!(ComponentWeights::parse("levenshtein=1.5,jaro=-0.5").is_err());
+        assert!(ComponentWeights::parse("jaro=0.5,jaro=0.5").is_err());
+        assert!(ComponentWeights::parse("weighted=1.0").is_err());
+    }
+
+    #[test]
+    fn test_weighted_similarity_is_weighted_sum() {
+        let (s1, s2) = ("Report_Final_v1.pdf", "report-final-v2.pdf");
+        let score = |algorithm| calculate_similarity(s1, s2, &algorithm, false);
+        let expected = 0.5 * score(Algorithm::Levenshtein) + 0.3 * score(Algorithm::Jaro) + 0.2 * score(Algorithm::Token);
+
+        let weights = ComponentWeights::parse("levenshtein=0.5,jaro=0.3,token=0.2").unwrap();
+        let weighted = calculate_combined_similarity(s1, s2, &[Algorithm::Weighted], &Require::All, &weights, false, false);
+        assert!((weighted - expected).abs() < 1e-9);
+        assert!((calculate_similarity(s1, s2, &Algorithm::Weighted, false) - expected).abs() < 1e-9);
+
+        let cache = SimilarityCache::new();
+        let cached = cache.combined_similarity(s1, s2, &[Algorithm::Weighted], &Require::All, &weights, false, false);
+        assert!((cached - expected).abs() < 1e-9);
+        assert_eq!(cache.computed(), 3);
+
+        let dice_only = ComponentWeights::parse("dice=1").unwrap();
+        let dice = calculate_combined_similarity(s1, s2, &[Algorithm::Weighted], &Require::All, &dice_only, false, false);
+        assert!((dice - score(Algorithm::Dice)).abs() < 1e-9);
+        assert!((calculate_similarity(s1, s2, &dice_only, false) - score(Algorithm::Dice)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_calibrate_score() {
+        assert!((calibrate_score(&Algorithm::Jaro, 0.5) - 0.0).abs() < f64::EPSILON);
+        assert!((calibrate_score(&Algorithm::Jaro, 0.75) - 0.5).abs() < 1e-9);
+        assert!((calibrate_score(&Algorithm::Jaro, 0.3) - 0.0).abs() < f64::EPSILON);
+        assert!((calibrate_score(&Algorithm::Token, 0.4) - 0.4).abs() < f64::EPSILON);
+        for algorithm in Algorithm::value_variants() {
+            assert!((calibrate_score(algorithm, 1.0) - 1.0).abs() < f64::EPSILON, "{:?}", algorithm);
+        }
+    }
+
+    #[test]
+    fn test_calibrated_unrelated_scores_are_comparable() {
+        let names = [
+            "invoice_march.pdf",
+            "holiday_beach.jpg",
+            "meeting_notes.docx",
+            "budget_2023.xlsx",
+            "project_plan.txt",
+            "tax_return.pdf",
+            "song.mp3",
+            "thesis_draft.doc",
+        ];
+        let weights = ComponentWeights::default();
+        let mean = |algorithm: &Algorithm, calibrate: bool| {
+            let mut scores = Vec::new();
+            for (i, a) in names.iter().enumerate() {
+                for b in &names[i + 1..] {
+                    let algorithms = std::slice::from_ref(algorithm);
+                    scores.push(calculate_combined_similarity(a, b, algorithms, &Require::All, &weights, false, calibrate));
+                }
+            }
+            scores.iter().sum::<f64>() / scores.len() as f64
+        };
+        
+        let spread = |calibrate: bool| {
+            let means: Vec<f64> = Algorithm::value_variants().iter().map(|algorithm| mean(algorithm, calibrate)).collect();
+            means.iter().cloned().fold(f64::MIN, f64::max) - means.iter().cloned().fold(f64::MAX, f64::min)
+        };
+        // Raw Jaro-Winkler sits around 0.5 for unrelated names, token near 0
+        assert!(spread(false) > 0.4, "{}", spread(false));
+        assert!(spread(true) < 0.1, "{}", spread(true));
+        for algorithm in Algorithm::value_variants() {
+            assert!(mean(algorithm, true) < 0.1, "{:?}: {}", algorithm, mean(algorithm, true));
+        }
+    }
+
+    #[test]
+    fn test_hamming_similarity() {
+        // One of 16 positions differs
+        assert!((hamming_similarity("log_20240101.txt", "log_20240102.txt") - 15.0 / 16.0).abs() < 1e-9);
+        assert!((hamming_similarity("log_20240101.txt", "log_20241231.txt") - 13.0 / 16.0).abs() < 1e-9);
+        // Positionwise, so a shift costs every later position
+        assert!((hamming_similarity("abcd", "bcda") - 0.0).abs() < f64::EPSILON);
+        assert!((hamming_similarity("", "") - 1.0).abs() < f64::EPSILON);
+        assert!(
+            (calculate_similarity("LOG_20240101.txt", "log_20240102.TXT", &Algorithm::Hamming, false) - 15.0 / 16.0).abs()
+                < 1e-9
+        );
+    }
+
+    #[test]
+    fn test_hamming_similarity_falls_back_to_levenshtein() {
+        for (a, b) in [("log_1.txt", "log_10.txt"), ("report.pdf", "report_v2.pdf"), ("abc", "")] {
+            assert!((hamming_similarity(a, b) - levenshtein_similarity(a, b)).abs() < f64::EPSILON, "{} / {}", a, b);
+        }
+        // Characters, not bytes, decide whether lengths match
+        assert!((hamming_similarity("café_1", "café_2") - 5.0 / 6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_partial_similarity() {
+        // The shorter name appears whole inside the longer one
+        assert!((partial_similarity("2024_annual_report", "annual_report") - 1.0).abs() < f64::EPSILON);
+        assert!(calculate_similarity("2024_Annual_Report.pdf", "annual_report.pdf", &Algorithm::Partial, false) > 0.99);
+        // Levenshtein alone penalizes the prefix
+        assert!(levenshtein_similarity("2024_annual_report", "annual_report") < 0.75);
+        // One substituted character in the best window: 11 / 12
+        assert!((partial_similarity("2024_annual_repert", "annual_report") - 11.0 / 12.0).abs() < 1e-9);
+        assert!(partial_similarity("holiday_beach.jpg", "invoice.pdf") < 0.5);
+        
+        assert!((partial_similarity("", "") - 1.0).abs() < f64::EPSILON);
+        assert!((partial_similarity("abc", "") - 0.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_partial_similarity_caps_windows() {
+        // Far more windows than the cap; sampling still finds the match at
+        // either end and stays in range elsewhere
+        let long = format!("report{}", "x".repeat(10_000));
+        assert!((partial_similarity(&long, "report") - 1.0).abs() < f64::EPSILON);
+        let long = format!("{}report", "x".repeat(10_000));
+        assert!((partial_similarity(&long, "report") - 1.0).abs() < f64::EPSILON);
+        let score = partial_similarity(&"ab".repeat(5_000), "report");
+        assert!((0.0..=1.0).contains(&score));
+    }
+
+    #[test]
+    fn test_lcs_similarity() {
+        // "2024reportfinal" keeps all 10 characters of "2024report": 20 / 25
+        assert!((lcs_similarity("2024_report_final", "2024_report") - 0.8).abs() < 1e-9);
+        // Non-contiguous subsequence "ace": 6 / 8
+        assert!((lcs_similarity("abcde", "ace") - 0.75).abs() < 1e-9);
+        // "abcbdab" / "bdcaba" share "bcba": 8 / 13
+        assert!((lcs_similarity("abcbdab", "bdcaba") - 8.0 / 13.0).abs() < 1e-9);
+        // Extensions and punctuation are ignored
+        assert!((lcs_similarity("Photo-01.JPG", "photo01.png") - 1.0).abs() < f64::EPSILON);
+        assert!((lcs_similarity("abc", "xyz") - 0.0).abs() < f64::EPSILON);
+        
+        assert!((lcs_similarity("", "") - 1.0).abs() < f64::EPSILON);
+        assert!((lcs_similarity("abc", "") - 0.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_token_sort_similarity() {
+        assert!((token_sort_similarity("final_report_2024", "2024_report_final") - 1.0).abs() < f64::EPSILON);
+        assert!(token_sort_similarity("final report 2024.pdf", "2024-report-final.pdf") > 0.99);
+        // Word order costs plain Levenshtein dearly
+        assert!(levenshtein_similarity("final_report_2024", "2024_report_final") < 0.5);
+        // A changed token still counts against the score
+        let score = token_sort_similarity("final_report_2024", "2023_report_final");
+        assert!(score > 0.9 && score < 1.0);
+        
+        assert!(token_sort_similarity("holiday_beach.jpg", "invoice_march.pdf") < 0.3);
+        assert!((token_sort_similarity("", "") - 1.0).abs() < f64::EPSILON);
+        assert_eq!(
+            calculate_similarity("Final_Report_2024", "2024_report_FINAL", &Algorithm::TokenSort, false),
+            1.0
+        );
+    }
+
+    #[test]
+    fn test_dice_similarity() {
+        // ni ig gh ht / na ac ch ht share one bigram: 2 * 1 / (4 + 4)
+        assert!((dice_similarity("night", "nacht") - 0.25).abs() < f64::EPSILON);
+        assert!((dice_similarity("hello", "hello") - 1.0).abs() < f64::EPSILON);
+        assert!((dice_similarity("abc", "xyz") - 0.0).abs() < f64::EPSILON);
+        
+        // Repeated bigrams count as a multiset: aa aa / aa gives 2 * 1 / 3
+        assert!((dice_similarity("aaa", "aa") - 2.0 / 3.0).abs() < 1e-9);
+        
+        // Strings without bigrams
+        assert!((dice_similarity("", "") - 1.0).abs() < f64::EPSILON);
+        assert!((dice_similarity("a", "a") - 1.0).abs() < f64::EPSILON);
+        assert!((dice_similarity("a", "b") - 0.0).abs() < f64::EPSILON);
+        assert!((dice_similarity("a", "ab") - 0.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_auto_weights_shift_with_length() {
+        let weight = |s1: &str, s2: &str, algorithm: Algorithm| {
+            auto_weights(s1, s2, &AutoWeights::default())
+                .into_iter()
+                .find(|(a, _)| *a == algorithm)
+                .unwrap()
+                .1
+        };
+        
+        for (short, long) in [("a.txt", "quarterly_financial_statement_final.pdf"), ("ab", "quarterlystatementfinalversion")] {
+            assert!(weight(short, short, Algorithm::Token) < weight(long, long, Algorithm::Token));
+            assert!(weight(short, short, Algorithm::Jaro) > weight(long, long, Algorithm::Jaro));
+        }
+        // Delimiters still favor tokens at equal length
+        assert!(weight("ab_cd", "ab_cd", Algorithm::Token) > weight("abxcd", "abxcd", Algorithm::Token));
+        
+        // At the crossover a plain name sits a quarter of the way toward tokens
+        let at_crossover = "a".repeat(16);
+        assert!((weight(&at_crossover, &at_crossover, Algorithm::Token) - 0.3).abs() < 1e-9);
+        
+        // A shorter crossover treats the same names as long
+        let tuned = auto_weights("report.pdf", "report2.pdf", &AutoWeights { crossover_length: 4.0, ..Default::default() })[0].1;
+        assert!(tuned > weight("report.pdf", "report2.pdf", Algorithm::Token));
+    }
+
+    #[test]
+    fn test_auto_similarity_stays_in_range() {
+        let pairs = [
+            ("", ""),
+            ("a", ""),
+            ("x", "y"),
+            ("report.pdf", "report.pdf"),
+            ("IMG_0001.jpg", "IMG_0002.jpg"),
+            ("quarterly_financial_statement_final.pdf", "statement.txt"),
+        ];
+        for crossover in [0.0, 1.0, DEFAULT_AUTO_CROSSOVER_LENGTH, 1000.0] {
+            for (s1, s2) in pairs {
+                let tuned = AutoWeights { crossover_length: crossover, ..Default::default() };
+                let weights = auto_weights(s1, s2, &tuned);
+                assert!((weights.iter().map(|(_, w)| w).sum::<f64>() - 1.0).abs() < 1e-9);
+                let score = auto_similarity(s1, s2, &tuned);
+                assert!((0.0..=1.0 + 1e-9).contains(&score), "{} vs {} scored {}", s1, s2, score);
+            }
+        }
+        assert!((auto_similarity("report.pdf", "report.pdf", &AutoWeights::default()) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_custom_auto_weights() {
+        let pairs = [("IMG_0001.jpg", "IMG_0002.jpg"), ("photograph", "photgraph"), ("march_invoice", "invoice_march")];
+        let only = |weights: AlgorithmWeights| AutoWeights { plain: weights, delimited: weights, ..Default::default() };
+        let token_only = only(AlgorithmWeights { token: 1.0, jaro: 0.0, levenshtein: 0.0 });
+        let levenshtein_only = only(AlgorithmWeights { token: 0.0, jaro: 0.0, levenshtein: 1.0 });
+        let even = only(AlgorithmWeights { token: 0.5, jaro: 0.0, levenshtein: 0.5 });
+        
+        for (s1, s2) in pairs {
+            let token = calculate_similarity(s1, s2, &Algorithm::Token, false);
+            let levenshtein = calculate_similarity(s1, s2, &Algorithm::Levenshtein, false);
+            let with = |weights: &AutoWeights| calculate_similarity_with(s1, s2, &Algorithm::Auto, false, weights);
+            
+            assert!((with(&token_only) - token).abs() < 1e-9);
+            assert!((with(&levenshtein_only) - levenshtein).abs() < 1e-9);
+            assert!((with(&even) - (token + levenshtein) / 2.0).abs() < 1e-9);
+            // The defaults are what plain `calculate_similarity` uses
+            assert_eq!(with(&AutoWeights::default()), calculate_similarity(s1, s2, &Algorithm::Auto, false));
+        }
+        
+        // Weights only apply to auto
+        let jaro = calculate_similarity_with("abc", "abd", &Algorithm::Jaro, false, &token_only);
+        assert_eq!(jaro, calculate_similarity("abc", "abd", &Algorithm::Jaro, false));
+    }
+
     #[test]
     fn test_tokenize() {
         assert_eq!(tokenize("file_name.txt"), vec!["file", "name", "txt"]);
@@ -200,4 +1355,103 @@ mod tests {
         assert!((substring_similarity("", "") - 1.0).abs() < f64::EPSILON);
         assert!((substring_similarity("test", "") - 0.0).abs() < f64::EPSILON);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_metaphone() {
+        assert_eq!(metaphone("smith"), "SM0");
+        assert_eq!(metaphone("smyth"), "SM0");
+        assert_eq!(metaphone("johnson"), metaphone("jonson"));
+        assert_eq!(metaphone("knight"), "NT");
+        assert_eq!(metaphone("phone"), "FN");
+        assert_eq!(metaphone("cat"), "KT");
+        assert_eq!(metaphone("dog"), "TK");
+    }
+
+    #[test]
+    fn test_phonetic_similarity() {
+        assert!((phonetic_similarity("smith", "smyth") - 1.0).abs() < f64::EPSILON);
+        assert!((phonetic_similarity("cat", "dog") - 0.0).abs() < f64::EPSILON);
+        assert!((phonetic_similarity("jonson_invoice.pdf", "johnson_invoice.pdf") - 1.0).abs() < f64::EPSILON);
+        
+        // Non-alphabetic tokens must match verbatim
+        assert!((phonetic_similarity("smith_2023", "smyth_2024") - 1.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_affixes_strip() {
+        let affixes = Affixes::new(Some(r"\d{8}_"), Some(r"_user\d+")).unwrap();
+        assert_eq!(affixes.strip("20240101_report.pdf"), "report.pdf");
+        assert_eq!(affixes.strip("report_user42.pdf"), "report.pdf");
+        assert_eq!(affixes.strip("exports/20240101_report_user7.pdf"), "exports/report.pdf");
+        // Matches are anchored, and a stem is never stripped to nothing
+        assert_eq!(affixes.strip("report_20240101_.pdf"), "report_20240101_.pdf");
+        assert_eq!(affixes.strip("20240101_.pdf"), "20240101_.pdf");
+        
+        assert_eq!(Affixes::default().strip("20240101_report.pdf"), "20240101_report.pdf");
+        assert!(Affixes::new(Some("("), None).is_err());
+    }
+
+    #[test]
+    fn test_strip_copy_suffixes() {
+        let affixes = Affixes::default().strip_copy_suffixes(true);
+        for copy in ["report (1).pdf", "report(12).pdf", "report - Copy.pdf", "report copy.pdf", "report Copy 2.pdf", "report_1.pdf"] {
+            assert_eq!(affixes.strip(copy), "report.pdf", "{}", copy);
+        }
+        assert_eq!(affixes.strip("report - Copy (2).pdf"), "report.pdf");
+        assert_eq!(affixes.strip("scans/report (1)"), "scans/report");
+        assert_eq!(
+            calculate_similarity(&affixes.strip("report (1).pdf"), &affixes.strip("report copy.pdf"), &Algorithm::Levenshtein, false),
+            1.0
+        );
+
+        // Version numbers, longer numbers and markers mid-name are not copies
+        for name in ["report_v1.pdf", "report_2024.pdf", "report (1) final.pdf", "photocopy.pdf", "(1).pdf"] {
+            assert_eq!(affixes.strip(name), name);
+        }
+        assert_eq!(Affixes::default().strip("report (1).pdf"), "report (1).pdf");
+
+        let affixes = Affixes::new(None, Some(r"_user\d+")).unwrap().strip_copy_suffixes(true);
+        assert_eq!(affixes.strip("report_user7 (1).pdf"), "report.pdf");
+    }
+
+    #[test]
+    fn test_normalize_unicode() {
+        let precomposed = "caf\u{e9}.txt";
+        let decomposed = "cafe\u{301}.txt";
+        assert_ne!(Affixes::default().strip(precomposed), Affixes::default().strip(decomposed));
+        assert!(calculate_similarity(precomposed, decomposed, &Algorithm::Levenshtein, false) < 1.0);
+        
+        let affixes = Affixes::default().normalize_unicode(true, false);
+        assert_eq!(affixes.strip(precomposed), affixes.strip(decomposed));
+        assert_eq!(affixes.strip(decomposed), precomposed);
+        assert_eq!(affixes.strip("Stra\u{df}e.pdf"), "strasse.pdf");
+        assert_eq!(affixes.strip("STRASSE.pdf"), "strasse.pdf");
+        // Full-width compatibility characters
+        assert_eq!(affixes.strip("\u{ff32}\u{ff45}\u{ff50}\u{ff4f}\u{ff52}\u{ff54}.pdf"), "report.pdf");
+        // Locale-independent: the dotless i stays its own letter
+        assert_ne!(affixes.strip("\u{131}zmir.txt"), affixes.strip("izmir.txt"));
+        
+        // Case-sensitive comparisons still normalize accents but keep case
+        let affixes = Affixes::default().normalize_unicode(true, true);
+        assert_eq!(affixes.strip("Cafe\u{301}.txt"), "Caf\u{e9}.txt");
+        
+        let affixes = Affixes::new(Some(r"\d{8}_"), None).unwrap().normalize_unicode(true, false);
+        assert_eq!(affixes.strip("20240101_Cafe\u{301}.txt"), "caf\u{e9}.txt");
+    }
+
+    #[test]
+    fn test_compare_modes() {
+        let affixes = Affixes::default().compare(CompareMode::Basename);
+        assert_eq!(affixes.strip("2023/report.pdf"), "report.pdf");
+        assert_eq!(affixes.strip("C:\\docs\\report.pdf"), "report.pdf");
+        assert_eq!(Affixes::default().strip("2023/report.pdf"), "2023/report.pdf");
+        
+        let affixes = Affixes::new(Some(r"\d{8}_"), None).unwrap().compare(CompareMode::Basename);
+        assert_eq!(affixes.strip("2024/20240101_report.pdf"), "report.pdf");
+        
+        let affixes = Affixes::new(Some(r"\d{8}_"), None).unwrap().compare(CompareMode::Stem);
+        assert_eq!(affixes.strip("2024/20240101_report.pdf"), "report");
+        assert_eq!(affixes.strip("/backup/archive.tar.gz"), "archive.tar");
+        assert_eq!(affixes.strip("/backup/.bashrc"), ".bashrc");
+    }
+}
@@ -0,0 +1,239 @@
+use crate::cli::OutputFormat;
+use crate::file_info::FileInfo;
+use crate::input::{normalize_root, FileDiscovery};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// How closely two directory trees match, file by file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreeComparison {
+    pub dir_a: String,
+    pub dir_b: String,
+    /// Jaccard similarity of the two trees' files (0.0-1.0): matching paths
+    /// divided by all distinct paths
+    pub similarity: f64,
+    /// Relative paths present, with the same content when compared, on both sides
+    pub matching: usize,
+    /// Relative paths present on both sides whose content differs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changed: Vec<String>,
+    pub only_in_a: Vec<String>,
+    pub only_in_b: Vec<String>,
+}
+
+/// Compares the files below `dir_a` and `dir_b` by their path relative to
+/// each root. With `compare_content`, files present on both sides must also
+/// have the same SHA-256 hash to match; otherwise they are listed as changed.
+pub fn compare_trees(
+    dir_a: &Path,
+    dir_b: &Path,
+    discovery: &FileDiscovery,
+    compare_content: bool,
+) -> Result<TreeComparison> {
+    let files_a = relative_files(dir_a, discovery)?;
+    let files_b = relative_files(dir_b, discovery)?;
+
+    let mut matching = 0;
+    let mut changed = Vec::new();
+    let mut only_in_a = Vec::new();
+    for (relative, path_a) in &files_a {
+        let Some(path_b) = files_b.get(relative) else {
+            only_in_a.push(relative.clone());
+            continue;
+        };
+        if compare_content && content_hash(path_a)? != content_hash(path_b)? {
+            changed.push(relative.clone());
+        } else {
+            matching += 1;
+        }
+    }
+    let only_in_b: Vec<String> = files_b
+        .keys()
+        .filter(|relative| !files_a.contains_key(*relative))
+        .cloned()
+        .collect();
+
+    let union = matching + changed.len() + only_in_a.len() + only_in_b.len();
+    let similarity = if union == 0 { 1.0 } else { matching as f64 / union as f64 };
+
+    Ok(TreeComparison {
+        dir_a: dir_a.display().to_string(),
+        dir_b: dir_b.display().to_string(),
+        similarity,
+        matching,
+        changed,
+        only_in_a,
+        only_in_b,
+    })
+}
+
+/// Every discovered file below `root`, keyed by its `/`-separated path
+/// relative to `root` so both trees share keys on any platform.
+fn relative_files(root: &Path, discovery: &FileDiscovery) -> Result<BTreeMap<String, PathBuf>> {
+    let paths = discovery.discover_paths(root)?;
+    let absolute_root = normalize_root(root)?;
+
+    Ok(paths
+        .into_iter()
+        .map(|path| {
+            let relative = path.strip_prefix(&absolute_root).unwrap_or(&path);
+            let key = relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            (key, path)
+        })
+        .collect())
+}
+
+fn content_hash(path: &Path) -> Result<String> {
+    FileInfo::from_path(path)
+        .and_then(|mut file| file.calculate_hash())
+        .with_context(|| format!("Failed to hash {}", path.display()))
+}
+
+pub fn format_tree_comparison<W: Write>(
+    comparison: &TreeComparison,
+    format: &OutputFormat,
+    writer: &mut W,
+) -> Result<()> {
+    match format {
+        OutputFormat::Json => writeln!(writer, "{}", serde_json::to_string_pretty(comparison)?)?,
+        OutputFormat::Jsonl => writeln!(writer, "{}", serde_json::to_string(comparison)?)?,
+        OutputFormat::Csv | OutputFormat::Tsv => {
+            let mut csv_writer = format.table_writer(writer);
+            csv_writer.write_record(["path", "status"])?;
+            let rows = [
+                (&comparison.changed, "changed"),
+                (&comparison.only_in_a, "only_in_a"),
+                (&comparison.only_in_b, "only_in_b"),
+            ];
+            for (paths, status) in rows {
+                for path in paths {
+                    csv_writer.write_record([path.as_str(), status])?;
+                }
+            }
+            csv_writer.flush()?;
+        }
+        OutputFormat::Html => {
+            anyhow::bail!("HTML output is not supported by tree-compare; use text, json, jsonl, csv or tsv");
+        }
+        OutputFormat::Text | OutputFormat::Table => {
+            let total = comparison.matching
+                + comparison.changed.len()
+                + comparison.only_in_a.len()
+                + comparison.only_in_b.len();
+            writeln!(writer, "Comparing {} with {}", comparison.dir_a, comparison.dir_b)?;
+            writeln!(
+                writer,
+                "Similarity: {:.1}% ({} of {} files match)",
+                comparison.similarity * 100.0,
+                comparison.matching,
+                total
+            )?;
+            let sections = [
+                ("Changed".to_string(), &comparison.changed),
+                (format!("Only in {}", comparison.dir_a), &comparison.only_in_a),
+                (format!("Only in {}", comparison.dir_b), &comparison.only_in_b),
+            ];
+            for (heading, paths) in sections.iter().filter(|(_, paths)| !paths.is_empty()) {
+                writeln!(writer, "\n{} ({}):", heading, paths.len())?;
+                for path in paths.iter() {
+                    writeln!(writer, "  {}", path)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn write_tree(root: &Path, files: &[(&str, &str)]) {
+        for (relative, content) in files {
+            let path = root.join(relative);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+    }
+
+    fn backups() -> (TempDir, PathBuf, PathBuf) {
+        let temp_dir = TempDir::new().unwrap();
+        let a = temp_dir.path().join("backup_a");
+        let b = temp_dir.path().join("backup_b");
+        write_tree(&a, &[
+            ("notes.txt", "notes"),
+            ("photos/beach.jpg", "beach"),
+            ("photos/city.jpg", "city"),
+            ("docs/report.pdf", "report v1"),
+            ("docs/old.pdf", "old"),
+        ]);
+        write_tree(&b, &[
+            ("notes.txt", "notes"),
+            ("photos/beach.jpg", "beach"),
+            ("photos/city.jpg", "city"),
+            ("docs/report.pdf", "report v2"),
+            ("docs/new.pdf", "new"),
+        ]);
+        (temp_dir, a, b)
+    }
+
+    #[test]
+    fn test_compare_trees_by_path() {
+        let (_temp_dir, a, b) = backups();
+
+        let comparison = compare_trees(&a, &b, &FileDiscovery::new(), false).unwrap();
+        assert_eq!(comparison.matching, 4);
+        assert!(comparison.changed.is_empty());
+        assert_eq!(comparison.only_in_a, vec!["docs/old.pdf"]);
+        assert_eq!(comparison.only_in_b, vec!["docs/new.pdf"]);
+        assert!((comparison.similarity - 4.0 / 6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_compare_trees_by_content() {
+        let (_temp_dir, a, b) = backups();
+
+        let comparison = compare_trees(&a, &b, &FileDiscovery::new(), true).unwrap();
+        assert_eq!(comparison.matching, 3);
+        assert_eq!(comparison.changed, vec!["docs/report.pdf"]);
+        assert!((comparison.similarity - 0.5).abs() < 1e-9);
+
+        let mut output = Vec::new();
+        format_tree_comparison(&comparison, &OutputFormat::Text, &mut output).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        assert!(text.contains("Similarity: 50.0% (3 of 6 files match)"));
+        assert!(text.contains("Changed (1):\n  docs/report.pdf"));
+
+        let mut output = Vec::new();
+        format_tree_comparison(&comparison, &OutputFormat::Csv, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "path,status\ndocs/report.pdf,changed\ndocs/old.pdf,only_in_a\ndocs/new.pdf,only_in_b\n"
+        );
+    }
+
+    #[test]
+    fn test_identical_and_empty_trees_match_fully() {
+        let temp_dir = TempDir::new().unwrap();
+        let a = temp_dir.path().join("a");
+        let b = temp_dir.path().join("b");
+        fs::create_dir_all(&a).unwrap();
+        fs::create_dir_all(&b).unwrap();
+        assert_eq!(compare_trees(&a, &b, &FileDiscovery::new(), true).unwrap().similarity, 1.0);
+
+        write_tree(&a, &[("x/y.txt", "same")]);
+        write_tree(&b, &[("x/y.txt", "same")]);
+        let comparison = compare_trees(&a, &b, &FileDiscovery::new(), true).unwrap();
+        assert_eq!(comparison.similarity, 1.0);
+        assert_eq!(comparison.matching, 1);
+    }
+}
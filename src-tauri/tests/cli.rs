@@ -0,0 +1,168 @@
+use std::process::{Command, Output};
+
+fn run_cli(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_similarity-checker-cli"))
+        .args(args)
+        .output()
+        .expect("failed to run similarity-checker-cli")
+}
+
+#[test]
+fn test_quiet_suppresses_stderr() {
+    // A single file is below the minimum group size, which normally warns
+    let output = run_cli(&["report_v1.pdf"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Warning:"));
+
+    let output = run_cli(&["--quiet", "report_v1.pdf"]);
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Total files: 1"));
+
+    // Warnings raised while collecting files are silenced too
+    let output = run_cli(&["nomatch_*.txt", "a.txt"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No files match pattern nomatch_*.txt"));
+    let output = run_cli(&["--quiet", "nomatch_*.txt", "a.txt"]);
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_quiet_hides_skipped_paths() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(temp_dir.path().join("report_v1.pdf"), "data").unwrap();
+    std::fs::write(temp_dir.path().join("report_v2.pdf"), "data").unwrap();
+    std::os::unix::fs::symlink(temp_dir.path().join("missing.pdf"), temp_dir.path().join("dangling.pdf")).unwrap();
+    let dir = temp_dir.path().to_str().unwrap();
+
+    let output = run_cli(&["--discover", dir, "--follow-symlinks"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("dangling.pdf"));
+
+    let output = run_cli(&["--quiet", "--discover", dir, "--follow-symlinks"]);
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn test_quiet_hides_results_written_message() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let report = temp_dir.path().join("report.json");
+
+    let output = run_cli(&["-q", "--format", "json", "--output", report.to_str().unwrap(), "report_v1.pdf", "report_v2.pdf"]);
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(output.stdout.is_empty());
+    let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&report).unwrap()).unwrap();
+    assert_eq!(json["summary"]["groups_found"], 1);
+}
+
+#[test]
+fn test_append_collects_runs() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let report = temp_dir.path().join("report.jsonl");
+    let report = report.to_str().unwrap();
+
+    for files in [["report_v1.pdf", "report_v2.pdf"], ["holiday_1.jpg", "holiday_2.jpg"]] {
+        let output = run_cli(&[&["--append", "-o", report, "-f", "jsonl", "-a", "lev"], &files[..]].concat());
+        assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+        assert!(String::from_utf8_lossy(&output.stderr).contains("Results appended to"));
+    }
+    let contents = std::fs::read_to_string(report).unwrap();
+    assert!(contents.contains("report_v2.pdf"));
+    assert!(contents.contains("holiday_2.jpg"));
+    assert_eq!(contents.lines().filter(|line| line.contains(r#""type":"summary""#)).count(), 2);
+
+    // Without --append the file is overwritten
+    let output = run_cli(&["-o", report, "-f", "jsonl", "-a", "lev", "report_v1.pdf", "report_v2.pdf"]);
+    assert!(output.status.success());
+    assert!(!std::fs::read_to_string(report).unwrap().contains("holiday_2.jpg"));
+
+    // Pretty JSON can be appended, but not read back as one document
+    let output = run_cli(&["--append", "-o", report, "-f", "json", "report_v1.pdf", "report_v2.pdf"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Warning: --append with --format json"));
+}
+
+#[test]
+fn test_detect_identical_groups_content_then_names() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    for (name, content) in [("holiday.jpg", "same bytes"), ("IMG_0042.jpg", "same bytes"), ("report_v1.pdf", "first"), ("report_v2.pdf", "second")] {
+        std::fs::write(temp_dir.path().join(name), content).unwrap();
+    }
+    let root = temp_dir.path().to_str().unwrap();
+
+    let output = run_cli(&["--detect-identical", "--discover", root, "--full-paths", "-a", "lev", "-f", "json"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let groups = json["groups"].as_array().unwrap();
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[0]["match_reason"], "identical_content");
+    assert!(groups[0]["files"][0].as_str().unwrap().ends_with("IMG_0042.jpg"));
+    assert!(groups[0]["files"][1].as_str().unwrap().ends_with("holiday.jpg"));
+    assert_eq!(groups[1]["match_reason"], "name_similarity");
+    assert!(groups[1]["files"][1].as_str().unwrap().ends_with("report_v2.pdf"));
+    assert_eq!(json["summary"]["total_files"], 4);
+
+    // File names alone can't be read
+    let output = run_cli(&["--detect-identical", "--discover", root]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("requires --full-paths"));
+}
+
+#[test]
+fn test_fail_on_duplicates_exit_code() {
+    let duplicates = ["-a", "lev", "-t", "80", "report_v1.pdf", "report_v2.pdf", "holiday.jpg"];
+
+    let output = run_cli(&duplicates);
+    assert_eq!(output.status.code(), Some(0));
+
+    let output = run_cli(&[&["--fail-on-duplicates"], &duplicates[..]].concat());
+    assert_eq!(output.status.code(), Some(2));
+    // The report is still printed
+    assert!(String::from_utf8_lossy(&output.stdout).contains("report_v2.pdf"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Found 1 group(s)"));
+
+    let output = run_cli(&[&["--fail-on-duplicates", "--fail-threshold", "2"], &duplicates[..]].concat());
+    assert_eq!(output.status.code(), Some(0));
+
+    let output = run_cli(&["--fail-on-duplicates", "-a", "lev", "-t", "80", "report_v1.pdf", "holiday.jpg"]);
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn test_limit_keeps_summary_total() {
+    let files = [
+        "report_v1.pdf", "report_v2.pdf", "invoice_v1.pdf", "invoice_v2.pdf", "budget_v1.xlsx",
+        "budget_v2.xlsx", "notes_v1.txt", "notes_v2.txt", "photo_v1.jpg", "photo_v2.jpg",
+    ];
+    let args = [&["-a", "lev", "-t", "80", "--format", "json"], &files[..]].concat();
+
+    let output = run_cli(&args);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["groups"].as_array().unwrap().len(), 5);
+
+    let output = run_cli(&[&["--limit", "2"], &args[..]].concat());
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["groups"].as_array().unwrap().len(), 2);
+    assert_eq!(json["summary"]["groups_found"], 5);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Showing the first 2 of 5 groups"));
+}
+
+#[test]
+fn test_interactive_requires_terminal() {
+    // Output captured by the test is not a terminal, so nothing is prompted or trashed
+    let output = run_cli(&["--interactive", "-a", "lev", "report_v1.pdf", "report_v2.pdf"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--interactive requires a terminal"));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_quiet_still_reports_errors() {
+    let output = run_cli(&["--quiet", "--threshold", "0", "a.txt", "b.txt"]);
+    assert!(!output.status.success());
+    assert!(!output.stderr.is_empty());
+}
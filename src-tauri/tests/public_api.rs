@@ -0,0 +1,31 @@
+use similarity_checker_lib::{calculate_similarity, group_files, Algorithm, Group, GroupingResult, Summary};
+
+#[test]
+fn test_public_api_groups_similar_files() {
+    let files = vec![
+        "report_v1.pdf".to_string(),
+        "report_v2.pdf".to_string(),
+        "holiday.jpg".to_string(),
+    ];
+
+    let result: GroupingResult = group_files(files, 50, &Algorithm::Token, false, 2);
+
+    let groups: &Vec<Group> = &result.groups;
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].files, vec!["report_v1.pdf", "report_v2.pdf"]);
+    assert_eq!(result.ungrouped, vec!["holiday.jpg"]);
+
+    let summary: &Summary = &result.summary;
+    assert_eq!(summary.total_files, 3);
+    assert_eq!(summary.groups_found, 1);
+    assert_eq!(summary.ungrouped_files, 1);
+}
+
+#[test]
+fn test_public_api_calculate_similarity() {
+    let score = calculate_similarity("Report.pdf", "report.pdf", &Algorithm::Levenshtein, false);
+    assert!((score - 1.0).abs() < f64::EPSILON);
+
+    let score = calculate_similarity("Report.pdf", "report.pdf", &Algorithm::Levenshtein, true);
+    assert!(score < 1.0);
+}
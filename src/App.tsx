@@ -3,6 +3,7 @@ import { Button } from "@/components/ui/button";
 import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "@/components/ui/card";
 import { Input } from "@/components/ui/input";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { open } from "@tauri-apps/plugin-dialog";
 import { AlertTriangle, Brain, Calendar, CheckSquare, FileText, FolderOpen, HardDrive, Loader2, Search, Square, Trash2 } from "lucide-react";
 import { Spinner } from "@/components/ui/spinner";
@@ -15,20 +16,40 @@ interface FileInfo {
   last_modified: number;
   path: string;
   hash?: string;
+  detected_mime?: string | null;
+  inode?: [number, number] | null;
+  hard_link?: boolean;
+}
+
+type SimilarityType = "identical" | "content" | "name" | "empty" | "perceptual";
+
+interface FileMatch {
+  similarity_type: SimilarityType;
+  score: number;
 }
 
 interface SimilarGroup {
   id: string;
   files: FileInfo[];
-  similarity_type: "identical" | "content" | "name";
+  similarity_type: SimilarityType;
   similarity_score: number;
+  matches?: (FileMatch | null)[];
 }
 
+type ProgressEvent =
+  | { stage: "hashing"; current: number; total: number; file: string }
+  | { stage: "comparing"; current: number; total: number };
+
 interface SimilarityResult {
   groups: SimilarGroup[];
   ungrouped_files: FileInfo[];
 }
 
+interface DeleteResult {
+  deleted: string[];
+  failed: [string, string][];
+}
+
 function App() {
   const [folderPath, setFolderPath] = useState<string>("");
   const [result, setResult] = useState<SimilarityResult | null>(null);
@@ -77,34 +98,24 @@ function App() {
     setProcessedCount(0);
     setTotalFilesCount(0);
 
-    try {
-      // Simulate progress updates for demonstration
-      // In a real implementation, you'd need to modify the Rust backend to emit progress events
-      const progressInterval = setInterval(() => {
-        setProcessedCount(prev => {
-          const newCount = prev + Math.floor(Math.random() * 3) + 1;
-          if (newCount >= totalFilesCount && totalFilesCount > 0) {
-            return totalFilesCount;
-          }
-          return newCount;
-        });
-        
-        // Simulate current file being processed
-        const sampleFiles = [
-          "document.pdf", "image.jpg", "report.docx", "data.xlsx", "backup.zip",
-          "presentation.pptx", "config.json", "readme.txt", "photo.png", "archive.tar"
-        ];
-        setCurrentFile(sampleFiles[Math.floor(Math.random() * sampleFiles.length)]);
-      }, 200);
-      
-      // Set initial total (this would come from file discovery in real implementation)
-      setTimeout(() => setTotalFilesCount(Math.floor(Math.random() * 50) + 20), 100);
+    const unlisten = await listen<ProgressEvent>("analysis-progress", (event) => {
+      const progress = event.payload;
+      setProcessedCount(progress.current);
+      setTotalFilesCount(progress.total);
+      if (progress.stage === "hashing") {
+        setStatus("Hashing files...");
+        setCurrentFile(progress.file);
+      } else {
+        setStatus("Comparing files...");
+        setCurrentFile("");
+      }
+    });
 
+    try {
       const analysisResult: SimilarityResult = await invoke("analyze_folder", {
         folderPath,
       });
       
-      clearInterval(progressInterval);
       setResult(analysisResult);
       setStatus(`Found ${analysisResult.groups.length} similar groups`);
       setCurrentFile("");
@@ -112,10 +123,20 @@ function App() {
       setStatus(`Error analyzing folder: ${error}`);
       setCurrentFile("");
     } finally {
+      unlisten();
       setLoading(false);
     }
   }
 
+  async function cancelAnalysis() {
+    try {
+      await invoke("cancel_analysis");
+      setStatus("Cancelling...");
+    } catch (error) {
+      setStatus(`Error cancelling analysis: ${error}`);
+    }
+  }
+
   function toggleFileSelection(filePath: string) {
     const newSelected = new Set(selectedFiles);
     if (newSelected.has(filePath)) {
@@ -183,13 +204,13 @@ function App() {
     setStatus("Deleting files...");
 
     try {
-      const message: string = await invoke("delete_files", {
+      const deleteResult: DeleteResult = await invoke("delete_files", {
         filePaths: Array.from(selectedFiles),
       });
 
       // Update the current result by removing deleted files instead of re-analyzing
       if (result) {
-        const deletedFilePaths = new Set(selectedFiles);
+        const deletedFilePaths = new Set(deleteResult.deleted);
         const updatedGroups = result.groups
           .map(group => ({
             ...group,
@@ -207,7 +228,14 @@ function App() {
         });
       }
 
-      setStatus(message);
+      const failedMessages = deleteResult.failed.map(
+        ([path, error]) => `Failed to delete '${path}': ${error}`
+      );
+      setStatus(
+        failedMessages.length > 0
+          ? `Deleted ${deleteResult.deleted.length} file(s); some files could not be deleted: ${failedMessages.join(", ")}`
+          : `Successfully deleted ${deleteResult.deleted.length} file(s) to trash`
+      );
       setSelectedFiles(new Set());
     } catch (error) {
       setStatus(`Error deleting files: ${error}`);
@@ -243,6 +271,8 @@ function App() {
       case "identical": return "IDENTICAL";
       case "content": return "SIZE";
       case "name": return "NAME";
+      case "empty": return "EMPTY";
+      case "perceptual": return "LOOKS ALIKE";
       default: return type.toUpperCase();
     }
   }
@@ -296,6 +326,11 @@ function App() {
                 <Search className="h-4 w-4" />
                 {loading ? "Analyzing..." : "Analyze Files"}
               </Button>
+              {loading && (
+                <Button onClick={cancelAnalysis} variant="outline" className="flex items-center gap-2">
+                  Cancel
+                </Button>
+              )}
               {selectedFiles.size > 0 && (
                 <Button
                   onClick={deleteSelectedFiles}
@@ -469,7 +504,18 @@ function App() {
                             <FileText className="h-5 w-5 text-muted-foreground" />
                             <div className="flex-1 min-w-0">
                               <p className="font-medium text-sm truncate">{getFileName(file.path)}</p>
-                              <p className="text-xs text-muted-foreground">{file.file_type || 'Unknown'}</p>
+                              <p className="text-xs text-muted-foreground">
+                                {file.file_type || 'Unknown'}
+                                {group.matches?.[fileIndex] && (
+                                  <> • {getSimilarityTypeDisplay(group.matches[fileIndex]!.similarity_type)} {(group.matches[fileIndex]!.score * 100).toFixed(0)}%</>
+                                )}
+                              </p>
+                              {file.hard_link && (
+                                <p className="flex items-center gap-1 text-xs text-orange-700" title="Hard link to another file in this group: deleting it frees no space">
+                                  <AlertTriangle className="h-3 w-3" />
+                                  Hard link, not a separate copy
+                                </p>
+                              )}
                             </div>
                           </div>
                           <div className="flex flex-col items-end gap-1 text-xs text-muted-foreground">